@@ -1,4 +1,5 @@
 
 
+#[allow(clippy::module_inception)]
 pub mod lifecycle;
 pub mod construct;
\ No newline at end of file
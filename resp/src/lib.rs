@@ -1,5 +1,7 @@
 pub mod protocol;
 pub mod parse;
+pub mod request;
+pub mod util;
 
 /// Error returned by most functions.
 ///
@@ -1,4 +1,5 @@
 use crate::protocol::Protocol;
+use crate::util::{string2ld, string2ll};
 use bytes::Bytes;
 use std::{fmt, str, vec};
 
@@ -14,6 +15,9 @@ pub struct Parser {
     parts: vec::IntoIter<Protocol>,
 }
 
+/// Error of the typed accessors on a malformed integer, as replied by Redis.
+const NOT_INTEGER_ERR: &str = "ERR value is not an integer or out of range";
+
 /// Error encountered while parsing a Protocol.
 ///
 /// Only `EndOfStream` errors are handled at runtime. All other errors result in
@@ -91,28 +95,61 @@ impl Parser {
         }
     }
 
-    /// Return the next entry as an integer.
-    ///
-    /// This includes `Simple`, `Bulk`, and `Integer` Protocol types. `Simple` and
-    /// `Bulk` Protocol types are parsed.
-    ///
-    /// If the next entry cannot be represented as an integer, then an error is
-    /// returned.
-    // pub(crate) fn next_int(&mut self) -> Result<u64, ParseError> {
-    //     use atoi::atoi;
-    //
-    //     const MSG: &str = "protocol error; invalid number";
-    //
-    //     match self.next()? {
-    //         // An integer Protocol type is already stored as an integer.
-    //         Protocol::Integer(v) => Ok(v),
-    //         // Simple and bulk Protocols must be parsed as integers. If the parsing
-    //         // fails, an error is returned.
-    //         Protocol::Simple(data) => atoi::<u64>(data.as_bytes()).ok_or_else(|| MSG.into()),
-    //         Protocol::Bulk(data) => atoi::<u64>(&data).ok_or_else(|| MSG.into()),
-    //         protocol => Err(format!("protocol error; expected int Protocol but got {:?}", protocol).into()),
-    //     }
-    // }
+    /// Return the next entry as a signed 64 bit integer, parsed with Redis'
+    /// strict rules: no spaces, no `+` sign and no leading zeros.
+    pub fn next_i64(&mut self) -> Result<i64, ParseError> {
+        let data = self.next_bytes()?;
+        string2ll(&data).ok_or_else(|| NOT_INTEGER_ERR.into())
+    }
+
+    /// Return the next entry as a non-negative integer.
+    pub fn next_u64(&mut self) -> Result<u64, ParseError> {
+        let value = self.next_i64()?;
+        u64::try_from(value).map_err(|_| "ERR value is out of range, must be positive".into())
+    }
+
+    /// Return the next entry as a float. `inf`, `+inf` and `-inf` are
+    /// accepted, NaN is not.
+    pub fn next_f64(&mut self) -> Result<f64, ParseError> {
+        let data = self.next_bytes()?;
+        string2ld(&data).ok_or_else(|| "ERR value is not a valid float".into())
+    }
+
+    /// Return the next entry upper-cased, for matching it against the
+    /// options of a command, or `None` if there are no more entries.
+    pub fn next_keyword(&mut self) -> Result<Option<String>, ParseError> {
+        if self.parts.as_slice().is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(self.next_string()?.to_uppercase()))
+    }
+
+    /// Consume the next entry if it is `keyword`, compared case-insensitively.
+    /// Returns `true` if it was.
+    pub fn next_if_keyword(&mut self, keyword: &str) -> bool {
+        let matches = match self.parts.as_slice().first() {
+            Some(Protocol::Bulk(data)) => data.eq_ignore_ascii_case(keyword.as_bytes()),
+            Some(Protocol::Simple(data)) => data.eq_ignore_ascii_case(keyword),
+            _ => false,
+        };
+        if matches {
+            self.parts.next();
+        }
+        matches
+    }
+
+    /// Number of entries left.
+    pub fn remaining(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// Return every entry left as raw bytes.
+    pub fn remaining_bytes(&mut self) -> impl Iterator<Item = Result<Bytes, ParseError>> + '_ {
+        std::iter::from_fn(move || match self.next_bytes() {
+            Err(ParseError::EndOfStream) => None,
+            result => Some(result),
+        })
+    }
 
     /// Ensure there are no more entries in the array
     pub fn finish(&mut self) -> Result<(), ParseError> {
@@ -146,3 +183,37 @@ impl fmt::Display for ParseError {
 }
 
 impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser(args: &[&str]) -> Parser {
+        Parser::new(Protocol::Array(args.iter().map(|arg| Protocol::Bulk(Bytes::from(arg.to_string()))).collect())).unwrap()
+    }
+
+    #[test]
+    fn typed_accessors_follow_redis_rules() {
+        let mut parse = parser(&["-12", "007", "5", "-1", "1.5e3", "-inf", "nan", "1e400"]);
+        assert_eq!(parse.next_i64().unwrap(), -12);
+        assert_eq!(parse.next_i64().unwrap_err().to_string(), "ERR value is not an integer or out of range");
+        assert_eq!(parse.next_u64().unwrap(), 5);
+        assert_eq!(parse.next_u64().unwrap_err().to_string(), "ERR value is out of range, must be positive");
+        assert_eq!(parse.next_f64().unwrap(), 1500.0);
+        assert_eq!(parse.next_f64().unwrap(), f64::NEG_INFINITY);
+        assert_eq!(parse.next_f64().unwrap_err().to_string(), "ERR value is not a valid float");
+        assert_eq!(parse.next_f64().unwrap_err().to_string(), "ERR value is not a valid float");
+        assert!(matches!(parse.next_i64(), Err(ParseError::EndOfStream)));
+    }
+
+    #[test]
+    fn keywords_and_remaining_args() {
+        let mut parse = parser(&["nx", "ex", "a", "b"]);
+        assert!(!parse.next_if_keyword("XX"));
+        assert!(parse.next_if_keyword("NX"));
+        assert_eq!(parse.next_keyword().unwrap().as_deref(), Some("EX"));
+        assert_eq!(parse.remaining(), 2);
+        assert_eq!(parse.remaining_bytes().collect::<Result<Vec<_>, _>>().unwrap(), vec!["a", "b"]);
+        assert_eq!(parse.next_keyword().unwrap(), None);
+    }
+}
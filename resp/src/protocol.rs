@@ -1,7 +1,7 @@
 //! Provides a type representing a Redis protocol Protocol as well as utilities for
 //! parsing Protocols from a byte array.
 
-use bytes::{Buf, Bytes};
+use bytes::{Buf, BufMut, Bytes};
 use std::convert::TryInto;
 use std::fmt;
 use std::io::Cursor;
@@ -9,14 +9,49 @@ use std::num::TryFromIntError;
 use std::string::FromUtf8Error;
 
 /// A Protocol in the Redis protocol.
+///
+/// Both RESP2 and the types added by RESP3 are represented. Values are
+/// encoded according to the version negotiated with the peer, the RESP3 ones
+/// being downgraded to their closest RESP2 equivalent for RESP2 peers.
 #[derive(Clone, Debug)]
 pub enum Protocol {
     Simple(String),
     Error(String),
-    Integer(u64),
+    Integer(i64),
     Bulk(Bytes),
+    /// Null bulk string, `$-1`, or the RESP3 null, `_`.
     Null,
     Array(Vec<Protocol>),
+    /// Null array, `*-1`, the reply of commands such as `BLPOP` on timeout.
+    NullArray,
+    /// RESP3 map, `%`. Flattened into an array of keys and values for RESP2.
+    Map(Vec<(Protocol, Protocol)>),
+    /// RESP3 set, `~`. An array for RESP2.
+    Set(Vec<Protocol>),
+    /// RESP3 double, `,`. A bulk string for RESP2.
+    Double(f64),
+    /// RESP3 boolean, `#`. The integer 1 or 0 for RESP2.
+    Boolean(bool),
+    /// RESP3 big number, `(`, kept as its decimal digits. A bulk string for
+    /// RESP2.
+    BigNumber(String),
+    /// RESP3 verbatim string, `=`, with its three letters format such as
+    /// `txt`. A bulk string of the data alone for RESP2.
+    Verbatim(String, Bytes),
+    /// RESP3 attributes, `|`, along with the value they decorate. Only the
+    /// value is sent to RESP2 peers.
+    Attribute(Vec<(Protocol, Protocol)>, Box<Protocol>),
+    /// RESP3 push, `>`, out of band data such as pub/sub messages. An array
+    /// for RESP2.
+    Push(Vec<Protocol>),
+}
+
+/// Version of the protocol spoken with a peer, as negotiated with `HELLO`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProtocolVersion {
+    #[default]
+    Resp2,
+    Resp3,
 }
 
 #[derive(Debug)]
@@ -53,14 +88,14 @@ impl Protocol {
     /// # Panics
     ///
     /// panics if `self` is not an array
-    // pub(crate) fn push_int(&mut self, value: u64) {
-    //     match self {
-    //         Protocol::Array(vec) => {
-    //             vec.push(Protocol::Integer(value));
-    //         }
-    //         _ => panic!("not an array Protocol"),
-    //     }
-    // }
+    pub fn push_int(&mut self, value: i64) {
+        match self {
+            Protocol::Array(vec) => {
+                vec.push(Protocol::Integer(value));
+            }
+            _ => panic!("not an array Protocol"),
+        }
+    }
 
     /// Checks if an entire message can be decoded from `src`
     pub fn check(src: &mut Cursor<&[u8]>) -> Result<(), Error> {
@@ -77,22 +112,33 @@ impl Protocol {
                 let _ = get_decimal(src)?;
                 Ok(())
             }
-            b'$' => {
-                if b'-' == peek_u8(src)? {
-                    // Skip '-1\r\n'
-                    skip(src, 4)
-                } else {
-                    // Read the bulk string
-                    let len: usize = get_decimal(src)?.try_into()?;
-
-                    // skip that number of bytes + 2 (\r\n).
-                    skip(src, len + 2)
+            b'_' | b',' | b'#' | b'(' => {
+                get_line(src)?;
+                Ok(())
+            }
+            b'$' | b'=' | b'!' => match get_length(src)? {
+                // Null bulk string, nothing follows.
+                None => Ok(()),
+                // skip that number of bytes + 2 (\r\n).
+                Some(len) => skip(src, len + 2),
+            },
+            b'*' | b'~' | b'>' => {
+                if let Some(len) = get_length(src)? {
+                    for _ in 0..len {
+                        Protocol::check(src)?;
+                    }
                 }
+
+                Ok(())
             }
-            b'*' => {
-                let len = get_decimal(src)?;
+            kind @ (b'%' | b'|') => {
+                let len = get_aggregate_length(src)?;
+                for _ in 0..len * 2 {
+                    Protocol::check(src)?;
+                }
 
-                for _ in 0..len {
+                // Attributes are followed by the value they decorate.
+                if kind == b'|' {
                     Protocol::check(src)?;
                 }
 
@@ -124,51 +170,181 @@ impl Protocol {
                 Ok(Protocol::Error(string))
             }
             b':' => {
-                let len = get_decimal(src)?;
-                Ok(Protocol::Integer(len))
+                let value = get_decimal(src)?;
+                Ok(Protocol::Integer(value))
             }
             b'$' => {
-                if b'-' == peek_u8(src)? {
-                    let line = get_line(src)?;
-
-                    if line != b"-1" {
-                        return Err("protocol error; invalid Protocol format".into());
-                    }
+                let Some(len) = get_length(src)? else {
+                    return Ok(Protocol::Null);
+                };
 
-                    Ok(Protocol::Null)
-                } else {
-                    // Read the bulk string
-                    let len = get_decimal(src)?.try_into()?;
-                    let n = len + 2;
+                Ok(Protocol::Bulk(get_bulk(src, len)?))
+            }
+            b'*' => {
+                let Some(len) = get_length(src)? else {
+                    return Ok(Protocol::NullArray);
+                };
 
-                    if src.remaining() < n {
-                        return Err(Error::Incomplete);
-                    }
+                Ok(Protocol::Array(parse_parts(src, len)?))
+            }
+            b'_' => {
+                if !get_line(src)?.is_empty() {
+                    return Err("protocol error; invalid Protocol format".into());
+                }
 
-                    let data = Bytes::copy_from_slice(&src.chunk()[..len]);
+                Ok(Protocol::Null)
+            }
+            b',' => {
+                let line = get_line(src)?;
+                let value = std::str::from_utf8(line)
+                    .ok()
+                    .and_then(|line| line.parse::<f64>().ok())
+                    .ok_or("protocol error; invalid double")?;
+
+                Ok(Protocol::Double(value))
+            }
+            b'#' => match get_line(src)? {
+                b"t" => Ok(Protocol::Boolean(true)),
+                b"f" => Ok(Protocol::Boolean(false)),
+                _ => Err("protocol error; invalid boolean".into()),
+            },
+            b'(' => {
+                let digits = String::from_utf8(get_line(src)?.to_vec())?;
+                let unsigned = digits.strip_prefix('-').unwrap_or(&digits);
+                if unsigned.is_empty() || !unsigned.bytes().all(|c| c.is_ascii_digit()) {
+                    return Err("protocol error; invalid big number".into());
+                }
 
-                    // skip that number of bytes + 2 (\r\n).
-                    skip(src, n)?;
+                Ok(Protocol::BigNumber(digits))
+            }
+            b'=' => {
+                let len = get_length(src)?.ok_or("protocol error; invalid verbatim string")?;
+                let data = get_bulk(src, len)?;
 
-                    Ok(Protocol::Bulk(data))
+                // The data starts with its format, such as `txt:`.
+                if data.len() < 4 || data[3] != b':' {
+                    return Err("protocol error; invalid verbatim string".into());
                 }
+                let format = String::from_utf8(data[..3].to_vec())?;
+
+                Ok(Protocol::Verbatim(format, data.slice(4..)))
             }
-            b'*' => {
-                let len = get_decimal(src)?.try_into()?;
-                let mut out = Vec::with_capacity(len);
+            b'!' => {
+                let len = get_length(src)?.ok_or("protocol error; invalid blob error")?;
+                let data = get_bulk(src, len)?;
 
-                for _ in 0..len {
-                    out.push(Protocol::parse(src)?);
-                }
+                Ok(Protocol::Error(String::from_utf8(data.to_vec())?))
+            }
+            b'~' => {
+                let len = get_aggregate_length(src)?;
+                Ok(Protocol::Set(parse_parts(src, len)?))
+            }
+            b'>' => {
+                let len = get_aggregate_length(src)?;
+                Ok(Protocol::Push(parse_parts(src, len)?))
+            }
+            b'%' => {
+                let len = get_aggregate_length(src)?;
+                Ok(Protocol::Map(parse_pairs(src, len)?))
+            }
+            b'|' => {
+                let len = get_aggregate_length(src)?;
+                let attributes = parse_pairs(src, len)?;
+                let value = Protocol::parse(src)?;
+
+                Ok(Protocol::Attribute(attributes, Box::new(value)))
+            }
+            actual => Err(format!("protocol error; invalid Protocol type byte `{}`", actual).into()),
+        }
+    }
+
+    /// Encode the Protocol into `dst`, for a peer speaking `version`.
+    ///
+    /// Arrays are encoded recursively, so replies may nest them to any depth.
+    /// Nothing is allocated besides the growth of `dst`: numbers are
+    /// formatted on the stack and payloads copied straight into `dst`. RESP3
+    /// values are downgraded on the fly for RESP2 peers, see `Protocol`.
+    pub fn encode<B: BufMut>(&self, dst: &mut B, version: ProtocolVersion) {
+        let resp3 = version == ProtocolVersion::Resp3;
 
-                Ok(Protocol::Array(out))
+        match self {
+            Protocol::Simple(val) => {
+                dst.put_u8(b'+');
+                dst.put_slice(val.as_bytes());
+                dst.put_slice(b"\r\n");
+            }
+            Protocol::Error(val) => {
+                dst.put_u8(b'-');
+                dst.put_slice(val.as_bytes());
+                dst.put_slice(b"\r\n");
+            }
+            Protocol::Integer(val) => {
+                dst.put_u8(b':');
+                put_decimal(dst, *val);
+            }
+            Protocol::Bulk(val) => put_bulk(dst, b'$', val),
+            Protocol::Null | Protocol::NullArray if resp3 => dst.put_slice(b"_\r\n"),
+            Protocol::Null => dst.put_slice(b"$-1\r\n"),
+            Protocol::NullArray => dst.put_slice(b"*-1\r\n"),
+            Protocol::Array(parts) => put_parts(dst, b'*', parts, version),
+            Protocol::Set(parts) => put_parts(dst, if resp3 { b'~' } else { b'*' }, parts, version),
+            Protocol::Push(parts) => put_parts(dst, if resp3 { b'>' } else { b'*' }, parts, version),
+            Protocol::Map(pairs) => {
+                if resp3 {
+                    dst.put_u8(b'%');
+                    put_decimal(dst, pairs.len() as i64);
+                } else {
+                    dst.put_u8(b'*');
+                    put_decimal(dst, pairs.len() as i64 * 2);
+                }
+                for (key, value) in pairs {
+                    key.encode(dst, version);
+                    value.encode(dst, version);
+                }
+            }
+            Protocol::Double(val) => {
+                let repr = double_repr(*val);
+                if resp3 {
+                    dst.put_u8(b',');
+                    dst.put_slice(repr.as_bytes());
+                    dst.put_slice(b"\r\n");
+                } else {
+                    put_bulk(dst, b'$', repr.as_bytes());
+                }
+            }
+            Protocol::Boolean(val) if resp3 => dst.put_slice(if *val { b"#t\r\n" } else { b"#f\r\n" }),
+            Protocol::Boolean(val) => dst.put_slice(if *val { b":1\r\n" } else { b":0\r\n" }),
+            Protocol::BigNumber(digits) if resp3 => {
+                dst.put_u8(b'(');
+                dst.put_slice(digits.as_bytes());
+                dst.put_slice(b"\r\n");
+            }
+            Protocol::BigNumber(digits) => put_bulk(dst, b'$', digits.as_bytes()),
+            Protocol::Verbatim(format, data) if resp3 => {
+                dst.put_u8(b'=');
+                put_decimal(dst, (format.len() + 1 + data.len()) as i64);
+                dst.put_slice(format.as_bytes());
+                dst.put_u8(b':');
+                dst.put_slice(data);
+                dst.put_slice(b"\r\n");
+            }
+            Protocol::Verbatim(_, data) => put_bulk(dst, b'$', data),
+            Protocol::Attribute(attributes, value) => {
+                if resp3 {
+                    dst.put_u8(b'|');
+                    put_decimal(dst, attributes.len() as i64);
+                    for (key, value) in attributes {
+                        key.encode(dst, version);
+                        value.encode(dst, version);
+                    }
+                }
+                value.encode(dst, version);
             }
-            _ => unimplemented!(),
         }
     }
 
     /// Converts the Protocol to an "unexpected Protocol" error
-    pub(crate) fn to_error(&self) -> crate::Error {
+    pub fn to_error(&self) -> crate::Error {
         format!("unexpected Protocol: {}", self).into()
     }
 }
@@ -195,8 +371,8 @@ impl fmt::Display for Protocol {
                 Ok(string) => string.fmt(fmt),
                 Err(_) => write!(fmt, "{:?}", msg),
             },
-            Protocol::Null => "(nil)".fmt(fmt),
-            Protocol::Array(parts) => {
+            Protocol::Null | Protocol::NullArray => "(nil)".fmt(fmt),
+            Protocol::Array(parts) | Protocol::Set(parts) | Protocol::Push(parts) => {
                 for (i, part) in parts.iter().enumerate() {
                     if i > 0 {
                         // use space as the array element display separator
@@ -208,16 +384,24 @@ impl fmt::Display for Protocol {
 
                 Ok(())
             }
-        }
-    }
-}
+            Protocol::Map(pairs) => {
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        write!(fmt, " ")?;
+                    }
 
-fn peek_u8(src: &mut Cursor<&[u8]>) -> Result<u8, Error> {
-    if !src.has_remaining() {
-        return Err(Error::Incomplete);
-    }
+                    write!(fmt, "{} => {}", key, value)?;
+                }
 
-    Ok(src.chunk()[0])
+                Ok(())
+            }
+            Protocol::Double(num) => double_repr(*num).fmt(fmt),
+            Protocol::Boolean(val) => val.fmt(fmt),
+            Protocol::BigNumber(digits) => digits.fmt(fmt),
+            Protocol::Verbatim(_, data) => String::from_utf8_lossy(data).fmt(fmt),
+            Protocol::Attribute(_, value) => value.fmt(fmt),
+        }
+    }
 }
 
 fn get_u8(src: &mut Cursor<&[u8]>) -> Result<u8, Error> {
@@ -237,13 +421,119 @@ fn skip(src: &mut Cursor<&[u8]>, n: usize) -> Result<(), Error> {
     Ok(())
 }
 
-/// Read a new-line terminated decimal
-fn get_decimal(src: &mut Cursor<&[u8]>) -> Result<u64, Error> {
-    use atoi::atoi;
-
+/// Read a new-line terminated signed decimal.
+///
+/// The whole line must be the number: trailing bytes are an error rather
+/// than silently ignored.
+fn get_decimal(src: &mut Cursor<&[u8]>) -> Result<i64, Error> {
     let line = get_line(src)?;
 
-    atoi::<u64>(line).ok_or_else(|| "protocol error; invalid Protocol format".into())
+    std::str::from_utf8(line)
+        .ok()
+        .and_then(|line| line.parse::<i64>().ok())
+        .ok_or_else(|| "protocol error; invalid Protocol format".into())
+}
+
+/// Read the length of a bulk string or an array, `None` for the `-1` of a
+/// null one. Any other negative length is an error.
+fn get_length(src: &mut Cursor<&[u8]>) -> Result<Option<usize>, Error> {
+    match get_decimal(src)? {
+        -1 => Ok(None),
+        len => Ok(Some(len.try_into()?)),
+    }
+}
+
+/// Read a bulk payload of `len` bytes followed by `\r\n`.
+fn get_bulk(src: &mut Cursor<&[u8]>, len: usize) -> Result<Bytes, Error> {
+    let n = len + 2;
+
+    if src.remaining() < n {
+        return Err(Error::Incomplete);
+    }
+
+    let data = Bytes::copy_from_slice(&src.chunk()[..len]);
+
+    // skip that number of bytes + 2 (\r\n).
+    skip(src, n)?;
+
+    Ok(data)
+}
+
+/// Read the length of an aggregate that has no null form, such as a map.
+fn get_aggregate_length(src: &mut Cursor<&[u8]>) -> Result<usize, Error> {
+    get_length(src)?.ok_or_else(|| "protocol error; invalid Protocol format".into())
+}
+
+fn parse_parts(src: &mut Cursor<&[u8]>, len: usize) -> Result<Vec<Protocol>, Error> {
+    let mut out = Vec::with_capacity(len);
+
+    for _ in 0..len {
+        out.push(Protocol::parse(src)?);
+    }
+
+    Ok(out)
+}
+
+fn parse_pairs(src: &mut Cursor<&[u8]>, len: usize) -> Result<Vec<(Protocol, Protocol)>, Error> {
+    let mut out = Vec::with_capacity(len);
+
+    for _ in 0..len {
+        out.push((Protocol::parse(src)?, Protocol::parse(src)?));
+    }
+
+    Ok(out)
+}
+
+/// Write a bulk payload, `$` or `=` prefixed.
+fn put_bulk<B: BufMut>(dst: &mut B, kind: u8, data: &[u8]) {
+    dst.put_u8(kind);
+    put_decimal(dst, data.len() as i64);
+    dst.put_slice(data);
+    dst.put_slice(b"\r\n");
+}
+
+/// Write an aggregate of `parts`, `kind` being its type byte.
+fn put_parts<B: BufMut>(dst: &mut B, kind: u8, parts: &[Protocol], version: ProtocolVersion) {
+    dst.put_u8(kind);
+    put_decimal(dst, parts.len() as i64);
+    for part in parts {
+        part.encode(dst, version);
+    }
+}
+
+/// Textual form of a double, as in Redis' RESP3 replies: `inf`, `-inf` and
+/// `nan` for the special values.
+fn double_repr(val: f64) -> String {
+    if val.is_nan() {
+        "nan".to_string()
+    } else if val.is_infinite() {
+        if val > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        val.to_string()
+    }
+}
+
+/// Write a decimal followed by `\r\n`.
+fn put_decimal<B: BufMut>(dst: &mut B, val: i64) {
+    // 20 digits hold any u64, the sign needs one more byte.
+    let mut buf = [0u8; 21];
+    let mut pos = buf.len();
+    let mut n = val.unsigned_abs();
+    loop {
+        pos -= 1;
+        buf[pos] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    if val < 0 {
+        pos -= 1;
+        buf[pos] = b'-';
+    }
+
+    dst.put_slice(&buf[pos..]);
+    dst.put_slice(b"\r\n");
 }
 
 /// Find a line
@@ -300,3 +590,62 @@ impl fmt::Display for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(src: &[u8]) -> Result<Protocol, Error> {
+        Protocol::check(&mut Cursor::new(src))?;
+        Protocol::parse(&mut Cursor::new(src))
+    }
+
+    #[test]
+    fn parses_signed_integers_and_nulls() {
+        assert!(matches!(parse(b":-1\r\n"), Ok(Protocol::Integer(-1))));
+        assert!(matches!(parse(b":-9223372036854775808\r\n"), Ok(Protocol::Integer(i64::MIN))));
+        assert!(matches!(parse(b"$-1\r\n"), Ok(Protocol::Null)));
+        assert!(matches!(parse(b"*-1\r\n"), Ok(Protocol::NullArray)));
+        assert!(matches!(parse(b"*2\r\n$-1\r\n:3\r\n"), Ok(Protocol::Array(v)) if v.len() == 2));
+
+        assert!(matches!(parse(b":12x\r\n"), Err(Error::Other(_))));
+        assert!(matches!(parse(b"$-2\r\n"), Err(Error::Other(_))));
+        assert!(matches!(parse(b":-1"), Err(Error::Incomplete)));
+    }
+
+    #[test]
+    fn encodes_nested_arrays() {
+        let reply = Protocol::Array(vec![
+            Protocol::Bulk(Bytes::from("0")),
+            Protocol::Array(vec![Protocol::Integer(i64::MIN), Protocol::NullArray, Protocol::Array(vec![])]),
+            Protocol::Null,
+        ]);
+
+        let mut dst = Vec::new();
+        reply.encode(&mut dst, ProtocolVersion::Resp2);
+        assert_eq!(dst, b"*3\r\n$1\r\n0\r\n*3\r\n:-9223372036854775808\r\n*-1\r\n*0\r\n$-1\r\n");
+        assert!(matches!(parse(&dst), Ok(Protocol::Array(parts)) if parts.len() == 3));
+    }
+
+    #[test]
+    fn resp3_types_are_downgraded_for_resp2() {
+        let reply = Protocol::Map(vec![
+            (Protocol::Simple("proto".into()), Protocol::Integer(3)),
+            (Protocol::Simple("ok".into()), Protocol::Boolean(true)),
+            (Protocol::Simple("score".into()), Protocol::Double(1.5)),
+            (Protocol::Simple("none".into()), Protocol::Null),
+        ]);
+
+        let mut resp3 = Vec::new();
+        reply.encode(&mut resp3, ProtocolVersion::Resp3);
+        assert_eq!(resp3, b"%4\r\n+proto\r\n:3\r\n+ok\r\n#t\r\n+score\r\n,1.5\r\n+none\r\n_\r\n");
+        assert!(matches!(parse(&resp3), Ok(Protocol::Map(pairs)) if pairs.len() == 4));
+
+        let mut resp2 = Vec::new();
+        reply.encode(&mut resp2, ProtocolVersion::Resp2);
+        assert_eq!(resp2, b"*8\r\n+proto\r\n:3\r\n+ok\r\n:1\r\n+score\r\n$3\r\n1.5\r\n+none\r\n$-1\r\n");
+
+        assert!(matches!(parse(b"=8\r\ntxt:some\r\n"), Ok(Protocol::Verbatim(f, d)) if f == "txt" && d == "some"));
+        assert!(matches!(parse(b"|1\r\n+ttl\r\n:3\r\n~1\r\n(-12\r\n"), Ok(Protocol::Attribute(_, v)) if matches!(*v, Protocol::Set(_))));
+    }
+}
@@ -0,0 +1,433 @@
+//! Incremental decoding of client requests.
+
+use bytes::{Buf, Bytes, BytesMut};
+use crate::protocol::Protocol;
+
+/// Bulk strings this long or longer are split off the read buffer rather than
+/// copied, Redis' `PROTO_MBULK_BIG_ARG`.
+const PROTO_MBULK_BIG_ARG: usize = 32 * 1024;
+
+/// Decoder of the requests sent by clients, after Redis' `processInlineBuffer`
+/// and `processMultibulkBuffer`.
+///
+/// Requests are either multibulk, an array of bulk strings, or inline, a
+/// single line of text. They are decoded in a single pass straight off the
+/// connection's read buffer. Big bulk payloads are split off it as `Bytes`
+/// sharing its memory rather than copied; small ones are copied, since a
+/// stored key or value sharing the buffer would keep all of it allocated. A
+/// request may arrive over several
+/// reads; what was decoded of it so far is kept here, so the next call
+/// resumes where the previous one stopped instead of starting over.
+#[derive(Debug, Default)]
+pub struct RequestDecoder {
+    limits: RequestLimits,
+    // Kind of the request being decoded, `None` between requests.
+    kind: Option<RequestKind>,
+    // Number of arguments announced by the multibulk header, once read.
+    multibulk_len: Option<usize>,
+    // Length of the bulk string being waited for, once its header is read.
+    bulk_len: Option<usize>,
+    // Arguments decoded so far.
+    args: Vec<Bytes>,
+    // Bytes at the head of the buffer already searched for a line end.
+    scanned: usize,
+}
+
+/// Bounds on the requests accepted by a `RequestDecoder`, so that a client
+/// cannot make the server allocate arbitrary amounts of memory by announcing
+/// huge lengths. Requests going over them are protocol errors.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestLimits {
+    /// Largest bulk string, Redis' `proto-max-bulk-len`.
+    pub max_bulk_len: usize,
+    /// Largest number of arguments of a multibulk request.
+    pub max_multibulk_len: usize,
+    /// Largest inline request, and largest multibulk or bulk header line,
+    /// Redis' `PROTO_INLINE_MAX_SIZE`.
+    pub max_inline_size: usize,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_bulk_len: 512 * 1024 * 1024,
+            max_multibulk_len: i32::MAX as usize,
+            max_inline_size: 64 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RequestKind {
+    Inline,
+    Multibulk,
+}
+
+impl RequestDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limits(limits: RequestLimits) -> Self {
+        Self { limits, ..Self::default() }
+    }
+
+    /// Decode the next request from `buf`, consuming its bytes.
+    ///
+    /// Returns `Ok(None)` if the request is not complete yet; call again once
+    /// more data has been appended to `buf`. Multibulk requests are returned
+    /// as arrays of bulk strings, those announcing no arguments or a negative
+    /// number of them as empty arrays. Inline requests are returned in the
+    /// same form as their multibulk equivalent, an empty line giving an empty
+    /// array.
+    ///
+    /// Errors are Redis' protocol errors, such as `Protocol error: invalid
+    /// bulk length`, meant to be replied before closing the connection. The
+    /// decoder and `buf` are left in an unspecified state after one.
+    pub fn decode(&mut self, buf: &mut BytesMut) -> crate::Result<Option<Protocol>> {
+        let kind = match self.kind {
+            Some(kind) => kind,
+            None if buf.is_empty() => return Ok(None),
+            None if buf[0] == b'*' => RequestKind::Multibulk,
+            None => RequestKind::Inline,
+        };
+        self.kind = Some(kind);
+
+        match kind {
+            RequestKind::Inline => self.decode_inline(buf),
+            RequestKind::Multibulk => self.decode_multibulk(buf),
+        }
+    }
+
+    fn decode_inline(&mut self, buf: &mut BytesMut) -> crate::Result<Option<Protocol>> {
+        let Some(end) = self.find(buf, b"\n") else {
+            if buf.len() > self.limits.max_inline_size {
+                return Err("Protocol error: too big inline request".into());
+            }
+            return Ok(None);
+        };
+
+        let line = buf.split_to(end + 1);
+        let line = line[..end].strip_suffix(b"\r").unwrap_or(&line[..end]);
+        let args = split_args(line).ok_or("Protocol error: unbalanced quotes in request")?;
+
+        self.reset();
+        Ok(Some(Protocol::Array(args.into_iter().map(|arg| Protocol::Bulk(Bytes::from(arg))).collect())))
+    }
+
+    fn decode_multibulk(&mut self, buf: &mut BytesMut) -> crate::Result<Option<Protocol>> {
+        let multibulk_len = match self.multibulk_len {
+            Some(len) => len,
+            None => {
+                let Some(len) = self.read_length(buf, b'*')? else {
+                    return Ok(None);
+                };
+                match len {
+                    // Redis ignores a multibulk announcing no arguments, or a
+                    // negative number of them.
+                    len if len <= 0 => {
+                        self.reset();
+                        return Ok(Some(Protocol::Array(Vec::new())));
+                    }
+                    len if len as u64 > self.limits.max_multibulk_len as u64 => {
+                        return Err("Protocol error: invalid multibulk length".into());
+                    }
+                    len => {
+                        let len = len as usize;
+                        self.multibulk_len = Some(len);
+                        self.args = Vec::with_capacity(len.min(1024));
+                        len
+                    }
+                }
+            }
+        };
+
+        while self.args.len() < multibulk_len {
+            let bulk_len = match self.bulk_len {
+                Some(len) => len,
+                None => {
+                    let Some(len) = self.read_length(buf, b'$')? else {
+                        return Ok(None);
+                    };
+                    let len = usize::try_from(len)
+                        .ok()
+                        .filter(|len| *len <= self.limits.max_bulk_len)
+                        .ok_or("Protocol error: invalid bulk length")?;
+                    self.bulk_len = Some(len);
+                    len
+                }
+            };
+
+            if buf.len() < bulk_len + 2 {
+                // Make room for the payload ahead of the reads rather than
+                // growing the buffer read after read. The length is only
+                // announced: past `PROTO_MBULK_BIG_ARG` the room made is
+                // bounded by what actually arrived, so that a header alone
+                // cannot make the server allocate gigabytes.
+                let missing = bulk_len + 2 - buf.len();
+                if bulk_len >= PROTO_MBULK_BIG_ARG {
+                    buf.reserve(missing.min(buf.len().max(PROTO_MBULK_BIG_ARG)));
+                } else {
+                    buf.reserve(missing);
+                }
+                return Ok(None);
+            }
+
+            let arg = if bulk_len >= PROTO_MBULK_BIG_ARG {
+                buf.split_to(bulk_len).freeze()
+            } else {
+                let arg = Bytes::copy_from_slice(&buf[..bulk_len]);
+                buf.advance(bulk_len);
+                arg
+            };
+            self.args.push(arg);
+            buf.advance(2);
+            self.bulk_len = None;
+        }
+
+        let args = std::mem::take(&mut self.args);
+        self.reset();
+        Ok(Some(Protocol::Array(args.into_iter().map(Protocol::Bulk).collect())))
+    }
+
+    /// Read a `\r\n` terminated header made of the `kind` byte and a signed
+    /// length. Returns `Ok(None)` if the line is not complete yet.
+    fn read_length(&mut self, buf: &mut BytesMut, kind: u8) -> crate::Result<Option<i64>> {
+        let Some(end) = self.find(buf, b"\r\n") else {
+            if buf.len() > self.limits.max_inline_size {
+                return Err(match kind {
+                    b'*' => "Protocol error: too big mbulk count string",
+                    _ => "Protocol error: too big bulk count string",
+                }.into());
+            }
+            return Ok(None);
+        };
+
+        if buf[0] != kind {
+            return Err(format!("Protocol error: expected '{}', got '{}'", kind as char, buf[0] as char).into());
+        }
+        let len = std::str::from_utf8(&buf[1..end])
+            .ok()
+            .and_then(|len| len.parse::<i64>().ok())
+            .ok_or(match kind {
+                b'*' => "Protocol error: invalid multibulk length",
+                _ => "Protocol error: invalid bulk length",
+            })?;
+
+        buf.advance(end + 2);
+        Ok(Some(len))
+    }
+
+    /// Position of the first `delimiter` in `buf`. The bytes searched in vain
+    /// are remembered so that the next call only looks at new data.
+    fn find(&mut self, buf: &BytesMut, delimiter: &[u8]) -> Option<usize> {
+        // A `\r\n` may straddle the previously searched bytes and new ones.
+        let from = self.scanned.saturating_sub(delimiter.len() - 1);
+        match buf[from..].windows(delimiter.len()).position(|window| window == delimiter) {
+            Some(pos) => {
+                self.scanned = 0;
+                Some(from + pos)
+            }
+            None => {
+                self.scanned = buf.len();
+                None
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.kind = None;
+        self.multibulk_len = None;
+        self.bulk_len = None;
+        self.args.clear();
+        self.scanned = 0;
+    }
+}
+
+/// Split an inline command into its arguments, Redis' `sdssplitargs`.
+///
+/// Arguments are separated by whitespace. Double quoted ones may contain
+/// the escapes `\n`, `\r`, `\t`, `\b`, `\a`, `\\`, `\"` and `\xHH`; single
+/// quoted ones only `\'`. Returns `None` if a quote is not closed or is not
+/// followed by a space.
+fn split_args(line: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut args = Vec::new();
+    let mut pos = 0;
+
+    loop {
+        while line.get(pos).is_some_and(|b| b.is_ascii_whitespace() || *b == 0x0b) {
+            pos += 1;
+        }
+        if pos == line.len() {
+            return Some(args);
+        }
+
+        let mut arg = Vec::new();
+        let mut in_double = false;
+        let mut in_single = false;
+        loop {
+            let c = line.get(pos).copied();
+            if in_double {
+                match c? {
+                    b'\\' if line.len() > pos + 3
+                        && line[pos + 1] == b'x'
+                        && line[pos + 2].is_ascii_hexdigit()
+                        && line[pos + 3].is_ascii_hexdigit() =>
+                    {
+                        let hex = std::str::from_utf8(&line[pos + 2..pos + 4]).ok()?;
+                        arg.push(u8::from_str_radix(hex, 16).ok()?);
+                        pos += 3;
+                    }
+                    b'\\' if pos + 1 < line.len() => {
+                        pos += 1;
+                        arg.push(match line[pos] {
+                            b'n' => b'\n',
+                            b'r' => b'\r',
+                            b't' => b'\t',
+                            b'b' => 0x08,
+                            b'a' => 0x07,
+                            other => other,
+                        });
+                    }
+                    b'"' => {
+                        // The closing quote must be followed by a space or
+                        // end the line.
+                        if line.get(pos + 1).is_some_and(|b| !b.is_ascii_whitespace()) {
+                            return None;
+                        }
+                        pos += 1;
+                        break;
+                    }
+                    other => arg.push(other),
+                }
+            } else if in_single {
+                match c? {
+                    b'\\' if line.get(pos + 1) == Some(&b'\'') => {
+                        pos += 1;
+                        arg.push(b'\'');
+                    }
+                    b'\'' => {
+                        if line.get(pos + 1).is_some_and(|b| !b.is_ascii_whitespace()) {
+                            return None;
+                        }
+                        pos += 1;
+                        break;
+                    }
+                    other => arg.push(other),
+                }
+            } else {
+                match c {
+                    None | Some(b' ' | b'\n' | b'\r' | b'\t' | 0) => break,
+                    Some(b'"') => in_double = true,
+                    Some(b'\'') => in_single = true,
+                    Some(other) => arg.push(other),
+                }
+            }
+            pos += 1;
+        }
+        args.push(arg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(request: Protocol) -> Vec<Bytes> {
+        match request {
+            Protocol::Array(parts) => parts.into_iter().map(|part| match part {
+                Protocol::Bulk(arg) => arg,
+                other => panic!("unexpected {:?}", other),
+            }).collect(),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resumes_partial_multibulk() {
+        let mut decoder = RequestDecoder::new();
+        let mut buf = BytesMut::new();
+        let request = b"*2\r\n$3\r\nGET\r\n$5\r\nhello\r\n*1\r\n$4\r\nPING\r\n";
+
+        // Feed the requests one byte at a time.
+        let mut decoded = Vec::new();
+        for &b in request.iter() {
+            buf.extend_from_slice(&[b]);
+            while let Some(request) = decoder.decode(&mut buf).unwrap() {
+                decoded.push(args(request));
+            }
+        }
+        assert_eq!(decoded, vec![vec!["GET", "hello"], vec!["PING"]]);
+        assert!(buf.is_empty());
+
+        for empty in [&b"*-1\r\n"[..], b"*-5\r\n", b"*0\r\n"] {
+            assert!(args(decoder.decode(&mut BytesMut::from(empty)).unwrap().unwrap()).is_empty());
+        }
+        assert!(decoder.decode(&mut BytesMut::from(&b"*1\r\n:1\r\n"[..])).is_err());
+    }
+
+    #[test]
+    fn copies_small_arguments() {
+        // Read as a connection does, into a buffer grown by large chunks.
+        let mut buf = BytesMut::with_capacity(16 * 1024);
+        for i in 0..1000 {
+            buf.extend_from_slice(format!("*3\r\n$3\r\nSET\r\n${}\r\nk{}\r\n$1\r\nv\r\n", i.to_string().len() + 1, i).as_bytes());
+        }
+        let big = vec![b'x'; PROTO_MBULK_BIG_ARG];
+        buf.extend_from_slice(format!("*1\r\n${}\r\n", big.len()).as_bytes());
+        buf.extend_from_slice(&big);
+        buf.extend_from_slice(b"\r\n");
+
+        let mut decoder = RequestDecoder::new();
+        let mut small = Vec::new();
+        while let Some(request) = decoder.decode(&mut buf).unwrap() {
+            small.extend(args(request));
+        }
+        let big = small.pop().unwrap();
+        assert_eq!(small.len(), 3000);
+        // Each small argument owns its bytes instead of keeping the buffer
+        // alive, while the big one shares it.
+        assert!(small.iter().all(|arg| arg.is_unique()));
+        assert!(!big.is_unique());
+    }
+
+    #[test]
+    fn big_bulks_grow_with_the_data() {
+        let mut decoder = RequestDecoder::new();
+        let mut buf = BytesMut::from(&b"*1\r\n$536870912\r\n"[..]);
+        assert!(matches!(decoder.decode(&mut buf), Ok(None)));
+        assert!(buf.capacity() <= 2 * PROTO_MBULK_BIG_ARG);
+
+        buf.extend_from_slice(&vec![b'x'; 1024 * 1024]);
+        assert!(matches!(decoder.decode(&mut buf), Ok(None)));
+        assert!(buf.capacity() <= 4 * 1024 * 1024);
+    }
+
+    #[test]
+    fn rejects_requests_over_the_limits() {
+        let limits = RequestLimits { max_bulk_len: 8, max_multibulk_len: 2, max_inline_size: 16 };
+        let decode = |src: &[u8]| RequestDecoder::with_limits(limits).decode(&mut BytesMut::from(src)).map_err(|e| e.to_string());
+
+        assert!(matches!(decode(b"*1\r\n$8\r\n"), Ok(None)));
+        assert_eq!(decode(b"*1\r\n$9\r\n").unwrap_err(), "Protocol error: invalid bulk length");
+        assert_eq!(decode(b"*3\r\n").unwrap_err(), "Protocol error: invalid multibulk length");
+        assert_eq!(decode(b"*1\r\n$-1\r\n").unwrap_err(), "Protocol error: invalid bulk length");
+        assert_eq!(decode(b"*11111111111111111").unwrap_err(), "Protocol error: too big mbulk count string");
+        assert_eq!(decode(b"PING PING PING PING").unwrap_err(), "Protocol error: too big inline request");
+    }
+
+    #[test]
+    fn decodes_inline_commands() {
+        fn decode(src: &[u8]) -> crate::Result<Option<Protocol>> {
+            RequestDecoder::new().decode(&mut BytesMut::from(src))
+        }
+
+        assert_eq!(args(decode(b"SET  key value\r\n").unwrap().unwrap()), vec!["SET", "key", "value"]);
+        assert_eq!(args(decode(b"set \"a b\\x41\\n\" 'it\\'s' \"\"\n").unwrap().unwrap()), vec!["set", "a bA\n", "it's", ""]);
+        assert!(args(decode(b"\r\n").unwrap().unwrap()).is_empty());
+
+        assert!(matches!(decode(b"PING"), Ok(None)));
+        assert!(decode(b"GET \"key\"x\n").is_err());
+        assert!(decode(b"GET 'key\n").is_err());
+    }
+}
@@ -0,0 +1,64 @@
+//! Conversions between strings and numbers, after Redis' `util.c`.
+
+/// Parse a signed 64 bit integer the way Redis' `string2ll` does.
+///
+/// The conversion is strict: no spaces, no `+` sign, no leading zeros, and
+/// the value must fit, so that converting the result back to a string gives
+/// the exact same bytes.
+pub fn string2ll(s: &[u8]) -> Option<i64> {
+    if s.is_empty() || s.len() > 20 {
+        return None;
+    }
+    if s == b"0" {
+        return Some(0);
+    }
+
+    let (negative, digits) = match s {
+        [b'-', rest @ ..] => (true, rest),
+        _ => (false, s),
+    };
+    // The first digit must be 1-9, which also rejects "-" and "-0".
+    if !matches!(digits.first(), Some(b'1'..=b'9')) {
+        return None;
+    }
+
+    let mut v: u64 = 0;
+    for &c in digits {
+        if !c.is_ascii_digit() {
+            return None;
+        }
+        v = v.checked_mul(10)?.checked_add((c - b'0') as u64)?;
+    }
+
+    if negative {
+        // -(i64::MIN) does not fit an i64, hence the detour through u64.
+        if v > i64::MIN.unsigned_abs() {
+            return None;
+        }
+        Some((v as i64).wrapping_neg())
+    } else {
+        i64::try_from(v).ok()
+    }
+}
+
+/// Longest string `string2ld` accepts, plus one, Redis'
+/// `MAX_LONG_DOUBLE_CHARS`.
+const MAX_LONG_DOUBLE_CHARS: usize = 5 * 1024;
+
+/// Parse a float the way Redis' `string2ld` does: the whole input must be a
+/// number, without surrounding spaces, and NaN is refused. `inf` and
+/// `-inf` are accepted, but not a finite number too large to represent.
+pub fn string2ld(s: &[u8]) -> Option<f64> {
+    if s.len() >= MAX_LONG_DOUBLE_CHARS {
+        return None;
+    }
+    let s = std::str::from_utf8(s).ok()?;
+    if s.is_empty() || s.starts_with(|c: char| c.is_ascii_whitespace()) {
+        return None;
+    }
+    let value = s.parse::<f64>().ok().filter(|value| !value.is_nan())?;
+    if value.is_infinite() && !s.to_ascii_lowercase().contains("inf") {
+        return None;
+    }
+    Some(value)
+}
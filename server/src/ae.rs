@@ -1,10 +1,18 @@
 use std::io;
+use std::thread;
 use std::time::Duration;
+use crate::eventloop::btree_time_event_manager::BTreeTimeEventManager;
+use crate::eventloop::eventloop::{
+    is_all_event, is_call_after_sleep, is_call_before_sleep, is_dont_wait, is_file_event, is_time_event,
+    EventLoopFlag, AE_ALL_EVENTS, AE_CALL_AFTER_SLEEP, AE_CALL_BEFORE_SLEEP,
+};
 use crate::eventloop::io_event::IoEventManager;
 use crate::eventloop::mio_event_manager::MioEventManager;
+use crate::eventloop::time_event::TimeEventManager;
 use crate::server::RedisServer;
 
 pub(crate) trait EventLoop {
+    #[allow(dead_code)]
     fn get_max_file_descriptor() -> i32;
 
     fn before_sleep(&mut self) -> io::Result<()>;
@@ -15,27 +23,101 @@ pub(crate) trait EventLoop {
 
 pub(crate) struct SingleThreadEventLoop {
     io_event_loop: MioEventManager,
+    time_event_manager: BTreeTimeEventManager,
 }
 
 impl SingleThreadEventLoop {
 
     pub(crate) fn new(redis_server: RedisServer) -> Self {
         Self {
-            io_event_loop: MioEventManager::new(redis_server)
+            time_event_manager: redis_server.time_event_manager.clone(),
+            io_event_loop: MioEventManager::new(redis_server),
         }
     }
 
-    pub(crate) fn run(&mut self) -> () {
+    pub(crate) fn run(&mut self) {
         loop {
-            // self.before_sleep().unwrap();
-            self.io_event_loop
-                .process_io_events(Some(Duration::from_secs(1)))
-                .expect("no io event");
-            // self.after_sleep().unwrap();
+            self.process_events(AE_ALL_EVENTS | AE_CALL_BEFORE_SLEEP | AE_CALL_AFTER_SLEEP)
+                .expect("event loop failure");
         }
     }
+
+    /// One iteration of the event loop, the equivalent of `aeProcessEvents`.
+    ///
+    /// File events are waited for and handled, then due time events run. The
+    /// wait lasts until the nearest timer when time events are requested,
+    /// and does not happen at all with `AE_DONT_WAIT`, which lets callers pump
+    /// the loop one non-blocking step at a time. See `eventloop.rs` for the
+    /// meaning of every flag.
+    ///
+    /// Returns the number of events processed.
+    pub(crate) fn process_events(&mut self, flags: EventLoopFlag) -> io::Result<i64> {
+        // Nothing to do? return ASAP
+        if !is_all_event(flags) {
+            return Ok(0);
+        }
+
+        let mut processed = 0;
+
+        // The listener is always registered, so there is always something to
+        // poll for when file events are requested. Without them, only waiting
+        // for the next timer is left to do.
+        if is_file_event(flags) || (is_time_event(flags) && !is_dont_wait(flags)) {
+            if is_call_before_sleep(flags) {
+                self.before_sleep()?;
+            }
+
+            let timeout = if is_dont_wait(flags) {
+                Some(Duration::ZERO)
+            } else if is_time_event(flags) {
+                self.time_event_manager.until_earliest_timer()
+            } else {
+                None
+            };
+
+            if is_file_event(flags) {
+                self.io_event_loop.poll_io_events(timeout)?;
+            } else if let Some(timeout) = timeout {
+                // Readiness is edge-triggered: polling without handling what
+                // fired would lose those events, so just sleep instead.
+                thread::sleep(timeout);
+            }
+
+            if is_call_after_sleep(flags) {
+                self.after_sleep()?;
+            }
+
+            if is_file_event(flags) {
+                processed += self.io_event_loop.process_fired_io_events()?;
+            }
+        }
+
+        if is_time_event(flags) {
+            processed += self.time_event_manager.process_time_events()?;
+        }
+
+        Ok(processed)
+    }
 }
 
+impl EventLoop for SingleThreadEventLoop {
+    fn get_max_file_descriptor() -> i32 {
+        MioEventManager::EVENTS_SIZE as i32
+    }
+
+    /// Called right before the loop blocks in the poller. Clients blocked on
+    /// keys that became ready are served, then the replies produced while
+    /// handling the last batch of events are written out.
+    fn before_sleep(&mut self) -> io::Result<()> {
+        self.io_event_loop.handle_blocked_clients()?;
+        self.io_event_loop.handle_clients_with_pending_writes()?;
+        Ok(())
+    }
+
+    fn after_sleep(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
 
 impl core::lifecycle::construct::Constructive for SingleThreadEventLoop {
     type Instance = ();
@@ -0,0 +1,186 @@
+//! Clients blocked on keys, after Redis' `blocked.c`.
+//!
+//! A blocking command finding nothing to pop parks its client: the command
+//! is kept in the client's `BlockingState` and the client id is queued on
+//! each of its keys in `Db`. Setting one of these keys signals it as ready,
+//! and `handle_clients_blocked_on_keys` runs the command of the clients
+//! queued on it again, first blocked first served. A client still blocked
+//! when its timeout elapses is replied a null array by a time event.
+
+use std::time::Duration;
+use bytes::Bytes;
+use resp::protocol::Protocol;
+use crate::client::Client;
+use crate::command::Command;
+use crate::error::{RedisError, Result};
+use crate::eventloop::event::EventID;
+use crate::eventloop::time_event::{TimeEventManager, AE_NOMORE};
+use crate::server::RedisServer;
+use crate::util::{mstime, string2ld};
+
+/// What a blocked client waits for, Redis' `blockingState`.
+#[derive(Debug)]
+pub(crate) struct BlockingState {
+    db: usize,
+    keys: Vec<Bytes>,
+    // Type of the values the command pops, `list` or `zset`.
+    type_name: &'static str,
+    timeout_event: Option<EventID>,
+    // Command run again once one of the keys is ready.
+    command: Box<Command>,
+}
+
+/// Parse the timeout of a blocking command, in seconds, into the UNIX time
+/// in milliseconds it elapses at, `None` to block forever. Redis'
+/// `getTimeoutFromObjectOrReply`.
+pub(crate) fn parse_timeout(value: &[u8]) -> Result<Option<i64>> {
+    let timeout = string2ld(value).ok_or_else(|| RedisError::Err("timeout is not a float or out of range".to_string()))?;
+    let ms = (timeout * 1000.0).ceil();
+    if ms < 0.0 {
+        return Err(RedisError::Err("timeout is negative".to_string()));
+    }
+    if ms == 0.0 {
+        return Ok(None);
+    }
+    let now = mstime();
+    if ms >= (i64::MAX - now) as f64 {
+        return Err(RedisError::Err("timeout is out of range".to_string()));
+    }
+    Ok(Some(now + ms as i64))
+}
+
+/// Park `client` until one of `keys` holds a value of type `type_name`, or
+/// until `deadline`, Redis' `blockForKeys`. `command` runs again once a key
+/// is ready, and blocks again if another client was served first.
+pub(crate) fn block_for_keys(
+    server: &RedisServer,
+    client: &mut Client,
+    keys: Vec<Bytes>,
+    type_name: &'static str,
+    deadline: Option<i64>,
+    command: Command,
+) -> Result<()> {
+    let client_id = client.id();
+    let mut dbs = server.keyspace.lock();
+    let db = &mut dbs[client.db];
+    // A key given twice queues the client once.
+    let mut unique: Vec<Bytes> = Vec::with_capacity(keys.len());
+    for key in keys {
+        if !unique.contains(&key) {
+            db.block_client(key.clone(), client_id);
+            unique.push(key);
+        }
+    }
+    drop(dbs);
+
+    let timeout_event = match deadline {
+        Some(deadline) => {
+            let delay = Duration::from_millis((deadline - mstime()).max(0) as u64);
+            let timed_out_server = server.clone();
+            let proc = move |event| {
+                if let Err(err) = reply_timeout(&timed_out_server, client_id, event) {
+                    log::warn!("Failed to reply to a timed out client: {}", err);
+                }
+                AE_NOMORE
+            };
+            Some(server.time_event_manager.clone().create_time_event(delay, Box::new(proc), None)?)
+        }
+        None => None,
+    };
+
+    client.bstate = Some(BlockingState { db: client.db, keys: unique, type_name, timeout_event, command: Box::new(command) });
+    Ok(())
+}
+
+/// Take `client` out of the blocked state, Redis' `unblockClient`. Returns
+/// the command it was blocked in, `None` if it was not blocked.
+pub(crate) fn unblock_client(server: &RedisServer, client: &mut Client) -> Option<Command> {
+    let bstate = client.bstate.take()?;
+    let mut dbs = server.keyspace.lock();
+    for key in &bstate.keys {
+        dbs[bstate.db].unblock_client(key, client.id());
+    }
+    drop(dbs);
+
+    if let Some(event) = bstate.timeout_event {
+        server
+            .time_event_manager
+            .clone()
+            .delete_time_event(event)
+            .expect("the timeout of a blocked client is pending");
+    }
+    Some(*bstate.command)
+}
+
+/// Time event of a blocked client whose timeout elapsed, Redis'
+/// `replyToBlockedClientTimedOut`.
+fn reply_timeout(server: &RedisServer, client_id: usize, event: EventID) -> resp::Result<()> {
+    let Some(client) = server.client_manager.get_client(client_id) else {
+        return Ok(());
+    };
+    let mut client = client.lock().unwrap();
+    match client.bstate.as_mut() {
+        // The event is running, there is nothing left to delete.
+        Some(bstate) if bstate.timeout_event == Some(event) => bstate.timeout_event = None,
+        _ => return Ok(()),
+    }
+    unblock_client(server, &mut client);
+    client.connection.write_protocol(&Protocol::NullArray)?;
+
+    server.client_manager.add_pending_write(client_id);
+    server.client_manager.queue_unblocked_client(client_id);
+    Ok(())
+}
+
+/// Serve the clients blocked on the keys signaled as ready, Redis'
+/// `handleClientsBlockedOnKeys`.
+///
+/// The command of every client queued on a ready key runs again, as long as
+/// the key holds a value of the type it pops. Serving a client may make
+/// other keys ready, such as the destination of `BLMOVE`, so this goes on
+/// until no key is ready.
+pub(crate) fn handle_clients_blocked_on_keys(server: &RedisServer) {
+    loop {
+        let mut dbs = server.keyspace.lock();
+        let ready: Vec<(usize, Bytes, Vec<usize>)> = dbs
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(id, db)| db.take_ready_keys().into_iter().map(move |(key, clients)| (id, key, clients)))
+            .collect();
+        drop(dbs);
+        if ready.is_empty() {
+            break;
+        }
+
+        for (db, key, clients) in ready {
+            for client_id in clients {
+                let Some(client) = server.client_manager.get_client(client_id) else {
+                    continue;
+                };
+                let mut client = client.lock().unwrap();
+                let Some(type_name) = client.bstate.as_ref().filter(|bstate| bstate.db == db).map(|bstate| bstate.type_name)
+                else {
+                    continue;
+                };
+                // A client served earlier may have emptied the key.
+                if server.keyspace.lock()[db].lookup_key(&key).is_none_or(|value| value.type_name() != type_name) {
+                    continue;
+                }
+
+                let Some(command) = unblock_client(server, &mut client) else {
+                    continue;
+                };
+                // The command was counted when it blocked, it is run again
+                // without going through `call`, as Redis'
+                // `updateStatsOnUnblock` does.
+                let result = command.apply(server, &mut client);
+                if client.reply_result(result).is_err() {
+                    client.close_after_reply();
+                }
+
+                server.client_manager.add_pending_write(client_id);
+                server.client_manager.queue_unblocked_client(client_id);
+            }
+        }
+    }
+}
@@ -1,83 +1,381 @@
+use std::collections::VecDeque;
+use bytes::Bytes;
 use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use mio::net::TcpStream;
+use mio::{Registry, Token};
+use crate::blocked::{handle_clients_blocked_on_keys, BlockingState};
 use crate::command::Command;
-use ahash::AHashMap;
+use crate::error::RedisError;
+use ahash::{AHashMap, AHashSet};
 use crate::connection::Connection;
-use resp::Result;
+use crate::server::RedisServer;
+use resp::{Result, protocol::Protocol, request::RequestLimits};
 
-#[derive(Debug, Clone)]
+/// Classes of clients, each with its own output buffer limit as configured by
+/// the `client-output-buffer-limit` directive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ClientClass {
+    Normal,
+    Replica,
+    Pubsub,
+}
+
+impl ClientClass {
+    pub(crate) const COUNT: usize = 3;
+
+    /// Parse a class name as accepted by `client-output-buffer-limit`.
+    pub(crate) fn from_name(name: &str) -> Option<ClientClass> {
+        match &name.to_lowercase()[..] {
+            "normal" => Some(ClientClass::Normal),
+            "replica" | "slave" => Some(ClientClass::Replica),
+            "pubsub" => Some(ClientClass::Pubsub),
+            _ => None,
+        }
+    }
+}
+
+/// Output buffer limit of a client class.
+///
+/// A client is disconnected as soon as its output buffer reaches
+/// `hard_limit_bytes`, or once it has stayed at or above `soft_limit_bytes`
+/// for more than `soft_limit_seconds`. A limit of zero disables it.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ClientBufferLimit {
+    pub(crate) hard_limit_bytes: usize,
+    pub(crate) soft_limit_bytes: usize,
+    pub(crate) soft_limit_seconds: u64,
+}
+
+#[derive(Debug)]
 pub(crate) struct Client {
     client_id: usize,
     address: SocketAddr,
-    pub(crate) connection: Arc<Mutex<Connection>>,
-    // cmd: Option<Command>,
+    pub(crate) connection: Connection,
+    // Index of the selected database.
+    pub(crate) db: usize,
+    // Name set with `HELLO SETNAME`.
+    pub(crate) name: Option<Bytes>,
+    // Last time the client sent a command or received part of a reply, used
+    // to close idle clients.
+    last_interaction: Instant,
+    // Close the connection once the output buffer is written, Redis'
+    // `CLIENT_CLOSE_AFTER_REPLY`. Further commands are ignored meanwhile.
+    close_after_reply: bool,
+    // Set while the client waits in a blocking command. Commands sent
+    // meanwhile stay in the read buffer until it is unblocked.
+    pub(crate) bstate: Option<BlockingState>,
 }
 
 impl Client {
-    pub(crate) fn new(client_id: usize, conn: TcpStream, address: SocketAddr) -> Client {
+    pub(crate) fn new(
+        client_id: usize,
+        conn: TcpStream,
+        address: SocketAddr,
+        limits: RequestLimits,
+        output_buffer_limits: &[ClientBufferLimit; ClientClass::COUNT],
+    ) -> Client {
+        // Every client is a normal one, there are no replicas nor pub/sub
+        // clients yet.
+        let output_limit = output_buffer_limits[ClientClass::Normal as usize];
         Self {
             client_id,
             address,
-            connection: Arc::new(Mutex::new(Connection::new(conn))),
-            // cmd: None
+            connection: Connection::new(conn, limits, output_limit),
+            db: 0,
+            name: None,
+            last_interaction: Instant::now(),
+            close_after_reply: false,
+            bstate: None,
         }
     }
 
-    pub(crate) fn read_from_query(&mut self) -> Result<()> {
-        let protocol = match self.connection.lock().unwrap().read_protocol() {
-            Ok(op_protocol) => op_protocol,
-            Err(e) => return Result::Err(e),
-        };
+    pub(crate) fn id(&self) -> usize {
+        self.client_id
+    }
+
+    /// Serve everything the client has sent so far.
+    ///
+    /// The socket is drained first, then every complete command sitting in the
+    /// read buffer is parsed and executed in order. Replies pile up in the
+    /// connection's write buffer, the client's output buffer, which the event
+    /// loop flushes before going to sleep. A trailing partial command stays
+    /// buffered until the rest of it arrives.
+    ///
+    /// A malformed request is answered with a protocol error, after which
+    /// nothing more is read and the connection is closed once the error has
+    /// been written, as Redis' `setProtocolError` does.
+    ///
+    /// Returns `Err` once the connection should be dropped, either because it
+    /// failed, because the peer closed it or because its output buffer grew
+    /// past the limits of its class.
+    pub(crate) fn read_from_query(&mut self, server: &RedisServer) -> Result<()> {
+        if self.close_after_reply {
+            return Ok(());
+        }
+
+        let limit = server.config.client_max_querybuf_len;
+        let (read, open) = self.connection.fill_buffer(limit)?;
+        server.stats.lock().unwrap().net_input_bytes += read as u64;
+        if read > 0 {
+            self.last_interaction = Instant::now();
+        }
 
-        if let Some(prot) = protocol {
-            let command = Command::from_protocol(prot).unwrap();
-            command.apply(&mut self.connection.lock().unwrap()).unwrap();
-            return Result::Ok(())
+        if self.connection.query_buffer_len() > limit {
+            server.stats.lock().unwrap().client_query_buffer_limit_disconnections += 1;
+            log::warn!("Closing client that reached max query buffer length: {}", self.address);
+            return Err("query buffer limit reached".into());
+        }
+
+        self.process_input_buffer(server)?;
+
+        if open {
+            Ok(())
+        } else {
+            Err("connection closed by peer".into())
         }
-        return Result::Ok(())
     }
-}
 
-// impl Drop for Client {
-//     fn drop(&mut self) {
-//         todo!()
-//     }
-// }
+    /// Run every complete command sitting in the read buffer, Redis'
+    /// `processInputBuffer`. A blocking command stops the processing until
+    /// the client is unblocked.
+    pub(crate) fn process_input_buffer(&mut self, server: &RedisServer) -> Result<()> {
+        while !self.close_after_reply && self.bstate.is_none() {
+            let protocol = match self.connection.parse_protocol() {
+                Ok(Some(protocol)) => protocol,
+                Ok(None) => break,
+                Err(err) => {
+                    log::debug!("Protocol error ({}) from client: {}", err, self.address);
+                    self.connection.write_protocol(&Protocol::Error(format!("ERR {}", err)))?;
+                    self.close_after_reply = true;
+                    break;
+                }
+            };
+
+            // An empty request carries no command, Redis skips it.
+            if matches!(&protocol, Protocol::Array(parts) if parts.is_empty()) {
+                continue;
+            }
+
+            let result = Command::from_protocol(protocol).and_then(|command| self.call(server, command));
+            self.reply_result(result)?;
+
+            // The command may have set keys clients are blocked on.
+            handle_clients_blocked_on_keys(server);
+
+            self.check_output_buffer_limits(server)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns `Err` once the output buffer went past the limits of the
+    /// client's class, in which case the client must be closed.
+    pub(crate) fn check_output_buffer_limits(&mut self, server: &RedisServer) -> Result<()> {
+        if self.connection.output_buffer_limit_reached() {
+            server.stats.lock().unwrap().client_output_buffer_limit_disconnections += 1;
+            log::warn!("Client {} closed for overcoming of output buffer limits.", self.address);
+            return Err("output buffer limit reached".into());
+        }
+        Ok(())
+    }
+
+    /// Run `command` on behalf of the client, Redis' `call`.
+    pub(crate) fn call(&mut self, server: &RedisServer, command: Command) -> crate::error::Result<()> {
+        server.stats.lock().unwrap().total_commands_processed += 1;
+        command.apply(server, self)
+    }
+
+    /// Reply the error of a failed command. Only a failed connection is
+    /// returned, to close the client.
+    pub(crate) fn reply_result(&mut self, result: crate::error::Result<()>) -> Result<()> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(RedisError::Io(err)) => Err(err.into()),
+            Err(err) => Ok(self.connection.write_protocol(&err.into())?),
+        }
+    }
+
+    /// Close the connection once the output buffer is written.
+    pub(crate) fn close_after_reply(&mut self) {
+        self.close_after_reply = true;
+    }
+
+    /// Write as much of the output buffer as the socket accepts, like Redis'
+    /// `writeToClient`.
+    ///
+    /// Writable interest stays registered only while something is left to
+    /// write, otherwise the loop would be woken up on every poll.
+    ///
+    /// A client past its output buffer limits is closed instead, whatever
+    /// filled its buffer: its own commands, a blocking command served or
+    /// timed out, or `clients_cron` finding the soft limit expired.
+    pub(crate) fn write_to_client(&mut self, server: &RedisServer, registry: &Registry) -> Result<()> {
+        self.check_output_buffer_limits(server)?;
+        let (written, done) = self.connection.flush()?;
+        server.stats.lock().unwrap().net_output_bytes += written as u64;
+        if written > 0 {
+            self.last_interaction = Instant::now();
+        }
+
+        self.connection.set_writable_interest(registry, Token(self.client_id), !done)?;
+        if done && self.close_after_reply {
+            return Err("connection closed after reply".into());
+        }
+        Ok(())
+    }
 
+    /// Returns `true` if the output buffer holds replies not yet written.
+    pub(crate) fn has_pending_replies(&self) -> bool {
+        self.connection.has_pending_replies()
+    }
+}
 
 type ClientID = usize;
 #[derive(Debug, Clone)]
 pub(crate) struct ClientManager {
-    clients: Arc<Mutex<AHashMap<ClientID, Box<Client>>>>,
+    clients: Arc<Mutex<AHashMap<ClientID, Arc<Mutex<Client>>>>>,
+
+    // Clients that produced replies since the event loop last went to sleep.
+    // Their output buffers are flushed in the before-sleep phase.
+    clients_pending_write: Arc<Mutex<AHashSet<ClientID>>>,
+
+    // Order in which `clients_cron` visits the clients, round-robin. Ids of
+    // removed clients are dropped lazily when their turn comes.
+    clients_rotation: Arc<Mutex<VecDeque<ClientID>>>,
+
+    // Clients unblocked since the event loop last went to sleep, whose
+    // pipelined commands are yet to be processed.
+    unblocked_clients: Arc<Mutex<VecDeque<ClientID>>>,
+
+    // Output buffer limits, indexed by `ClientClass`.
+    output_buffer_limits: [ClientBufferLimit; ClientClass::COUNT],
+
+    // Limits on the requests of every client.
+    request_limits: RequestLimits,
 }
 
-impl Default for ClientManager {
-    fn default() -> Self {
+impl ClientManager {
+    pub(crate) fn new(output_buffer_limits: [ClientBufferLimit; ClientClass::COUNT], request_limits: RequestLimits) -> Self {
         Self {
-            clients: Arc::new(Mutex::new(AHashMap::new()))
+            clients: Arc::new(Mutex::new(AHashMap::new())),
+            clients_pending_write: Arc::new(Mutex::new(AHashSet::new())),
+            clients_rotation: Arc::new(Mutex::new(VecDeque::new())),
+            unblocked_clients: Arc::new(Mutex::new(VecDeque::new())),
+            output_buffer_limits,
+            request_limits,
         }
     }
-}
 
-impl ClientManager {
-
-    pub(crate) fn get_client(&mut self, client_id: usize) -> Option<Box<Client>> {
-        let mut binding = self.clients.lock().unwrap();
-        let client = binding.get_mut(&client_id);
-        return match client {
-            None => {None}
-            Some(c) => { Some(c.clone()) }
-        }
+    pub(crate) fn get_client(&self, client_id: usize) -> Option<Arc<Mutex<Client>>> {
+        let binding = self.clients.lock().unwrap();
+        binding.get(&client_id).cloned()
     }
 
-    pub(crate) fn create_client(&mut self, fd: usize, conn: TcpStream, address: SocketAddr) -> () {
-        let client = Box::new(Client::new(fd, conn, address));
+    pub(crate) fn create_client(&mut self, fd: usize, conn: TcpStream, address: SocketAddr) {
+        let client = Arc::new(Mutex::new(Client::new(fd, conn, address, self.request_limits, &self.output_buffer_limits)));
         self.clients.lock().unwrap().insert(fd, client);
+        self.clients_rotation.lock().unwrap().push_back(fd);
     }
 
-    pub(crate) fn remove_client(&mut self, client_id: ClientID) {
+    /// Remove a client, returning it so that it can be unblocked.
+    pub(crate) fn remove_client(&mut self, client_id: ClientID) -> Option<Arc<Mutex<Client>>> {
         let mut binding = self.clients.lock().unwrap();
-        binding.remove(&client_id);
+        self.clients_pending_write.lock().unwrap().remove(&client_id);
+        binding.remove(&client_id)
+    }
+
+    /// Number of clients currently connected.
+    pub(crate) fn connected_clients(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    /// Queue a client whose output buffer must be flushed before the event
+    /// loop goes back to sleep.
+    pub(crate) fn add_pending_write(&self, client_id: ClientID) {
+        self.clients_pending_write.lock().unwrap().insert(client_id);
+    }
+
+    /// Queue a client just unblocked, so that the commands it sent while
+    /// blocked are processed before the event loop goes back to sleep.
+    pub(crate) fn queue_unblocked_client(&self, client_id: ClientID) {
+        self.unblocked_clients.lock().unwrap().push_back(client_id);
+    }
+
+    /// Take the next client queued by `queue_unblocked_client`.
+    pub(crate) fn pop_unblocked_client(&mut self) -> Option<ClientID> {
+        self.unblocked_clients.lock().unwrap().pop_front()
     }
-}
\ No newline at end of file
+
+    /// Take the clients queued by `add_pending_write`, leaving the queue empty.
+    pub(crate) fn take_clients_pending_write(&mut self) -> Vec<ClientID> {
+        self.clients_pending_write.lock().unwrap().drain().collect()
+    }
+
+    /// Periodic housekeeping of the clients, Redis' `clientsCron`.
+    ///
+    /// Only a slice of the clients is visited per call, sized so that every
+    /// client is seen about once per second at the given `hz`, which keeps a
+    /// single cron tick short however many clients are connected. Clients
+    /// idle for longer than `max_idle` are closed, unless they are blocked.
+    /// The soft output buffer limit is checked again, for clients that stay
+    /// over it without being sent anything: the ones found past it are
+    /// queued for a write, which closes them.
+    ///
+    /// Returns the number of clients closed.
+    pub(crate) fn clients_cron(&mut self, hz: u32, max_idle: Option<Duration>) -> usize {
+        const CLIENTS_CRON_MIN_ITERATIONS: usize = 5;
+
+        let rotation = self.clients_rotation.clone();
+        let mut rotation = rotation.lock().unwrap();
+        let numclients = rotation.len();
+        let iterations = (numclients / hz as usize).max(numclients.min(CLIENTS_CRON_MIN_ITERATIONS));
+
+        let mut closed = 0;
+        for _ in 0..iterations {
+            let Some(client_id) = rotation.pop_front() else {
+                break;
+            };
+            let Some(client) = self.get_client(client_id) else {
+                continue;
+            };
+            rotation.push_back(client_id);
+
+            let mut client = client.lock().unwrap();
+            let idle = client.last_interaction.elapsed();
+            let blocked = client.bstate.is_some();
+            let limit_reached = client.connection.output_buffer_limit_reached();
+            drop(client);
+            if limit_reached {
+                self.add_pending_write(client_id);
+                continue;
+            }
+            if !blocked && max_idle.is_some_and(|max_idle| idle > max_idle) {
+                log::debug!("Closing idle client");
+                self.remove_client(client_id);
+                closed += 1;
+            }
+        }
+        closed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::RedisServerConfig;
+    use crate::testing::TestClient;
+
+    #[test]
+    fn query_buffer_limit() {
+        let config = RedisServerConfig { client_max_querybuf_len: 64 * 1024, ..RedisServerConfig::default() };
+        let mut client = TestClient::with_config(config);
+        assert_eq!(client.call(&["PING"]), "+PONG\r\n");
+
+        // Half of a big argument stays buffered until the rest arrives.
+        let mut request = b"*2\r\n$3\r\nSET\r\n$200000\r\n".to_vec();
+        request.resize(request.len() + 100 * 1024, b'x');
+        assert!(!client.send(&request));
+        assert_eq!(client.server.stats.lock().unwrap().client_query_buffer_limit_disconnections, 1);
+    }
+}
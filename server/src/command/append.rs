@@ -0,0 +1,63 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::{Bytes, BytesMut};
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::command::check_string_length;
+use crate::db::object::RedisObject;
+use crate::server::RedisServer;
+
+/// Append a value to the string of key, creating it if needed.
+#[derive(Debug)]
+pub struct Append {
+    key: Bytes,
+    value: Bytes,
+}
+
+impl Append {
+    /// Parse an `Append` instance from a received frame.
+    ///
+    /// The `APPEND` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// APPEND key value
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Append> {
+        let key = parse.next_bytes()?;
+        let value = parse.next_bytes()?;
+        Ok(Append { key, value })
+    }
+
+    /// Apply the `Append` command and reply with the new length of the string.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+
+        let response = match db.lookup_key(&self.key).map(|value| value.string_value()) {
+            Some(None) => RedisError::WrongType.into(),
+            None => {
+                let len = self.value.len();
+                db.set_key(self.key, RedisObject::string(self.value), false);
+                server.incr_dirty(1);
+                Protocol::Integer(len as i64)
+            }
+            Some(Some(old)) => match check_string_length(server, old.len() + self.value.len()) {
+                Err(err) => err.into(),
+                Ok(()) => {
+                    let mut value = BytesMut::with_capacity(old.len() + self.value.len());
+                    value.extend_from_slice(&old);
+                    value.extend_from_slice(&self.value);
+                    let len = value.len();
+                    db.set_key(self.key, RedisObject::RawString(value.freeze()), true);
+                    server.incr_dirty(1);
+                    Protocol::Integer(len as i64)
+                }
+            },
+        };
+        drop(dbs);
+
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
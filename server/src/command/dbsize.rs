@@ -0,0 +1,30 @@
+use resp::{protocol::Protocol, parse::Parser};
+use crate::client::Client;
+use crate::error::Result;
+use crate::server::RedisServer;
+
+/// Return the number of keys in the selected database.
+#[derive(Debug, Default)]
+pub struct DbSize;
+
+impl DbSize {
+    /// Parse a `DbSize` instance from a received frame.
+    ///
+    /// The `DBSIZE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// DBSIZE
+    /// ```
+    pub(crate) fn parse_frames(_parse: &mut Parser) -> Result<DbSize> {
+        Ok(DbSize)
+    }
+
+    /// Apply the `DbSize` command and reply to `dst`.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let size = server.keyspace.lock()[dst.db].len();
+        dst.connection.write_protocol(&Protocol::Integer(size as i64))?;
+        Ok(())
+    }
+}
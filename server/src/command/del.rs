@@ -0,0 +1,86 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::error::Result;
+use crate::server::RedisServer;
+
+/// Delete the given keys, `DEL` and `UNLINK`.
+///
+/// `UNLINK` frees the values in the background in Redis. Values are cheap to
+/// drop here, so both delete in place.
+#[derive(Debug)]
+pub struct Del {
+    keys: Vec<Bytes>,
+}
+
+impl Del {
+    /// Parse a `Del` instance from a received frame.
+    ///
+    /// The `DEL` or `UNLINK` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// DEL key [key ...]
+    /// UNLINK key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Del> {
+        let mut keys = vec![];
+        for key in parse.remaining_bytes() {
+            keys.push(key?);
+        }
+        Ok(Del { keys })
+    }
+
+    /// Apply the `Del` command and reply with the number of keys deleted.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+
+        // Expired keys are deleted by the lookup, and not counted.
+        let deleted = self.keys.iter().filter(|key| db.lookup_key(key).is_some() && db.delete_key(key)).count();
+        drop(dbs);
+        server.incr_dirty(deleted as u64);
+
+        dst.connection.write_protocol(&Protocol::Integer(deleted as i64))?;
+        Ok(())
+    }
+}
+
+/// Count how many of the given keys exist, `EXISTS`, or were accessed,
+/// `TOUCH`. A key given twice is counted twice.
+#[derive(Debug)]
+pub struct Exists {
+    keys: Vec<Bytes>,
+}
+
+impl Exists {
+    /// Parse an `Exists` instance from a received frame.
+    ///
+    /// The `EXISTS` or `TOUCH` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// EXISTS key [key ...]
+    /// TOUCH key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Exists> {
+        let mut keys = vec![];
+        for key in parse.remaining_bytes() {
+            keys.push(key?);
+        }
+        Ok(Exists { keys })
+    }
+
+    /// Apply the `Exists` command and reply with the count.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        let count = self.keys.iter().filter(|key| db.lookup_key(key).is_some()).count();
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Integer(count as i64))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::command::set::ExpireUnit;
+use crate::error::{RedisError, Result};
+use crate::server::RedisServer;
+use crate::util::{mstime, string2ll};
+
+/// Conditions of `EXPIRE` on the current time to live, `XX` combining with
+/// `GT` or `LT`.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct ExpireFlags {
+    /// `NX`, only if the key has no time to live.
+    nx: bool,
+    /// `XX`, only if the key has a time to live.
+    xx: bool,
+    /// `GT`, only if the new expire time is later. No time to live counts as
+    /// an infinite one.
+    gt: bool,
+    /// `LT`, only if the new expire time is sooner.
+    lt: bool,
+}
+
+impl ExpireFlags {
+    /// Parse `NX`, `XX`, `GT` or `LT`, the single condition `HEXPIRE`
+    /// accepts. Returns `None` for another option.
+    pub(crate) fn parse_one(option: &str) -> Option<ExpireFlags> {
+        let mut flags = ExpireFlags::default();
+        match &option.to_uppercase()[..] {
+            "NX" => flags.nx = true,
+            "XX" => flags.xx = true,
+            "GT" => flags.gt = true,
+            "LT" => flags.lt = true,
+            _ => return None,
+        }
+        Some(flags)
+    }
+
+    /// Whether the conditions allow replacing the `current` expire time with
+    /// `when`.
+    pub(crate) fn allow(self, current: Option<i64>, when: i64) -> bool {
+        !(self.nx && current.is_some()
+            || self.xx && current.is_none()
+            || self.gt && current.is_none_or(|current| when <= current)
+            || self.lt && current.is_some_and(|current| when >= current))
+    }
+}
+
+/// Set a time to live on key, `EXPIRE`, `PEXPIRE`, `EXPIREAT` and
+/// `PEXPIREAT`, Redis' `expireGenericCommand`.
+#[derive(Debug)]
+pub struct Expire {
+    key: Bytes,
+    /// UNIX time in milliseconds.
+    when: i64,
+    flags: ExpireFlags,
+}
+
+impl Expire {
+    /// Parse an `Expire` instance from a received frame.
+    ///
+    /// The command name has already been consumed and is given as `command`.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// EXPIRE key seconds [NX | XX | GT | LT]
+    /// PEXPIRE key milliseconds [NX | XX | GT | LT]
+    /// EXPIREAT key unix-time-seconds [NX | XX | GT | LT]
+    /// PEXPIREAT key unix-time-milliseconds [NX | XX | GT | LT]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, command: &str) -> Result<Expire> {
+        let key = parse.next_bytes()?;
+        let when = parse.next_bytes()?;
+
+        // Options are checked before the time, as Redis does.
+        let mut flags = ExpireFlags::default();
+        while parse.remaining() > 0 {
+            let option = parse.next_string()?;
+            match &option.to_uppercase()[..] {
+                "NX" => flags.nx = true,
+                "XX" => flags.xx = true,
+                "GT" => flags.gt = true,
+                "LT" => flags.lt = true,
+                _ => return Err(RedisError::Err(format!("Unsupported option {}", option))),
+            }
+        }
+        if flags.nx && (flags.xx || flags.gt || flags.lt) {
+            return Err(RedisError::Err("NX and XX, GT or LT options at the same time are not compatible".to_string()));
+        }
+        if flags.gt && flags.lt {
+            return Err(RedisError::Err("GT and LT options at the same time are not compatible".to_string()));
+        }
+
+        let when = string2ll(&when).ok_or_else(RedisError::not_integer)?;
+        let unit = if command.starts_with('p') { ExpireUnit::Milliseconds } else { ExpireUnit::Seconds };
+        let basetime = if command.ends_with("at") { 0 } else { mstime() };
+        // Unlike `SET`, times in the past are accepted and delete the key.
+        let when = match unit {
+            ExpireUnit::Seconds => when.checked_mul(1000),
+            ExpireUnit::Milliseconds => Some(when),
+        }
+        .and_then(|when| when.checked_add(basetime))
+        .ok_or_else(|| RedisError::Err(format!("invalid expire time in '{}' command", command)))?;
+
+        Ok(Expire { key, when, flags })
+    }
+
+    /// Apply the `Expire` command and reply 1 if the time to live was set, 0
+    /// if the key does not exist or a condition was not met.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+
+        let set = db.lookup_key(&self.key).is_some() && self.flags.allow(db.get_expire(&self.key), self.when);
+        if set {
+            if self.when <= mstime() {
+                db.delete_key(&self.key);
+            } else {
+                db.set_expire(self.key, self.when);
+            }
+            server.incr_dirty(1);
+        }
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Integer(set as i64))?;
+        Ok(())
+    }
+}
+
+/// Return the time to live of key, `TTL` and `PTTL`, or the time it expires
+/// at, `EXPIRETIME` and `PEXPIRETIME`, Redis' `ttlGenericCommand`.
+#[derive(Debug)]
+pub struct Ttl {
+    key: Bytes,
+    unit: ExpireUnit,
+    /// Reply the UNIX time the key expires at rather than the time left.
+    absolute: bool,
+}
+
+impl Ttl {
+    /// Parse a `Ttl` instance from a received frame.
+    ///
+    /// The command name has already been consumed and is given as `command`.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// TTL key
+    /// PTTL key
+    /// EXPIRETIME key
+    /// PEXPIRETIME key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, command: &str) -> Result<Ttl> {
+        let key = parse.next_bytes()?;
+        let unit = if command.starts_with('p') { ExpireUnit::Milliseconds } else { ExpireUnit::Seconds };
+        Ok(Ttl { key, unit, absolute: command.ends_with("time") })
+    }
+
+    /// Apply the `Ttl` command. Replies -2 if the key does not exist and -1
+    /// if it has no time to live.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+
+        let reply = match db.lookup_key(&self.key) {
+            None => -2,
+            Some(_) => match db.get_expire(&self.key) {
+                None => -1,
+                Some(when) if self.absolute => match self.unit {
+                    ExpireUnit::Seconds => when / 1000,
+                    ExpireUnit::Milliseconds => when,
+                },
+                Some(when) => {
+                    let ttl = (when - mstime()).max(0);
+                    match self.unit {
+                        // Rounded to the nearest second.
+                        ExpireUnit::Seconds => (ttl + 500) / 1000,
+                        ExpireUnit::Milliseconds => ttl,
+                    }
+                }
+            },
+        };
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Integer(reply))?;
+        Ok(())
+    }
+}
+
+/// Remove the time to live of key.
+#[derive(Debug)]
+pub struct Persist {
+    key: Bytes,
+}
+
+impl Persist {
+    /// Parse a `Persist` instance from a received frame.
+    ///
+    /// The `PERSIST` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// PERSIST key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Persist> {
+        let key = parse.next_bytes()?;
+        Ok(Persist { key })
+    }
+
+    /// Apply the `Persist` command and reply 1 if the key had a time to live,
+    /// 0 otherwise.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+
+        let removed = db.lookup_key(&self.key).is_some() && db.remove_expire(&self.key);
+        if removed {
+            server.incr_dirty(1);
+        }
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Integer(removed as i64))?;
+        Ok(())
+    }
+}
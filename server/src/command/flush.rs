@@ -0,0 +1,86 @@
+use resp::{protocol::Protocol, parse::Parser};
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::server::RedisServer;
+
+/// Parse the optional `ASYNC` / `SYNC` modifier shared by `FLUSHDB` and
+/// `FLUSHALL`.
+///
+/// Both modes free the keys right away: there is no lazy freeing thread.
+/// Returns `false` on an unknown modifier.
+fn parse_flush_mode(parse: &mut Parser) -> Result<bool> {
+    match parse.next_keyword()? {
+        Some(mode) => Ok(mode == "ASYNC" || mode == "SYNC"),
+        None => Ok(true),
+    }
+}
+
+/// Delete all the keys of the selected database.
+#[derive(Debug)]
+pub struct FlushDb {
+    valid_mode: bool,
+}
+
+impl FlushDb {
+    /// Parse a `FlushDb` instance from a received frame.
+    ///
+    /// The `FLUSHDB` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// FLUSHDB [ASYNC | SYNC]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<FlushDb> {
+        Ok(FlushDb { valid_mode: parse_flush_mode(parse)? })
+    }
+
+    /// Apply the `FlushDb` command and reply to `dst`.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let response = if self.valid_mode {
+            let removed = server.keyspace.lock()[dst.db].flush();
+            server.incr_dirty(removed as u64);
+            Protocol::Simple("OK".to_string())
+        } else {
+            RedisError::syntax().into()
+        };
+
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
+
+/// Delete all the keys of every database.
+#[derive(Debug)]
+pub struct FlushAll {
+    valid_mode: bool,
+}
+
+impl FlushAll {
+    /// Parse a `FlushAll` instance from a received frame.
+    ///
+    /// The `FLUSHALL` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// FLUSHALL [ASYNC | SYNC]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<FlushAll> {
+        Ok(FlushAll { valid_mode: parse_flush_mode(parse)? })
+    }
+
+    /// Apply the `FlushAll` command and reply to `dst`.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let response = if self.valid_mode {
+            let removed: usize = server.keyspace.lock().iter_mut().map(|db| db.flush()).sum();
+            server.incr_dirty(removed as u64);
+            Protocol::Simple("OK".to_string())
+        } else {
+            RedisError::syntax().into()
+        };
+
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::command::set::{parse_expire_time, ExpireUnit};
+use crate::server::RedisServer;
+use crate::util::mstime;
+
+/// Reply for the string value of a key: the value, nil for a missing key or
+/// an error if the key holds another type.
+fn string_reply(value: Option<Option<Bytes>>) -> Protocol {
+    match value {
+        None => Protocol::Null,
+        Some(None) => RedisError::WrongType.into(),
+        Some(Some(value)) => Protocol::Bulk(value),
+    }
+}
+
+/// Get the value of key.
+#[derive(Debug)]
+pub struct Get {
+    key: Bytes,
+}
+
+impl Get {
+    /// Parse a `Get` instance from a received frame.
+    ///
+    /// The `GET` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// GET key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Get> {
+        let key = parse.next_bytes()?;
+        Ok(Get { key })
+    }
+
+    /// Apply the `Get` command and reply with the value.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let value = server.keyspace.lock()[dst.db].lookup_key(&self.key).map(|value| value.string_value());
+        dst.connection.write_protocol(&string_reply(value))?;
+        Ok(())
+    }
+}
+
+/// Get the value of key and delete the key.
+#[derive(Debug)]
+pub struct GetDel {
+    key: Bytes,
+}
+
+impl GetDel {
+    /// Parse a `GetDel` instance from a received frame.
+    ///
+    /// The `GETDEL` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// GETDEL key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<GetDel> {
+        let key = parse.next_bytes()?;
+        Ok(GetDel { key })
+    }
+
+    /// Apply the `GetDel` command and reply with the value, which is only
+    /// deleted if it is a string.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        let value = db.lookup_key(&self.key).map(|value| value.string_value());
+        if let Some(Some(_)) = value {
+            db.delete_key(&self.key);
+            server.incr_dirty(1);
+        }
+        drop(dbs);
+
+        dst.connection.write_protocol(&string_reply(value))?;
+        Ok(())
+    }
+}
+
+/// What `GETEX` does to the time to live of the key.
+#[derive(Debug)]
+enum GetExAction {
+    None,
+    /// expire at this UNIX time in milliseconds
+    Expire(i64),
+    /// `PERSIST`
+    Persist,
+}
+
+/// Get the value of key and optionally set its expiration.
+#[derive(Debug)]
+pub struct GetEx {
+    key: Bytes,
+    action: GetExAction,
+}
+
+impl GetEx {
+    /// Parse a `GetEx` instance from a received frame.
+    ///
+    /// The `GETEX` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// GETEX key [EX seconds | PX milliseconds | EXAT unix-time-seconds |
+    ///   PXAT unix-time-milliseconds | PERSIST]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<GetEx> {
+        let key = parse.next_bytes()?;
+        let mut action = GetExAction::None;
+
+        while let Some(option) = parse.next_keyword()? {
+            match (&option[..], &action) {
+                ("PERSIST", GetExAction::None) => action = GetExAction::Persist,
+                ("EX" | "PX" | "EXAT" | "PXAT", GetExAction::None) if parse.remaining() > 0 => {
+                    let unit = if option.starts_with('E') { ExpireUnit::Seconds } else { ExpireUnit::Milliseconds };
+                    action = GetExAction::Expire(parse_expire_time(parse.next_i64()?, unit, option.ends_with("AT"), "getex")?);
+                }
+                _ => return Err(RedisError::syntax()),
+            }
+        }
+
+        Ok(GetEx { key, action })
+    }
+
+    /// Apply the `GetEx` command and reply with the value. The time to live
+    /// is only changed if the key holds a string; an expire time already in
+    /// the past deletes the key.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        let value = db.lookup_key(&self.key).map(|value| value.string_value());
+
+        if let Some(Some(_)) = value {
+            match self.action {
+                GetExAction::Expire(when) if when <= mstime() => {
+                    db.delete_key(&self.key);
+                    server.incr_dirty(1);
+                }
+                GetExAction::Expire(when) => {
+                    db.set_expire(self.key, when);
+                    server.incr_dirty(1);
+                }
+                GetExAction::Persist => {
+                    if db.remove_expire(&self.key) {
+                        server.incr_dirty(1);
+                    }
+                }
+                GetExAction::None => {}
+            }
+        }
+        drop(dbs);
+
+        dst.connection.write_protocol(&string_reply(value))?;
+        Ok(())
+    }
+}
+
+/// Get the values of all the given keys, nil for the missing ones and the ones
+/// not holding a string.
+#[derive(Debug)]
+pub struct MGet {
+    keys: Vec<Bytes>,
+}
+
+impl MGet {
+    /// Parse a `MGet` instance from a received frame.
+    ///
+    /// The `MGET` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// MGET key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<MGet> {
+        let mut keys = vec![parse.next_bytes()?];
+        for key in parse.remaining_bytes() {
+            keys.push(key?);
+        }
+        Ok(MGet { keys })
+    }
+
+    /// Apply the `MGet` command and reply with an array of the values.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        let values = self
+            .keys
+            .iter()
+            .map(|key| match db.lookup_key(key).and_then(|value| value.string_value()) {
+                Some(value) => Protocol::Bulk(value),
+                None => Protocol::Null,
+            })
+            .collect();
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Array(values))?;
+        Ok(())
+    }
+}
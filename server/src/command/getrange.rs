@@ -0,0 +1,61 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::server::RedisServer;
+
+/// Return the substring of the string value of key between the `start` and
+/// `end` offsets, both inclusive. Negative offsets count from the end.
+#[derive(Debug)]
+pub struct GetRange {
+    key: Bytes,
+    start: i64,
+    end: i64,
+}
+
+impl GetRange {
+    /// Parse a `GetRange` instance from a received frame.
+    ///
+    /// The `GETRANGE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// GETRANGE key start end
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<GetRange> {
+        let key = parse.next_bytes()?;
+        let start = parse.next_i64()?;
+        let end = parse.next_i64()?;
+        Ok(GetRange { key, start, end })
+    }
+
+    /// Apply the `GetRange` command and reply with the substring, empty for a
+    /// missing key or an empty range.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let value = server.keyspace.lock()[dst.db].lookup_key(&self.key).map(|value| value.string_value());
+        let response = match value {
+            None => Protocol::Bulk(Bytes::new()),
+            Some(None) => RedisError::WrongType.into(),
+            Some(Some(value)) => Protocol::Bulk(Self::substring(value, self.start, self.end)),
+        };
+
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+
+    /// Clamp the offsets the way Redis' `getrangeCommand` does.
+    fn substring(value: Bytes, start: i64, end: i64) -> Bytes {
+        let len = value.len() as i64;
+        if (start < 0 && end < 0 && start > end) || len == 0 {
+            return Bytes::new();
+        }
+
+        let start = if start < 0 { (len + start).max(0) } else { start };
+        let end = if end < 0 { (len + end).max(0) } else { end.min(len - 1) };
+        if start > end {
+            return Bytes::new();
+        }
+        value.slice(start as usize..=end as usize)
+    }
+}
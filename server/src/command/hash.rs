@@ -0,0 +1,856 @@
+use resp::{protocol::{Protocol, ProtocolVersion}, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::command::expire::ExpireFlags;
+use crate::command::keys::ScanOptions;
+use crate::command::set::ExpireUnit;
+use crate::db::{hash::Hash, keyspace::Db, object::RedisObject};
+use crate::error::{RedisError, Result};
+use crate::server::RedisServer;
+use crate::util::{add_floats, mstime, random, string2ld, string2ll};
+
+/// The hash stored at key, `None` if the key does not exist.
+fn lookup_hash<'a>(db: &'a mut Db, key: &[u8]) -> Result<Option<&'a mut Hash>> {
+    match db.lookup_key(key) {
+        None => Ok(None),
+        Some(RedisObject::Hash(hash)) => Ok(Some(hash)),
+        Some(_) => Err(RedisError::WrongType),
+    }
+}
+
+/// The hash stored at key, created empty if the key does not exist.
+fn lookup_or_create_hash<'a>(server: &RedisServer, db: &'a mut Db, key: &Bytes) -> Result<&'a mut Hash> {
+    if lookup_hash(db, key)?.is_none() {
+        let hash = Hash::new(server.config.hash_max_listpack_entries, server.config.hash_max_listpack_value);
+        db.set_key(key.clone(), RedisObject::Hash(hash), false);
+    }
+    Ok(lookup_hash(db, key)?.expect("the hash was just created"))
+}
+
+/// Fields and their values as an array of `[field, value]` arrays in RESP3,
+/// flattened in RESP2.
+fn field_value_pairs(entries: Vec<(Bytes, Bytes)>, version: ProtocolVersion) -> Protocol {
+    match version {
+        ProtocolVersion::Resp3 => Protocol::Array(
+            entries
+                .into_iter()
+                .map(|(field, value)| Protocol::Array(vec![Protocol::Bulk(field), Protocol::Bulk(value)]))
+                .collect(),
+        ),
+        ProtocolVersion::Resp2 => Protocol::Array(
+            entries.into_iter().flat_map(|(field, value)| [Protocol::Bulk(field), Protocol::Bulk(value)]).collect(),
+        ),
+    }
+}
+
+/// Set fields of a hash, `HSET`, or a field only if it does not exist yet,
+/// `HSETNX`.
+#[derive(Debug)]
+pub struct HSet {
+    key: Bytes,
+    entries: Vec<(Bytes, Bytes)>,
+    nx: bool,
+}
+
+impl HSet {
+    /// Parse a `HSet` instance from a received frame.
+    ///
+    /// The `HSET` or `HSETNX` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HSET key field value [field value ...]
+    /// HSETNX key field value
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, nx: bool) -> Result<HSet> {
+        let key = parse.next_bytes()?;
+        if parse.remaining() == 0 || !parse.remaining().is_multiple_of(2) {
+            return Err(RedisError::WrongArity(String::new()));
+        }
+        let mut entries = Vec::with_capacity(parse.remaining() / 2);
+        while parse.remaining() > 0 {
+            entries.push((parse.next_bytes()?, parse.next_bytes()?));
+        }
+        Ok(HSet { key, entries, nx })
+    }
+
+    /// Apply the `HSet` command and reply with the number of fields added,
+    /// or whether the field was set for `HSETNX`.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        if self.nx && lookup_hash(db, &self.key)?.is_some_and(|hash| hash.contains(&self.entries[0].0)) {
+            drop(dbs);
+            dst.connection.write_protocol(&Protocol::Integer(0))?;
+            return Ok(());
+        }
+
+        let hash = lookup_or_create_hash(server, db, &self.key)?;
+        let changes = self.entries.len();
+        let mut added = 0;
+        for (field, value) in self.entries {
+            if hash.insert(field, value, false) {
+                added += 1;
+            }
+        }
+        drop(dbs);
+        server.incr_dirty(changes as u64);
+
+        dst.connection.write_protocol(&Protocol::Integer(added))?;
+        Ok(())
+    }
+}
+
+/// Return the value of a field of a hash.
+#[derive(Debug)]
+pub struct HGet {
+    key: Bytes,
+    field: Bytes,
+}
+
+impl HGet {
+    /// Parse a `HGet` instance from a received frame.
+    ///
+    /// The `HGET` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HGET key field
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<HGet> {
+        let key = parse.next_bytes()?;
+        let field = parse.next_bytes()?;
+        Ok(HGet { key, field })
+    }
+
+    /// Apply the `HGet` command and reply with the value, nil if the key or
+    /// the field does not exist.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let value = lookup_hash(&mut server.keyspace.lock()[dst.db], &self.key)?.and_then(|hash| hash.get(&self.field));
+        dst.connection.write_protocol(&value.map_or(Protocol::Null, Protocol::Bulk))?;
+        Ok(())
+    }
+}
+
+/// Return the values of fields of a hash.
+#[derive(Debug)]
+pub struct HMGet {
+    key: Bytes,
+    fields: Vec<Bytes>,
+}
+
+impl HMGet {
+    /// Parse a `HMGet` instance from a received frame.
+    ///
+    /// The `HMGET` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HMGET key field [field ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<HMGet> {
+        let key = parse.next_bytes()?;
+        let mut fields = vec![parse.next_bytes()?];
+        for field in parse.remaining_bytes() {
+            fields.push(field?);
+        }
+        Ok(HMGet { key, fields })
+    }
+
+    /// Apply the `HMGet` command and reply with the values, nil for the
+    /// fields that do not exist.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let hash = lookup_hash(&mut dbs[dst.db], &self.key)?;
+        let values = self
+            .fields
+            .iter()
+            .map(|field| hash.as_ref().and_then(|hash| hash.get(field)).map_or(Protocol::Null, Protocol::Bulk))
+            .collect();
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Array(values))?;
+        Ok(())
+    }
+}
+
+/// Remove fields from a hash.
+#[derive(Debug)]
+pub struct HDel {
+    key: Bytes,
+    fields: Vec<Bytes>,
+}
+
+impl HDel {
+    /// Parse a `HDel` instance from a received frame.
+    ///
+    /// The `HDEL` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HDEL key field [field ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<HDel> {
+        let key = parse.next_bytes()?;
+        let mut fields = vec![parse.next_bytes()?];
+        for field in parse.remaining_bytes() {
+            fields.push(field?);
+        }
+        Ok(HDel { key, fields })
+    }
+
+    /// Apply the `HDel` command and reply with the number of fields removed.
+    /// The key is deleted with its last field.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        let mut removed = 0;
+        if let Some(hash) = lookup_hash(db, &self.key)? {
+            removed = self.fields.iter().filter(|field| hash.remove(field)).count();
+            if hash.is_empty() {
+                db.delete_key(&self.key);
+            }
+        }
+        drop(dbs);
+        server.incr_dirty(removed as u64);
+
+        dst.connection.write_protocol(&Protocol::Integer(removed as i64))?;
+        Ok(())
+    }
+}
+
+/// Determine whether a field exists in a hash.
+#[derive(Debug)]
+pub struct HExists {
+    key: Bytes,
+    field: Bytes,
+}
+
+impl HExists {
+    /// Parse a `HExists` instance from a received frame.
+    ///
+    /// The `HEXISTS` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HEXISTS key field
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<HExists> {
+        let key = parse.next_bytes()?;
+        let field = parse.next_bytes()?;
+        Ok(HExists { key, field })
+    }
+
+    /// Apply the `HExists` command and reply with 1 if the field exists, 0
+    /// otherwise.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let exists = lookup_hash(&mut server.keyspace.lock()[dst.db], &self.key)?.is_some_and(|hash| hash.contains(&self.field));
+        dst.connection.write_protocol(&Protocol::Integer(exists as i64))?;
+        Ok(())
+    }
+}
+
+/// Return the number of fields of a hash.
+#[derive(Debug)]
+pub struct HLen {
+    key: Bytes,
+}
+
+impl HLen {
+    /// Parse a `HLen` instance from a received frame.
+    ///
+    /// The `HLEN` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HLEN key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<HLen> {
+        let key = parse.next_bytes()?;
+        Ok(HLen { key })
+    }
+
+    /// Apply the `HLen` command and reply with the number of fields, 0 if the
+    /// key does not exist.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let len = lookup_hash(&mut server.keyspace.lock()[dst.db], &self.key)?.map_or(0, |hash| hash.len());
+        dst.connection.write_protocol(&Protocol::Integer(len as i64))?;
+        Ok(())
+    }
+}
+
+/// Return the fields, `HKEYS`, the values, `HVALS`, or both, `HGETALL`, of
+/// a hash.
+#[derive(Debug)]
+pub struct HGetAll {
+    key: Bytes,
+    fields: bool,
+    values: bool,
+}
+
+impl HGetAll {
+    /// Parse a `HGetAll` instance from a received frame.
+    ///
+    /// The `HKEYS`, `HVALS` or `HGETALL` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HKEYS key
+    /// HVALS key
+    /// HGETALL key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, fields: bool, values: bool) -> Result<HGetAll> {
+        let key = parse.next_bytes()?;
+        Ok(HGetAll { key, fields, values })
+    }
+
+    /// Apply the `HGetAll` command and reply with an array of the fields or
+    /// the values, or a map of the fields to their values for `HGETALL`.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let entries: Vec<(Bytes, Bytes)> =
+            lookup_hash(&mut dbs[dst.db], &self.key)?.map(|hash| hash.iter().collect()).unwrap_or_default();
+        drop(dbs);
+
+        let response = match (self.fields, self.values) {
+            (true, true) => Protocol::Map(
+                entries.into_iter().map(|(field, value)| (Protocol::Bulk(field), Protocol::Bulk(value))).collect(),
+            ),
+            (true, false) => Protocol::Array(entries.into_iter().map(|(field, _)| Protocol::Bulk(field)).collect()),
+            _ => Protocol::Array(entries.into_iter().map(|(_, value)| Protocol::Bulk(value)).collect()),
+        };
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
+
+/// Increment the integer value of a field of a hash. A missing field is
+/// taken as 0.
+#[derive(Debug)]
+pub struct HIncrBy {
+    key: Bytes,
+    field: Bytes,
+    increment: i64,
+}
+
+impl HIncrBy {
+    /// Parse a `HIncrBy` instance from a received frame.
+    ///
+    /// The `HINCRBY` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HINCRBY key field increment
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<HIncrBy> {
+        let key = parse.next_bytes()?;
+        let field = parse.next_bytes()?;
+        let increment = parse.next_i64()?;
+        Ok(HIncrBy { key, field, increment })
+    }
+
+    /// Apply the `HIncrBy` command and reply with the new value.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        let current = match lookup_hash(db, &self.key)?.and_then(|hash| hash.get(&self.field)) {
+            Some(value) => string2ll(&value).ok_or_else(|| RedisError::Err("hash value is not an integer".to_string()))?,
+            None => 0,
+        };
+        let new = current
+            .checked_add(self.increment)
+            .ok_or_else(|| RedisError::Err("increment or decrement would overflow".to_string()))?;
+        // The field keeps its time to live.
+        lookup_or_create_hash(server, db, &self.key)?.insert(self.field, Bytes::from(new.to_string()), true);
+        drop(dbs);
+        server.incr_dirty(1);
+
+        dst.connection.write_protocol(&Protocol::Integer(new))?;
+        Ok(())
+    }
+}
+
+/// Increment the floating point value of a field of a hash. A missing field
+/// is taken as 0.
+#[derive(Debug)]
+pub struct HIncrByFloat {
+    key: Bytes,
+    field: Bytes,
+    increment: Bytes,
+}
+
+impl HIncrByFloat {
+    /// Parse a `HIncrByFloat` instance from a received frame.
+    ///
+    /// The `HINCRBYFLOAT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HINCRBYFLOAT key field increment
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<HIncrByFloat> {
+        let key = parse.next_bytes()?;
+        let field = parse.next_bytes()?;
+        let increment = parse.next_bytes()?;
+        string2ld(&increment).ok_or_else(RedisError::not_float)?;
+        Ok(HIncrByFloat { key, field, increment })
+    }
+
+    /// Apply the `HIncrByFloat` command and reply with the new value as a
+    /// bulk string. The value is stored in its human readable form.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        let current = match lookup_hash(db, &self.key)?.and_then(|hash| hash.get(&self.field)) {
+            Some(value) => string2ld(&value)
+                .map(|_| value)
+                .ok_or_else(|| RedisError::Err("hash value is not a float".to_string()))?,
+            None => Bytes::from_static(b"0"),
+        };
+        let new = add_floats(&current, &self.increment)
+            .ok_or_else(|| RedisError::Err("increment would produce NaN or Infinity".to_string()))?;
+        let new = Bytes::from(new);
+        lookup_or_create_hash(server, db, &self.key)?.insert(self.field, new.clone(), true);
+        drop(dbs);
+        server.incr_dirty(1);
+
+        dst.connection.write_protocol(&Protocol::Bulk(new))?;
+        Ok(())
+    }
+}
+
+/// Return the length of the value of a field of a hash.
+#[derive(Debug)]
+pub struct HStrlen {
+    key: Bytes,
+    field: Bytes,
+}
+
+impl HStrlen {
+    /// Parse a `HStrlen` instance from a received frame.
+    ///
+    /// The `HSTRLEN` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HSTRLEN key field
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<HStrlen> {
+        let key = parse.next_bytes()?;
+        let field = parse.next_bytes()?;
+        Ok(HStrlen { key, field })
+    }
+
+    /// Apply the `HStrlen` command and reply with the length, 0 if the key or
+    /// the field does not exist.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let len = lookup_hash(&mut server.keyspace.lock()[dst.db], &self.key)?
+            .and_then(|hash| hash.get(&self.field))
+            .map_or(0, |value| value.len());
+        dst.connection.write_protocol(&Protocol::Integer(len as i64))?;
+        Ok(())
+    }
+}
+
+/// Return random fields of a hash.
+#[derive(Debug)]
+pub struct HRandField {
+    key: Bytes,
+    count: Option<i64>,
+    withvalues: bool,
+}
+
+impl HRandField {
+    /// Parse a `HRandField` instance from a received frame.
+    ///
+    /// The `HRANDFIELD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HRANDFIELD key [count [WITHVALUES]]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<HRandField> {
+        let key = parse.next_bytes()?;
+        let count = if parse.remaining() > 0 { Some(parse.next_i64()?) } else { None };
+        let withvalues = match parse.remaining() {
+            0 => false,
+            1 if parse.next_if_keyword("WITHVALUES") => true,
+            _ => return Err(RedisError::syntax()),
+        };
+        // The reply of a negative count holds that many fields, and as many
+        // values.
+        if count.is_some_and(|count| count < -i64::MAX / if withvalues { 2 } else { 1 }) {
+            return Err(RedisError::Err("value is out of range".to_string()));
+        }
+        Ok(HRandField { key, count, withvalues })
+    }
+
+    /// Apply the `HRandField` command and reply with a random field, or an
+    /// array of `count` fields, distinct if `count` is positive and possibly
+    /// repeated if negative.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let hash = lookup_hash(&mut dbs[dst.db], &self.key)?;
+        let Some(count) = self.count else {
+            let field = hash.and_then(|hash| hash.random_entry()).map(|(field, _)| field);
+            drop(dbs);
+            dst.connection.write_protocol(&field.map_or(Protocol::Null, Protocol::Bulk))?;
+            return Ok(());
+        };
+
+        let entries: Vec<(Bytes, Bytes)> = match hash {
+            None => vec![],
+            Some(hash) if count < 0 => {
+                (0..count.unsigned_abs()).filter_map(|_| hash.random_entry()).collect()
+            }
+            Some(hash) => {
+                // Shuffle the first `count` entries in, Fisher-Yates.
+                let mut entries: Vec<(Bytes, Bytes)> = hash.iter().collect();
+                let count = entries.len().min(count as usize);
+                for i in 0..count {
+                    let j = i + random() as usize % (entries.len() - i);
+                    entries.swap(i, j);
+                }
+                entries.truncate(count);
+                entries
+            }
+        };
+        drop(dbs);
+
+        let response = if self.withvalues {
+            field_value_pairs(entries, dst.connection.version())
+        } else {
+            Protocol::Array(entries.into_iter().map(|(field, _)| Protocol::Bulk(field)).collect())
+        };
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
+
+/// Incrementally iterate the fields of a hash, with their values.
+#[derive(Debug)]
+pub struct HScan {
+    key: Bytes,
+    options: ScanOptions,
+    novalues: bool,
+}
+
+impl HScan {
+    /// Parse a `HScan` instance from a received frame.
+    ///
+    /// The `HSCAN` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HSCAN key cursor [MATCH pattern] [COUNT count] [NOVALUES]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<HScan> {
+        let key = parse.next_bytes()?;
+        let mut scan = HScan { key, options: ScanOptions::parse_cursor(parse)?, novalues: false };
+
+        while let Some(option) = parse.next_keyword()? {
+            match &option[..] {
+                "NOVALUES" => scan.novalues = true,
+                _ if scan.options.parse_option(&option, parse)? => {}
+                _ => return Err(RedisError::syntax()),
+            }
+        }
+
+        Ok(scan)
+    }
+
+    /// Apply the `HScan` command and reply with the next cursor and a batch
+    /// of fields, each followed by its value unless `NOVALUES` is given. A
+    /// hash still encoded as a listpack is returned in a single batch.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let mut elements = vec![];
+        let mut cursor = 0;
+        if let Some(hash) = lookup_hash(&mut dbs[dst.db], &self.key)? {
+            cursor = self.options.cursor;
+            let mut max_iterations = self.options.max_iterations();
+            loop {
+                cursor = hash.scan(cursor, |field, value| {
+                    if self.options.matches(field) {
+                        elements.push(Protocol::Bulk(field.clone()));
+                        if !self.novalues {
+                            elements.push(Protocol::Bulk(value.clone()));
+                        }
+                    }
+                });
+                max_iterations -= 1;
+                if cursor == 0 || max_iterations == 0 || elements.len() >= self.options.count {
+                    break;
+                }
+            }
+        }
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Array(vec![
+            Protocol::Bulk(Bytes::from(cursor.to_string())),
+            Protocol::Array(elements),
+        ]))?;
+        Ok(())
+    }
+}
+
+/// Latest UNIX time in milliseconds a field may expire at, Redis'
+/// `HFE_MAX_ABS_TIME_MSEC`.
+const HFE_MAX_ABS_TIME_MSEC: i64 = ((1 << 48) - 1) >> 2;
+
+/// Parse the `FIELDS numfields field [field ...]` ending the commands on the
+/// time to live of fields.
+fn parse_fields(parse: &mut Parser) -> Result<Vec<Bytes>> {
+    if !parse.next_if_keyword("FIELDS") {
+        return Err(RedisError::Err("Mandatory argument FIELDS is missing or not at the right position".to_string()));
+    }
+    let numfields = parse
+        .next_i64()
+        .ok()
+        .and_then(|numfields| usize::try_from(numfields).ok())
+        .filter(|numfields| *numfields > 0)
+        .ok_or_else(|| RedisError::Err("Parameter `numFields` should be greater than 0".to_string()))?;
+    if numfields != parse.remaining() {
+        return Err(RedisError::Err("The `numfields` parameter must match the number of arguments".to_string()));
+    }
+    let mut fields = Vec::with_capacity(numfields);
+    for field in parse.remaining_bytes() {
+        fields.push(field?);
+    }
+    Ok(fields)
+}
+
+/// Reply to the commands on the time to live of fields of a missing key: -2
+/// for every field, as for a missing field.
+fn no_such_fields(fields: &[Bytes]) -> Protocol {
+    Protocol::Array(fields.iter().map(|_| Protocol::Integer(-2)).collect())
+}
+
+/// Set a time to live on fields of a hash, `HEXPIRE`, `HPEXPIRE`,
+/// `HEXPIREAT` and `HPEXPIREAT`, Redis' `hexpireGenericCommand`.
+#[derive(Debug)]
+pub struct HExpire {
+    key: Bytes,
+    /// UNIX time in milliseconds.
+    when: i64,
+    flags: ExpireFlags,
+    fields: Vec<Bytes>,
+}
+
+impl HExpire {
+    /// Parse a `HExpire` instance from a received frame.
+    ///
+    /// The command name has already been consumed and is given as `command`.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HEXPIRE key seconds [NX | XX | GT | LT] FIELDS numfields field [field ...]
+    /// HPEXPIRE key milliseconds [NX | XX | GT | LT] FIELDS numfields field [field ...]
+    /// HEXPIREAT key unix-time-seconds [NX | XX | GT | LT] FIELDS numfields field [field ...]
+    /// HPEXPIREAT key unix-time-milliseconds [NX | XX | GT | LT] FIELDS numfields field [field ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, command: &str) -> Result<HExpire> {
+        let key = parse.next_bytes()?;
+        let when = parse.next_i64()?;
+        if when < 0 {
+            return Err(RedisError::Err("invalid expire time, must be >= 0".to_string()));
+        }
+        let invalid = || RedisError::Err(format!("invalid expire time in '{}' command", command));
+        let when = match command.starts_with("hp") {
+            true => when,
+            false if when > HFE_MAX_ABS_TIME_MSEC / 1000 => return Err(invalid()),
+            false => when * 1000,
+        };
+        let basetime = if command.ends_with("at") { 0 } else { mstime() };
+        if when > HFE_MAX_ABS_TIME_MSEC - basetime {
+            return Err(invalid());
+        }
+
+        let flags = ["NX", "XX", "GT", "LT"]
+            .into_iter()
+            .find(|option| parse.next_if_keyword(option))
+            .and_then(ExpireFlags::parse_one)
+            .unwrap_or_default();
+        let fields = parse_fields(parse)?;
+        Ok(HExpire { key, when: when + basetime, flags, fields })
+    }
+
+    /// Apply the `HExpire` command and reply for every field: -2 if it does
+    /// not exist, 0 if a condition was not met, 1 if the time to live was
+    /// set, and 2 if the field was deleted as the time is in the past.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        let Some(hash) = lookup_hash(db, &self.key)? else {
+            drop(dbs);
+            dst.connection.write_protocol(&no_such_fields(&self.fields))?;
+            return Ok(());
+        };
+
+        let now = mstime();
+        let mut replies = Vec::with_capacity(self.fields.len());
+        let mut changes = 0;
+        for field in self.fields {
+            let reply = if !hash.contains(&field) {
+                -2
+            } else if !self.flags.allow(hash.get_expire(&field), self.when) {
+                0
+            } else if self.when <= now {
+                hash.remove(&field);
+                2
+            } else {
+                hash.set_expire(field, self.when);
+                1
+            };
+            if reply > 0 {
+                changes += 1;
+            }
+            replies.push(Protocol::Integer(reply));
+        }
+        if hash.is_empty() {
+            db.delete_key(&self.key);
+        } else if let Some(when) = hash.min_expire() {
+            db.add_hash_expire(self.key, when);
+        }
+        drop(dbs);
+        server.incr_dirty(changes);
+
+        dst.connection.write_protocol(&Protocol::Array(replies))?;
+        Ok(())
+    }
+}
+
+/// Return the time to live of fields of a hash, `HTTL` and `HPTTL`, or the
+/// time they expire at, `HEXPIRETIME` and `HPEXPIRETIME`, Redis'
+/// `httlGenericCommand`.
+#[derive(Debug)]
+pub struct HTtl {
+    key: Bytes,
+    unit: ExpireUnit,
+    /// Reply the UNIX time the fields expire at rather than the time left.
+    absolute: bool,
+    fields: Vec<Bytes>,
+}
+
+impl HTtl {
+    /// Parse a `HTtl` instance from a received frame.
+    ///
+    /// The command name has already been consumed and is given as `command`.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HTTL key FIELDS numfields field [field ...]
+    /// HPTTL key FIELDS numfields field [field ...]
+    /// HEXPIRETIME key FIELDS numfields field [field ...]
+    /// HPEXPIRETIME key FIELDS numfields field [field ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, command: &str) -> Result<HTtl> {
+        let key = parse.next_bytes()?;
+        let fields = parse_fields(parse)?;
+        let unit = if command.starts_with("hp") { ExpireUnit::Milliseconds } else { ExpireUnit::Seconds };
+        Ok(HTtl { key, unit, absolute: command.ends_with("time"), fields })
+    }
+
+    /// Apply the `HTtl` command and reply for every field: -2 if it does not
+    /// exist, -1 if it has no time to live. Seconds are rounded up.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let Some(hash) = lookup_hash(&mut dbs[dst.db], &self.key)? else {
+            drop(dbs);
+            dst.connection.write_protocol(&no_such_fields(&self.fields))?;
+            return Ok(());
+        };
+
+        let basetime = if self.absolute { 0 } else { mstime() };
+        let replies = self
+            .fields
+            .iter()
+            .map(|field| {
+                let reply = match hash.get_expire(field) {
+                    _ if !hash.contains(field) => -2,
+                    None => -1,
+                    Some(when) => match self.unit {
+                        ExpireUnit::Seconds => (when + 999 - basetime) / 1000,
+                        ExpireUnit::Milliseconds => when - basetime,
+                    },
+                };
+                Protocol::Integer(reply)
+            })
+            .collect();
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Array(replies))?;
+        Ok(())
+    }
+}
+
+/// Remove the time to live of fields of a hash.
+#[derive(Debug)]
+pub struct HPersist {
+    key: Bytes,
+    fields: Vec<Bytes>,
+}
+
+impl HPersist {
+    /// Parse a `HPersist` instance from a received frame.
+    ///
+    /// The `HPERSIST` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HPERSIST key FIELDS numfields field [field ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<HPersist> {
+        let key = parse.next_bytes()?;
+        let fields = parse_fields(parse)?;
+        Ok(HPersist { key, fields })
+    }
+
+    /// Apply the `HPersist` command and reply for every field: -2 if it does
+    /// not exist, -1 if it has no time to live, 1 if it was removed.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let Some(hash) = lookup_hash(&mut dbs[dst.db], &self.key)? else {
+            drop(dbs);
+            dst.connection.write_protocol(&no_such_fields(&self.fields))?;
+            return Ok(());
+        };
+
+        let mut changes = 0;
+        let replies = self
+            .fields
+            .iter()
+            .map(|field| {
+                let reply = if !hash.contains(field) {
+                    -2
+                } else if hash.remove_expire(field) {
+                    changes += 1;
+                    1
+                } else {
+                    -1
+                };
+                Protocol::Integer(reply)
+            })
+            .collect();
+        drop(dbs);
+        server.incr_dirty(changes);
+
+        dst.connection.write_protocol(&Protocol::Array(replies))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use resp::{protocol::{Protocol, ProtocolVersion}, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::server::REDIS_VERSION;
+
+/// Switch to a different protocol version, optionally authenticating and
+/// naming the connection, and reply with a summary of the server.
+#[derive(Debug, Default)]
+pub struct Hello {
+    /// protocol version to switch to, unchanged if `None`
+    version: Option<ProtocolVersion>,
+    /// name given with `SETNAME`, an empty one clearing it
+    name: Option<Bytes>,
+}
+
+impl Hello {
+    /// Parse a `Hello` instance from a received frame.
+    ///
+    /// The `HELLO` string has already been consumed. There are no users other
+    /// than `default`, which needs no password, so `AUTH` only checks the
+    /// user name.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// HELLO [protover [AUTH username password] [SETNAME clientname]]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Hello> {
+        let mut hello = Hello::default();
+
+        if parse.remaining() == 0 {
+            return Ok(hello);
+        }
+        let version = parse.next_i64().map_err(|_| RedisError::Err("Protocol version is not an integer or out of range".to_string()))?;
+        hello.version = Some(match version {
+            2 => ProtocolVersion::Resp2,
+            3 => ProtocolVersion::Resp3,
+            _ => return Err(RedisError::Custom("NOPROTO unsupported protocol version".to_string())),
+        });
+
+        while parse.remaining() > 0 {
+            let option = parse.next_string()?;
+            let syntax_error = || RedisError::Err(format!("Syntax error in HELLO option '{}'", option));
+
+            match &option.to_uppercase()[..] {
+                "AUTH" => {
+                    let username = parse.next_bytes().map_err(|_| syntax_error())?;
+                    let _password = parse.next_bytes().map_err(|_| syntax_error())?;
+                    if &username[..] != b"default" {
+                        return Err(RedisError::Custom("WRONGPASS invalid username-password pair or user is disabled.".to_string()));
+                    }
+                }
+                "SETNAME" => {
+                    let name = parse.next_bytes().map_err(|_| syntax_error())?;
+                    if !name.iter().all(|c| (b'!'..=b'~').contains(c)) {
+                        return Err(RedisError::Err("Client names cannot contain spaces, newlines or special characters.".to_string()));
+                    }
+                    hello.name = Some(name);
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(hello)
+    }
+
+    /// Apply the `Hello` command to `dst`. The reply is already encoded with
+    /// the new protocol version.
+    pub(crate) fn apply(self, dst: &mut Client) -> Result<()> {
+        if let Some(version) = self.version {
+            dst.connection.set_version(version);
+        }
+        if let Some(name) = self.name {
+            dst.name = if name.is_empty() { None } else { Some(name) };
+        }
+
+        let proto = match dst.connection.version() {
+            ProtocolVersion::Resp2 => 2,
+            ProtocolVersion::Resp3 => 3,
+        };
+        let bulk = |value: &'static str| Protocol::Bulk(Bytes::from_static(value.as_bytes()));
+        let response = Protocol::Map(vec![
+            (bulk("server"), bulk("redis")),
+            (bulk("version"), bulk(REDIS_VERSION)),
+            (bulk("proto"), Protocol::Integer(proto)),
+            (bulk("id"), Protocol::Integer(dst.id() as i64)),
+            (bulk("mode"), bulk("standalone")),
+            (bulk("role"), bulk("master")),
+            (bulk("modules"), Protocol::array()),
+        ]);
+
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
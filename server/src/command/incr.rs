@@ -0,0 +1,168 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::db::object::RedisObject;
+use crate::server::RedisServer;
+use crate::util::{add_floats, string2ld, string2ll};
+
+/// Increment the integer value of key, `INCR`, `INCRBY`, `DECR` and `DECRBY`.
+/// A missing key is taken as 0.
+#[derive(Debug)]
+pub struct IncrBy {
+    key: Bytes,
+    increment: i64,
+}
+
+impl IncrBy {
+    /// Parse an `IncrBy` instance from a received frame.
+    ///
+    /// The command name has already been consumed. `INCR` and `DECR` have
+    /// their `increment` fixed to 1 and -1, `DECRBY` negates the argument.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// INCR key
+    /// DECR key
+    /// INCRBY key increment
+    /// DECRBY key decrement
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, command: &str) -> Result<IncrBy> {
+        let key = parse.next_bytes()?;
+        let increment = match command {
+            "incr" => 1,
+            "decr" => -1,
+            "incrby" => parse.next_i64()?,
+            _ => {
+                let decrement = parse.next_i64()?;
+                decrement.checked_neg().ok_or_else(|| RedisError::Err("decrement would overflow".to_string()))?
+            }
+        };
+        Ok(IncrBy { key, increment })
+    }
+
+    /// Apply the `IncrBy` command and reply with the new value.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+
+        let new = match db.lookup_key(&self.key) {
+            Some(value) => {
+                let current = match value {
+                    RedisObject::Int(current) => *current,
+                    _ => {
+                        let current = value.string_value().ok_or(RedisError::WrongType)?;
+                        string2ll(&current).ok_or_else(RedisError::not_integer)?
+                    }
+                };
+                let new = current
+                    .checked_add(self.increment)
+                    .ok_or_else(|| RedisError::Err("increment or decrement would overflow".to_string()))?;
+                // Replaced in place, which keeps the time to live.
+                *value = RedisObject::Int(new);
+                new
+            }
+            None => {
+                db.set_key(self.key, RedisObject::Int(self.increment), false);
+                self.increment
+            }
+        };
+        drop(dbs);
+        server.incr_dirty(1);
+
+        dst.connection.write_protocol(&Protocol::Integer(new))?;
+        Ok(())
+    }
+}
+
+/// Increment the floating point value of key. A missing key is taken as 0.
+#[derive(Debug)]
+pub struct IncrByFloat {
+    key: Bytes,
+    increment: Bytes,
+}
+
+impl IncrByFloat {
+    /// Parse an `IncrByFloat` instance from a received frame.
+    ///
+    /// The `INCRBYFLOAT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// INCRBYFLOAT key increment
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<IncrByFloat> {
+        let key = parse.next_bytes()?;
+        let increment = parse.next_bytes()?;
+        string2ld(&increment).ok_or_else(RedisError::not_float)?;
+        Ok(IncrByFloat { key, increment })
+    }
+
+    /// Apply the `IncrByFloat` command and reply with the new value as a bulk
+    /// string. The value is stored in its human readable form.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+
+        let current = match db.lookup_key(&self.key).map(|value| value.string_value()) {
+            None => Bytes::from_static(b"0"),
+            Some(None) => return Err(RedisError::WrongType),
+            Some(Some(value)) => string2ld(&value).map(|_| value).ok_or_else(RedisError::not_float)?,
+        };
+        let new = add_floats(&current, &self.increment)
+            .ok_or_else(|| RedisError::Err("increment would produce NaN or Infinity".to_string()))?;
+        let new = Bytes::from(new);
+        db.set_key(self.key, RedisObject::String(new.clone()), true);
+        drop(dbs);
+        server.incr_dirty(1);
+
+        dst.connection.write_protocol(&Protocol::Bulk(new))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::TestClient;
+
+    #[test]
+    fn integer_overflow() {
+        let mut c = TestClient::new();
+        assert_eq!(c.call(&["INCR", "n"]), ":1\r\n");
+        assert_eq!(c.call(&["DECRBY", "n", "3"]), ":-2\r\n");
+        c.call(&["SET", "n", "9223372036854775806"]);
+        assert_eq!(c.call(&["INCR", "n"]), ":9223372036854775807\r\n");
+        assert_eq!(c.call(&["INCR", "n"]), "-ERR increment or decrement would overflow\r\n");
+        assert_eq!(c.call(&["GET", "n"]), "$19\r\n9223372036854775807\r\n");
+
+        c.call(&["SET", "n", "-9223372036854775807"]);
+        assert_eq!(c.call(&["DECRBY", "n", "1"]), ":-9223372036854775808\r\n");
+        assert_eq!(c.call(&["DECR", "n"]), "-ERR increment or decrement would overflow\r\n");
+        assert_eq!(c.call(&["DECRBY", "m", "-9223372036854775808"]), "-ERR decrement would overflow\r\n");
+
+        c.call(&["SET", "s", "12a"]);
+        assert_eq!(c.call(&["INCR", "s"]), "-ERR value is not an integer or out of range\r\n");
+        c.call(&["SET", "s", "9223372036854775808"]);
+        assert_eq!(c.call(&["INCRBY", "s", "1"]), "-ERR value is not an integer or out of range\r\n");
+        c.call(&["RPUSH", "l", "a"]);
+        assert!(c.call(&["INCR", "l"]).starts_with("-WRONGTYPE"));
+    }
+
+    #[test]
+    fn float_formatting() {
+        let mut c = TestClient::new();
+        assert_eq!(c.call(&["INCRBYFLOAT", "f", "0.1"]), "$3\r\n0.1\r\n");
+        assert_eq!(c.call(&["INCRBYFLOAT", "f", "0.2"]), "$3\r\n0.3\r\n");
+        c.call(&["SET", "f", "1e20"]);
+        assert_eq!(c.call(&["INCRBYFLOAT", "f", "1"]), "$21\r\n100000000000000000000\r\n");
+        assert_eq!(c.call(&["INCRBYFLOAT", "g", "1e-20"]), "$1\r\n0\r\n");
+        assert_eq!(c.call(&["INCRBYFLOAT", "h", "123456789012345678"]), "$18\r\n123456789012345678\r\n");
+        assert_eq!(c.call(&["HINCRBYFLOAT", "hh", "x", "1e17"]), "$18\r\n100000000000000000\r\n");
+
+        assert_eq!(c.call(&["INCRBYFLOAT", "f", "x"]), "-ERR value is not a valid float\r\n");
+        assert_eq!(c.call(&["INCRBYFLOAT", "f", "inf"]), "-ERR increment would produce NaN or Infinity\r\n");
+        assert_eq!(c.call(&["GET", "f"]), "$21\r\n100000000000000000000\r\n");
+    }
+}
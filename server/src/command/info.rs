@@ -0,0 +1,126 @@
+use resp::{protocol::Protocol, parse::Parser};
+use ahash::AHashSet;
+use bytes::Bytes;
+use std::fmt::Write;
+use std::process;
+use std::sync::atomic::Ordering;
+use std::time::UNIX_EPOCH;
+use crate::client::Client;
+use crate::error::Result;
+use crate::server::{RedisServer, REDIS_VERSION};
+
+/// Returns information and statistics about the server in a format that is
+/// simple to parse by computers and easy to read by humans.
+#[derive(Debug, Default)]
+pub struct Info {
+    /// requested sections, all of the default ones when empty
+    sections: Vec<String>,
+}
+
+impl Info {
+    /// Sections returned when no section, `default`, `all` or `everything` is
+    /// requested.
+    const DEFAULT_SECTIONS: [&'static str; 5] = ["server", "clients", "persistence", "stats", "keyspace"];
+
+    /// Parse an `Info` instance from a received frame.
+    ///
+    /// The `INFO` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// INFO [section [section ...]]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Info> {
+        let mut sections = vec![];
+        while let Some(section) = parse.next_keyword()? {
+            sections.push(section.to_lowercase());
+        }
+        Ok(Info { sections })
+    }
+
+    /// Apply the `Info` command and reply with the requested sections as a
+    /// single bulk string, a verbatim one for RESP3 clients.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let all = self.sections.is_empty()
+            || self.sections.iter().any(|s| s == "default" || s == "all" || s == "everything");
+
+        let mut info = String::new();
+        for section in Self::DEFAULT_SECTIONS {
+            if all || self.sections.iter().any(|s| s == section) {
+                if !info.is_empty() {
+                    info.push_str("\r\n");
+                }
+                Self::write_section(&mut info, section, server).expect("writing to a String cannot fail");
+            }
+        }
+
+        dst.connection.write_protocol(&Protocol::Verbatim("txt".to_string(), Bytes::from(info)))?;
+        Ok(())
+    }
+
+    fn write_section(info: &mut String, section: &str, server: &RedisServer) -> std::fmt::Result {
+        match section {
+            "server" => {
+                let uptime = server.start_time.elapsed().as_secs();
+                write!(info, "# Server\r\n")?;
+                write!(info, "redis_version:{}\r\n", REDIS_VERSION)?;
+                write!(info, "process_id:{}\r\n", process::id())?;
+                write!(info, "tcp_port:{}\r\n", server.config.port)?;
+                write!(info, "uptime_in_seconds:{}\r\n", uptime)?;
+                write!(info, "uptime_in_days:{}\r\n", uptime / (3600 * 24))?;
+                write!(info, "hz:{}\r\n", server.hz.load(Ordering::Relaxed))?;
+                write!(info, "configured_hz:{}\r\n", server.config.hz)?;
+            }
+            "clients" => {
+                write!(info, "# Clients\r\n")?;
+                write!(info, "connected_clients:{}\r\n", server.client_manager.connected_clients())?;
+                let dbs = server.keyspace.lock();
+                // A client blocked on several keys is counted once.
+                let blocked_clients: AHashSet<usize> = dbs.iter().flat_map(|db| db.blocked_clients()).collect();
+                let total_blocking_keys: usize = dbs.iter().map(|db| db.blocking_keys_len()).sum();
+                drop(dbs);
+                write!(info, "blocked_clients:{}\r\n", blocked_clients.len())?;
+                write!(info, "total_blocking_keys:{}\r\n", total_blocking_keys)?;
+            }
+            "persistence" => {
+                let persistence = server.persistence.lock().unwrap();
+                let lastsave = persistence.lastsave.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+                write!(info, "# Persistence\r\n")?;
+                write!(info, "rdb_changes_since_last_save:{}\r\n", persistence.dirty)?;
+                write!(info, "rdb_bgsave_in_progress:0\r\n")?;
+                write!(info, "rdb_last_save_time:{}\r\n", lastsave)?;
+                write!(info, "rdb_last_bgsave_status:ok\r\n")?;
+            }
+            "stats" => {
+                let dbs = server.keyspace.lock();
+                let expired_keys: u64 = dbs.iter().map(|db| db.expired_keys).sum();
+                let expired_subkeys: u64 = dbs.iter().map(|db| db.expired_fields).sum();
+                drop(dbs);
+                let stats = server.stats.lock().unwrap();
+                write!(info, "# Stats\r\n")?;
+                write!(info, "total_connections_received:{}\r\n", stats.total_connections_received)?;
+                write!(info, "total_commands_processed:{}\r\n", stats.total_commands_processed)?;
+                write!(info, "instantaneous_ops_per_sec:{}\r\n", stats.instantaneous_ops.get())?;
+                write!(info, "total_net_input_bytes:{}\r\n", stats.net_input_bytes)?;
+                write!(info, "total_net_output_bytes:{}\r\n", stats.net_output_bytes)?;
+                write!(info, "instantaneous_input_kbps:{:.2}\r\n", stats.instantaneous_net_input.get() as f64 / 1024.0)?;
+                write!(info, "instantaneous_output_kbps:{:.2}\r\n", stats.instantaneous_net_output.get() as f64 / 1024.0)?;
+                write!(info, "client_query_buffer_limit_disconnections:{}\r\n", stats.client_query_buffer_limit_disconnections)?;
+                write!(info, "client_output_buffer_limit_disconnections:{}\r\n", stats.client_output_buffer_limit_disconnections)?;
+                write!(info, "expired_keys:{}\r\n", expired_keys)?;
+                write!(info, "expired_subkeys:{}\r\n", expired_subkeys)?;
+                write!(info, "expired_stale_perc:{:.2}\r\n", stats.expired_stale_perc * 100.0)?;
+                write!(info, "expired_time_cap_reached_count:{}\r\n", stats.expired_time_cap_reached_count)?;
+            }
+            "keyspace" => {
+                write!(info, "# Keyspace\r\n")?;
+                for db in server.keyspace.lock().iter().filter(|db| db.len() > 0) {
+                    write!(info, "db{}:keys={},expires={},avg_ttl={},subexpiry=0\r\n", db.id, db.len(), db.expires_len(), db.avg_ttl)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
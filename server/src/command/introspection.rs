@@ -0,0 +1,201 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::command::table::{all_commands, lookup_command, CommandSpec, Keynum, CMD_WRITE};
+
+#[derive(Debug)]
+enum Subcommand {
+    /// `COMMAND`, every command.
+    List,
+    Count,
+    Info(Vec<String>),
+    Docs(Vec<String>),
+    /// The command whose keys to find, with its arguments.
+    GetKeys(Vec<Bytes>),
+}
+
+/// Describe the commands of the server, from the command table.
+#[derive(Debug)]
+pub struct CommandIntrospection {
+    subcommand: Subcommand,
+}
+
+impl CommandIntrospection {
+    /// Parse a `CommandIntrospection` instance from a received frame.
+    ///
+    /// The `COMMAND` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// COMMAND
+    /// COMMAND COUNT
+    /// COMMAND INFO [command-name [command-name ...]]
+    /// COMMAND DOCS [command-name [command-name ...]]
+    /// COMMAND GETKEYS command [arg [arg ...]]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<CommandIntrospection> {
+        let wrong_arity = |subcommand: &str| RedisError::WrongArity(format!("command|{}", subcommand));
+
+        let subcommand = match parse.next_keyword()?.as_deref() {
+            None => Subcommand::List,
+            Some("COUNT") if parse.remaining() == 0 => Subcommand::Count,
+            Some("COUNT") => return Err(wrong_arity("count")),
+            Some(name @ ("INFO" | "DOCS")) => {
+                let mut names = vec![];
+                while let Some(name) = parse.next_keyword()? {
+                    names.push(name.to_lowercase());
+                }
+                if name == "INFO" { Subcommand::Info(names) } else { Subcommand::Docs(names) }
+            }
+            Some("GETKEYS") if parse.remaining() > 0 => {
+                Subcommand::GetKeys(parse.remaining_bytes().collect::<std::result::Result<_, _>>()?)
+            }
+            Some("GETKEYS") => return Err(wrong_arity("getkeys")),
+            Some(other) => {
+                return Err(RedisError::Err(format!("unknown subcommand '{}'. Try COMMAND HELP.", other.to_lowercase())));
+            }
+        };
+
+        Ok(CommandIntrospection { subcommand })
+    }
+
+    /// Apply the `CommandIntrospection` command and reply to `dst`.
+    pub(crate) fn apply(self, dst: &mut Client) -> Result<()> {
+        let response = match self.subcommand {
+            Subcommand::List => Protocol::Array(all_commands().into_iter().map(command_info).collect()),
+            Subcommand::Count => Protocol::Integer(all_commands().len() as i64),
+            Subcommand::Info(names) if names.is_empty() => {
+                Protocol::Array(all_commands().into_iter().map(command_info).collect())
+            }
+            Subcommand::Info(names) => Protocol::Array(
+                names
+                    .iter()
+                    .map(|name| lookup_command(name).map_or(Protocol::Null, command_info))
+                    .collect(),
+            ),
+            Subcommand::Docs(names) => {
+                let specs = if names.is_empty() {
+                    all_commands()
+                } else {
+                    names.iter().filter_map(|name| lookup_command(name)).collect()
+                };
+                Protocol::Map(specs.into_iter().map(|spec| (bulk(spec.name), command_docs(spec))).collect())
+            }
+            Subcommand::GetKeys(args) => get_keys(&args),
+        };
+
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
+
+fn bulk(value: &str) -> Protocol {
+    Protocol::Bulk(Bytes::copy_from_slice(value.as_bytes()))
+}
+
+/// Reply describing a command for `COMMAND INFO`, Redis' `addReplyCommandInfo`.
+fn command_info(spec: &CommandSpec) -> Protocol {
+    Protocol::Array(vec![
+        bulk(spec.name),
+        Protocol::Integer(spec.arity),
+        Protocol::Set(spec.flag_names().map(|flag| Protocol::Simple(flag.to_string())).collect()),
+        Protocol::Integer(spec.first_key),
+        Protocol::Integer(spec.last_key),
+        Protocol::Integer(spec.key_step),
+        Protocol::Set(spec.acl_categories().into_iter().map(|category| Protocol::Simple(format!("@{}", category))).collect()),
+        // Tips.
+        Protocol::Set(vec![]),
+        Protocol::Array(key_specs(spec)),
+        // Subcommands.
+        Protocol::Array(vec![]),
+    ])
+}
+
+/// Key specifications of `spec`, its `Keynum` spec or the equivalent of its
+/// legacy key positions.
+fn key_specs(spec: &CommandSpec) -> Vec<Protocol> {
+    let pair = |key: &str, value: Protocol| (bulk(key), value);
+    let (index, find_keys) = match spec.keynum {
+        Some(Keynum { index, first_key }) => (index, Protocol::Map(vec![
+            pair("type", bulk("keynum")),
+            pair("spec", Protocol::Map(vec![
+                pair("keynumidx", Protocol::Integer(0)),
+                pair("firstkey", Protocol::Integer(first_key)),
+                pair("keystep", Protocol::Integer(1)),
+            ])),
+        ])),
+        None if spec.first_key == 0 => return vec![],
+        None => {
+            // The last key is relative to the first one, or to the end if negative.
+            let last_key = if spec.last_key < 0 { spec.last_key } else { spec.last_key - spec.first_key };
+            (spec.first_key, Protocol::Map(vec![
+                pair("type", bulk("range")),
+                pair("spec", Protocol::Map(vec![
+                    pair("lastkey", Protocol::Integer(last_key)),
+                    pair("keystep", Protocol::Integer(spec.key_step)),
+                    pair("limit", Protocol::Integer(0)),
+                ])),
+            ]))
+        }
+    };
+    let access = if spec.flags & CMD_WRITE != 0 { "RW" } else { "RO" };
+
+    vec![Protocol::Map(vec![
+        pair("flags", Protocol::Set(vec![Protocol::Simple(access.to_string())])),
+        pair("begin_search", Protocol::Map(vec![
+            pair("type", bulk("index")),
+            pair("spec", Protocol::Map(vec![pair("index", Protocol::Integer(index))])),
+        ])),
+        pair("find_keys", find_keys),
+    ])]
+}
+
+/// Documentation of a command for `COMMAND DOCS`.
+fn command_docs(spec: &CommandSpec) -> Protocol {
+    Protocol::Map(vec![
+        (bulk("summary"), bulk(spec.summary())),
+        (bulk("since"), bulk(spec.since())),
+        (bulk("group"), bulk(spec.group())),
+    ])
+}
+
+/// Reply of `COMMAND GETKEYS`, the keys of the command in `args`.
+fn get_keys(args: &[Bytes]) -> Protocol {
+    let name = String::from_utf8_lossy(&args[0]).to_lowercase();
+    let Some(spec) = lookup_command(&name) else {
+        return Protocol::Error("ERR Invalid command specified".to_string());
+    };
+    if !spec.has_keys() {
+        return Protocol::Error("ERR The command has no key arguments".to_string());
+    }
+    if !spec.check_arity(args.len()) {
+        return Protocol::Error("ERR Invalid number of arguments specified for command".to_string());
+    }
+
+    match spec.get_keys(args) {
+        Some(keys) if !keys.is_empty() => Protocol::Array(keys.into_iter().map(Protocol::Bulk).collect()),
+        _ => Protocol::Error("ERR Invalid arguments specified for command".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::TestClient;
+
+    #[test]
+    fn keys_after_their_count() {
+        let mut client = TestClient::new();
+        assert_eq!(client.call(&["COMMAND", "GETKEYS", "LMPOP", "2", "a", "b", "LEFT"]), "*2\r\n$1\r\na\r\n$1\r\nb\r\n");
+        assert_eq!(client.call(&["COMMAND", "GETKEYS", "BLMPOP", "0", "1", "a", "RIGHT", "COUNT", "2"]), "*1\r\n$1\r\na\r\n");
+        assert_eq!(client.call(&["COMMAND", "GETKEYS", "SINTERCARD", "2", "a", "b", "LIMIT", "1"]), "*2\r\n$1\r\na\r\n$1\r\nb\r\n");
+        assert_eq!(client.call(&["COMMAND", "GETKEYS", "SINTERCARD", "3", "a", "b"]), "-ERR Invalid arguments specified for command\r\n");
+        assert_eq!(client.call(&["COMMAND", "GETKEYS", "LMPOP", "x", "a", "LEFT"]), "-ERR Invalid arguments specified for command\r\n");
+        assert_eq!(client.call(&["COMMAND", "GETKEYS", "PING"]), "-ERR The command has no key arguments\r\n");
+
+        let info = client.call(&["COMMAND", "INFO", "ZMPOP"]);
+        assert!(info.contains("+movablekeys\r\n"));
+        assert!(info.contains("$6\r\nkeynum\r\n"));
+    }
+}
@@ -0,0 +1,231 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::server::RedisServer;
+use crate::util::string_match;
+
+/// Return the keys matching a glob-style pattern.
+#[derive(Debug)]
+pub struct Keys {
+    pattern: Bytes,
+}
+
+impl Keys {
+    /// Parse a `Keys` instance from a received frame.
+    ///
+    /// The `KEYS` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// KEYS pattern
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Keys> {
+        let pattern = parse.next_bytes()?;
+        Ok(Keys { pattern })
+    }
+
+    /// Apply the `Keys` command and reply with the matching keys, in no
+    /// particular order.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let all_keys = &self.pattern[..] == b"*";
+        let keys: Vec<Protocol> = server.keyspace.lock()[dst.db]
+            .keys()
+            .filter(|key| all_keys || string_match(&self.pattern, key, false))
+            .map(|key| Protocol::Bulk(key.clone()))
+            .collect();
+
+        dst.connection.write_protocol(&Protocol::Array(keys))?;
+        Ok(())
+    }
+}
+
+/// Cursor and options shared by `SCAN` and the commands iterating the
+/// elements of a value, such as `HSCAN`.
+#[derive(Debug)]
+pub(crate) struct ScanOptions {
+    pub(crate) cursor: usize,
+    pattern: Option<Bytes>,
+    pub(crate) count: usize,
+}
+
+impl ScanOptions {
+    /// Parse the cursor, the options then being given to `parse_option`.
+    pub(crate) fn parse_cursor(parse: &mut Parser) -> Result<ScanOptions> {
+        let cursor = parse.next_string()?.parse().map_err(|_| RedisError::Err("invalid cursor".to_string()))?;
+        Ok(ScanOptions { cursor, pattern: None, count: 10 })
+    }
+
+    /// Parse the `MATCH` or `COUNT` option. Returns false if `option` is
+    /// another one.
+    pub(crate) fn parse_option(&mut self, option: &str, parse: &mut Parser) -> Result<bool> {
+        match option {
+            "COUNT" if parse.remaining() > 0 => {
+                self.count = usize::try_from(parse.next_i64()?)
+                    .ok()
+                    .filter(|count| *count >= 1)
+                    .ok_or_else(RedisError::syntax)?;
+            }
+            "MATCH" if parse.remaining() > 0 => {
+                let pattern = parse.next_bytes()?;
+                // `*` matches everything, no need to check it.
+                self.pattern = (&pattern[..] != b"*").then_some(pattern);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Most buckets to visit for one batch, to bound the work when most of
+    /// them are empty.
+    pub(crate) fn max_iterations(&self) -> usize {
+        self.count.saturating_mul(10)
+    }
+
+    /// Whether `name` matches the `MATCH` pattern.
+    pub(crate) fn matches(&self, name: &[u8]) -> bool {
+        self.pattern.as_ref().is_none_or(|pattern| string_match(pattern, name, false))
+    }
+}
+
+/// Incrementally iterate the keys, Redis' `scanGenericCommand`.
+///
+/// The cursor is the one of `Dict::scan`: every key present during the whole
+/// iteration is returned at least once, even if the table is resized between
+/// calls.
+#[derive(Debug)]
+pub struct Scan {
+    options: ScanOptions,
+    type_name: Option<String>,
+}
+
+impl Scan {
+    /// Parse a `Scan` instance from a received frame.
+    ///
+    /// The `SCAN` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Scan> {
+        let mut scan = Scan { options: ScanOptions::parse_cursor(parse)?, type_name: None };
+
+        while let Some(option) = parse.next_keyword()? {
+            match &option[..] {
+                "TYPE" if parse.remaining() > 0 => scan.type_name = Some(parse.next_string()?.to_lowercase()),
+                _ if scan.options.parse_option(&option, parse)? => {}
+                _ => return Err(RedisError::syntax()),
+            }
+        }
+
+        Ok(scan)
+    }
+
+    /// Apply the `Scan` command and reply with the next cursor and a batch of
+    /// keys. About `COUNT` buckets are visited, so a batch may be empty
+    /// before the iteration is over, which is when the cursor is 0.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+
+        let mut keys = vec![];
+        let mut cursor = self.options.cursor;
+        let mut max_iterations = self.options.max_iterations();
+        loop {
+            cursor = db.scan(cursor, |key, value| {
+                let type_matches = self.type_name.as_ref().is_none_or(|type_name| type_name == value.type_name());
+                if type_matches && self.options.matches(key) {
+                    keys.push(key.clone());
+                }
+            });
+            max_iterations -= 1;
+            if cursor == 0 || max_iterations == 0 || keys.len() >= self.options.count {
+                break;
+            }
+        }
+        // Expired keys are deleted rather than returned.
+        keys.retain(|key| db.lookup_key(key).is_some());
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Array(vec![
+            Protocol::Bulk(Bytes::from(cursor.to_string())),
+            Protocol::Array(keys.into_iter().map(Protocol::Bulk).collect()),
+        ]))?;
+        Ok(())
+    }
+}
+
+/// Return a random key.
+#[derive(Debug, Default)]
+pub struct RandomKey;
+
+impl RandomKey {
+    /// Parse a `RandomKey` instance from a received frame.
+    ///
+    /// The `RANDOMKEY` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// RANDOMKEY
+    /// ```
+    pub(crate) fn parse_frames(_parse: &mut Parser) -> Result<RandomKey> {
+        Ok(RandomKey)
+    }
+
+    /// Apply the `RandomKey` command and reply with a key, nil if the
+    /// database is empty.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let key = server.keyspace.lock()[dst.db].random_key();
+        dst.connection.write_protocol(&key.map_or(Protocol::Null, Protocol::Bulk))?;
+        Ok(())
+    }
+}
+
+/// Return the type of the value of key, `none` if it does not exist.
+#[derive(Debug)]
+pub struct Type {
+    key: Bytes,
+}
+
+impl Type {
+    /// Parse a `Type` instance from a received frame.
+    ///
+    /// The `TYPE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// TYPE key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Type> {
+        let key = parse.next_bytes()?;
+        Ok(Type { key })
+    }
+
+    /// Apply the `Type` command and reply with the name of the type.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let type_name = server.keyspace.lock()[dst.db].lookup_key(&self.key).map_or("none", |value| value.type_name());
+        dst.connection.write_protocol(&Protocol::Simple(type_name.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_scan_count() {
+        let mut parse = Parser::new(Protocol::Array(
+            ["0", "COUNT", "9223372036854775807"].into_iter().map(|arg| Protocol::Bulk(Bytes::from(arg))).collect(),
+        ))
+        .unwrap();
+        let scan = Scan::parse_frames(&mut parse).unwrap();
+        assert_eq!(scan.options.count, i64::MAX as usize);
+        assert_eq!(scan.options.max_iterations(), usize::MAX);
+    }
+}
@@ -0,0 +1,852 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::blocked::{block_for_keys, parse_timeout};
+use crate::client::Client;
+use crate::command::{parse_mpop_count, parse_numkeys, Command};
+use crate::db::{keyspace::Db, listpack::ListpackEntry, object::RedisObject, quicklist::Quicklist};
+use crate::error::{RedisError, Result};
+use crate::server::RedisServer;
+
+/// End of a list, Redis' `LIST_HEAD` and `LIST_TAIL`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ListEnd {
+    Left,
+    Right,
+}
+
+impl ListEnd {
+    /// Parse `LEFT` or `RIGHT`.
+    pub(crate) fn parse(parse: &mut Parser) -> Result<ListEnd> {
+        match &parse.next_string()?.to_uppercase()[..] {
+            "LEFT" => Ok(ListEnd::Left),
+            "RIGHT" => Ok(ListEnd::Right),
+            _ => Err(RedisError::syntax()),
+        }
+    }
+}
+
+/// The list stored at key, `None` if the key does not exist.
+pub(crate) fn lookup_list<'a>(db: &'a mut Db, key: &[u8]) -> Result<Option<&'a mut Quicklist>> {
+    match db.lookup_key(key) {
+        None => Ok(None),
+        Some(RedisObject::List(list)) => Ok(Some(list)),
+        Some(_) => Err(RedisError::WrongType),
+    }
+}
+
+/// The list stored at key, created empty if the key does not exist.
+fn lookup_or_create_list<'a>(server: &RedisServer, db: &'a mut Db, key: &Bytes) -> Result<&'a mut Quicklist> {
+    if lookup_list(db, key)?.is_none() {
+        db.set_key(key.clone(), RedisObject::List(Quicklist::new(server.config.list_max_listpack_size)), false);
+    }
+    Ok(lookup_list(db, key)?.expect("the list was just created"))
+}
+
+/// Push `value` at the `end` of the list.
+fn push(list: &mut Quicklist, end: ListEnd, value: &[u8]) {
+    match end {
+        ListEnd::Left => list.push_front(value),
+        ListEnd::Right => list.push_back(value),
+    }
+}
+
+/// Pop an element from the `end` of the list.
+fn pop_end(list: &mut Quicklist, end: ListEnd) -> Option<Bytes> {
+    match end {
+        ListEnd::Left => list.pop_front(),
+        ListEnd::Right => list.pop_back(),
+    }
+}
+
+/// Pop up to `count` elements from the `end` of the list at key, deleting
+/// the key once the list is empty. Returns `None` if the key does not exist.
+pub(crate) fn pop_elements(db: &mut Db, key: &[u8], end: ListEnd, count: usize) -> Result<Option<Vec<Bytes>>> {
+    let Some(list) = lookup_list(db, key)? else {
+        return Ok(None);
+    };
+    let count = count.min(list.len());
+    let elements: Vec<Bytes> = (0..count).filter_map(|_| pop_end(list, end)).collect();
+    if list.is_empty() {
+        db.delete_key(key);
+    }
+    Ok(Some(elements))
+}
+
+/// Normalize the `start` and `stop` indexes of a range, negative ones
+/// counting from the end, into an index and a number of elements.
+fn range_of(start: i64, stop: i64, len: usize) -> (usize, usize) {
+    let len = len as i64;
+    let start = if start < 0 { (len + start).max(0) } else { start };
+    let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+    if start > stop || start >= len {
+        (0, 0)
+    } else {
+        (start as usize, (stop - start + 1) as usize)
+    }
+}
+
+/// Normalize an index, negative ones counting from the end. Returns `None`
+/// if it is out of range.
+fn index_of(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    usize::try_from(index).ok().filter(|index| *index < len)
+}
+
+fn bulk_array(elements: impl IntoIterator<Item = Bytes>) -> Protocol {
+    Protocol::Array(elements.into_iter().map(Protocol::Bulk).collect())
+}
+
+/// Insert elements at the head, `LPUSH`, or the tail, `RPUSH`, of a list.
+/// The `X` variants only push to an existing list.
+#[derive(Debug)]
+pub struct Push {
+    key: Bytes,
+    values: Vec<Bytes>,
+    end: ListEnd,
+    xx: bool,
+}
+
+impl Push {
+    /// Parse a `Push` instance from a received frame.
+    ///
+    /// The `LPUSH`, `RPUSH`, `LPUSHX` or `RPUSHX` string has already been
+    /// consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LPUSH key element [element ...]
+    /// RPUSH key element [element ...]
+    /// LPUSHX key element [element ...]
+    /// RPUSHX key element [element ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, end: ListEnd, xx: bool) -> Result<Push> {
+        let key = parse.next_bytes()?;
+        let mut values = vec![];
+        for value in parse.remaining_bytes() {
+            values.push(value?);
+        }
+        Ok(Push { key, values, end, xx })
+    }
+
+    /// Apply the `Push` command and reply with the length of the list, 0 if
+    /// nothing was pushed to a missing key.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+
+        let list = if self.xx { lookup_list(db, &self.key)? } else { Some(lookup_or_create_list(server, db, &self.key)?) };
+        let len = match list {
+            Some(list) => {
+                for value in &self.values {
+                    push(list, self.end, value);
+                }
+                server.incr_dirty(self.values.len() as u64);
+                list.len()
+            }
+            None => 0,
+        };
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Integer(len as i64))?;
+        Ok(())
+    }
+}
+
+/// Remove and return elements from the head, `LPOP`, or the tail, `RPOP`,
+/// of a list.
+#[derive(Debug)]
+pub struct Pop {
+    key: Bytes,
+    end: ListEnd,
+    count: Option<u64>,
+}
+
+impl Pop {
+    /// Parse a `Pop` instance from a received frame.
+    ///
+    /// The `LPOP` or `RPOP` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LPOP key [count]
+    /// RPOP key [count]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, end: ListEnd) -> Result<Pop> {
+        let key = parse.next_bytes()?;
+        let count = if parse.remaining() > 0 { Some(parse.next_u64()?) } else { None };
+        Ok(Pop { key, end, count })
+    }
+
+    /// Apply the `Pop` command and reply with the element, or the array of
+    /// elements if a count was given. A missing key replies nil.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let count = self.count.map_or(1, |count| usize::try_from(count).unwrap_or(usize::MAX));
+        let elements = pop_elements(&mut server.keyspace.lock()[dst.db], &self.key, self.end, count)?;
+        if let Some(elements) = &elements {
+            server.incr_dirty(elements.len() as u64);
+        }
+
+        let response = match (elements, self.count) {
+            (None, None) => Protocol::Null,
+            (None, Some(_)) => Protocol::NullArray,
+            (Some(mut elements), None) => elements.pop().map_or(Protocol::Null, Protocol::Bulk),
+            (Some(elements), Some(_)) => bulk_array(elements),
+        };
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
+
+/// Return a range of elements of a list.
+#[derive(Debug)]
+pub struct LRange {
+    key: Bytes,
+    start: i64,
+    stop: i64,
+}
+
+impl LRange {
+    /// Parse a `LRange` instance from a received frame.
+    ///
+    /// The `LRANGE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LRANGE key start stop
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<LRange> {
+        let key = parse.next_bytes()?;
+        let start = parse.next_i64()?;
+        let stop = parse.next_i64()?;
+        Ok(LRange { key, start, stop })
+    }
+
+    /// Apply the `LRange` command and reply with the elements from start to
+    /// stop, both included. Negative indexes count from the end.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let response = match lookup_list(&mut dbs[dst.db], &self.key)? {
+            Some(list) => {
+                let (index, count) = range_of(self.start, self.stop, list.len());
+                bulk_array(list.range(index, count))
+            }
+            None => Protocol::Array(vec![]),
+        };
+        drop(dbs);
+
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
+
+/// Return an element of a list by its index.
+#[derive(Debug)]
+pub struct LIndex {
+    key: Bytes,
+    index: i64,
+}
+
+impl LIndex {
+    /// Parse a `LIndex` instance from a received frame.
+    ///
+    /// The `LINDEX` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LINDEX key index
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<LIndex> {
+        let key = parse.next_bytes()?;
+        let index = parse.next_i64()?;
+        Ok(LIndex { key, index })
+    }
+
+    /// Apply the `LIndex` command and reply with the element, nil if the
+    /// index is out of range.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let value = lookup_list(&mut dbs[dst.db], &self.key)?
+            .and_then(|list| index_of(self.index, list.len()).and_then(|index| list.get(index)));
+        drop(dbs);
+
+        dst.connection.write_protocol(&value.map_or(Protocol::Null, Protocol::Bulk))?;
+        Ok(())
+    }
+}
+
+/// Set the value of an element of a list by its index.
+#[derive(Debug)]
+pub struct LSet {
+    key: Bytes,
+    index: i64,
+    value: Bytes,
+}
+
+impl LSet {
+    /// Parse a `LSet` instance from a received frame.
+    ///
+    /// The `LSET` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LSET key index element
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<LSet> {
+        let key = parse.next_bytes()?;
+        let index = parse.next_i64()?;
+        let value = parse.next_bytes()?;
+        Ok(LSet { key, index, value })
+    }
+
+    /// Apply the `LSet` command and reply `OK`.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let list = lookup_list(&mut dbs[dst.db], &self.key)?.ok_or_else(|| RedisError::Err("no such key".to_string()))?;
+        let index = index_of(self.index, list.len()).ok_or_else(|| RedisError::Err("index out of range".to_string()))?;
+        list.set(index, &self.value);
+        drop(dbs);
+        server.incr_dirty(1);
+
+        dst.connection.write_protocol(&Protocol::Simple("OK".to_string()))?;
+        Ok(())
+    }
+}
+
+/// Insert an element before or after another one, the pivot.
+#[derive(Debug)]
+pub struct LInsert {
+    key: Bytes,
+    after: bool,
+    pivot: Bytes,
+    value: Bytes,
+}
+
+impl LInsert {
+    /// Parse a `LInsert` instance from a received frame.
+    ///
+    /// The `LINSERT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LINSERT key BEFORE|AFTER pivot element
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<LInsert> {
+        let key = parse.next_bytes()?;
+        let after = match &parse.next_string()?.to_uppercase()[..] {
+            "BEFORE" => false,
+            "AFTER" => true,
+            _ => return Err(RedisError::syntax()),
+        };
+        let pivot = parse.next_bytes()?;
+        let value = parse.next_bytes()?;
+        Ok(LInsert { key, after, pivot, value })
+    }
+
+    /// Apply the `LInsert` command and reply with the length of the list,
+    /// -1 if the pivot was not found and 0 if the key does not exist.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let len = match lookup_list(&mut dbs[dst.db], &self.key)? {
+            Some(list) => {
+                let pivot = list.iter().position(|entry| entry.eq_bytes(&self.pivot));
+                match pivot {
+                    Some(index) => {
+                        list.insert(index + self.after as usize, &self.value);
+                        server.incr_dirty(1);
+                        list.len() as i64
+                    }
+                    None => -1,
+                }
+            }
+            None => 0,
+        };
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Integer(len))?;
+        Ok(())
+    }
+}
+
+/// Remove the elements equal to a value.
+#[derive(Debug)]
+pub struct LRem {
+    key: Bytes,
+    count: i64,
+    value: Bytes,
+}
+
+impl LRem {
+    /// Parse a `LRem` instance from a received frame.
+    ///
+    /// The `LREM` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LREM key count element
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<LRem> {
+        let key = parse.next_bytes()?;
+        let count = parse.next_i64()?;
+        let value = parse.next_bytes()?;
+        Ok(LRem { key, count, value })
+    }
+
+    /// Apply the `LRem` command and reply with the number of elements
+    /// removed: the first count ones if positive, the last ones if negative,
+    /// all of them if zero.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        let removed = match lookup_list(db, &self.key)? {
+            Some(list) => {
+                let removed = list.remove_matching(&self.value, self.count);
+                if list.is_empty() {
+                    db.delete_key(&self.key);
+                }
+                removed
+            }
+            None => 0,
+        };
+        drop(dbs);
+        server.incr_dirty(removed as u64);
+
+        dst.connection.write_protocol(&Protocol::Integer(removed as i64))?;
+        Ok(())
+    }
+}
+
+/// Trim a list to a range of elements.
+#[derive(Debug)]
+pub struct LTrim {
+    key: Bytes,
+    start: i64,
+    stop: i64,
+}
+
+impl LTrim {
+    /// Parse a `LTrim` instance from a received frame.
+    ///
+    /// The `LTRIM` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LTRIM key start stop
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<LTrim> {
+        let key = parse.next_bytes()?;
+        let start = parse.next_i64()?;
+        let stop = parse.next_i64()?;
+        Ok(LTrim { key, start, stop })
+    }
+
+    /// Apply the `LTrim` command and reply `OK`. An empty range deletes the
+    /// key.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        if let Some(list) = lookup_list(db, &self.key)? {
+            let len = list.len();
+            let (index, count) = range_of(self.start, self.stop, len);
+            list.remove_range(index + count, len - index - count);
+            list.remove_range(0, index);
+            if list.is_empty() {
+                db.delete_key(&self.key);
+            }
+            server.incr_dirty((len - count) as u64);
+        }
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Simple("OK".to_string()))?;
+        Ok(())
+    }
+}
+
+/// Return the length of a list.
+#[derive(Debug)]
+pub struct LLen {
+    key: Bytes,
+}
+
+impl LLen {
+    /// Parse a `LLen` instance from a received frame.
+    ///
+    /// The `LLEN` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LLEN key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<LLen> {
+        let key = parse.next_bytes()?;
+        Ok(LLen { key })
+    }
+
+    /// Apply the `LLen` command and reply with the length, 0 if the key does
+    /// not exist.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let len = lookup_list(&mut server.keyspace.lock()[dst.db], &self.key)?.map_or(0, |list| list.len());
+        dst.connection.write_protocol(&Protocol::Integer(len as i64))?;
+        Ok(())
+    }
+}
+
+/// Return the index of the elements equal to a value.
+#[derive(Debug)]
+pub struct LPos {
+    key: Bytes,
+    value: Bytes,
+    rank: i64,
+    count: Option<usize>,
+    maxlen: usize,
+}
+
+impl LPos {
+    /// Parse a `LPos` instance from a received frame.
+    ///
+    /// The `LPOS` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LPOS key element [RANK rank] [COUNT num-matches] [MAXLEN len]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<LPos> {
+        let key = parse.next_bytes()?;
+        let value = parse.next_bytes()?;
+        let mut lpos = LPos { key, value, rank: 1, count: None, maxlen: 0 };
+
+        while let Some(option) = parse.next_keyword()? {
+            match &option[..] {
+                "RANK" if parse.remaining() > 0 => {
+                    lpos.rank = parse.next_i64()?;
+                    if lpos.rank == 0 {
+                        return Err(RedisError::Err(
+                            "RANK can't be zero: use 1 to start from the first match, 2 from the second ... \
+                             or use negative to start from the end of the list"
+                                .to_string(),
+                        ));
+                    }
+                }
+                "COUNT" if parse.remaining() > 0 => {
+                    let count = usize::try_from(parse.next_i64()?)
+                        .map_err(|_| RedisError::Err("COUNT can't be negative".to_string()))?;
+                    lpos.count = Some(count);
+                }
+                "MAXLEN" if parse.remaining() > 0 => {
+                    lpos.maxlen = usize::try_from(parse.next_i64()?)
+                        .map_err(|_| RedisError::Err("MAXLEN can't be negative".to_string()))?;
+                }
+                _ => return Err(RedisError::syntax()),
+            }
+        }
+
+        Ok(lpos)
+    }
+
+    /// Apply the `LPos` command and reply with the index of the rank-th
+    /// match, from the end if the rank is negative, or the indexes of up to
+    /// count matches, all of them if 0. Only the first maxlen elements
+    /// scanned are compared, all of them if 0.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let mut matches = vec![];
+        if let Some(list) = lookup_list(&mut dbs[dst.db], &self.key)? {
+            let len = list.len();
+            let maxlen = if self.maxlen == 0 { len } else { self.maxlen };
+            let wanted = match self.count {
+                Some(0) => usize::MAX,
+                Some(count) => count,
+                None => 1,
+            };
+            let skip = self.rank.unsigned_abs() as usize - 1;
+
+            let entries: Box<dyn Iterator<Item = (usize, ListpackEntry<'_>)>> = if self.rank > 0 {
+                Box::new(list.iter().enumerate())
+            } else {
+                Box::new(list.iter().rev().enumerate().map(|(i, entry)| (len - 1 - i, entry)))
+            };
+            matches = entries
+                .take(maxlen)
+                .filter(|(_, entry)| entry.eq_bytes(&self.value))
+                .skip(skip)
+                .take(wanted)
+                .map(|(index, _)| Protocol::Integer(index as i64))
+                .collect();
+        }
+        drop(dbs);
+
+        let response = match self.count {
+            Some(_) => Protocol::Array(matches),
+            None => matches.pop().unwrap_or(Protocol::Null),
+        };
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
+
+/// Pop an element from one end of a list and push it to one end of another
+/// list, or the same one.
+#[derive(Debug)]
+pub struct LMove {
+    source: Bytes,
+    destination: Bytes,
+    wherefrom: ListEnd,
+    whereto: ListEnd,
+}
+
+impl LMove {
+    /// Parse a `LMove` instance from a received frame.
+    ///
+    /// The `LMOVE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LMOVE source destination LEFT|RIGHT LEFT|RIGHT
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<LMove> {
+        let source = parse.next_bytes()?;
+        let destination = parse.next_bytes()?;
+        let wherefrom = ListEnd::parse(parse)?;
+        let whereto = ListEnd::parse(parse)?;
+        Ok(LMove { source, destination, wherefrom, whereto })
+    }
+
+    /// Move the element, returning it. Returns `None` if source does not
+    /// exist.
+    fn move_element(&self, server: &RedisServer, db: &mut Db) -> Result<Option<Bytes>> {
+        if lookup_list(db, &self.source)?.is_none() {
+            return Ok(None);
+        }
+        // Check the destination before popping, not to lose the element.
+        lookup_list(db, &self.destination)?;
+
+        // The source is deleted once the element is pushed, so rotating a
+        // list of one element keeps the key and its time to live.
+        let value = lookup_list(db, &self.source)?.and_then(|source| pop_end(source, self.wherefrom));
+        if let Some(value) = &value {
+            push(lookup_or_create_list(server, db, &self.destination)?, self.whereto, value);
+            server.incr_dirty(1);
+        }
+        if lookup_list(db, &self.source)?.is_some_and(|source| source.is_empty()) {
+            db.delete_key(&self.source);
+        }
+        Ok(value)
+    }
+
+    /// Apply the `LMove` command and reply with the element moved, nil if
+    /// source does not exist.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let value = self.move_element(server, &mut server.keyspace.lock()[dst.db])?;
+        dst.connection.write_protocol(&value.map_or(Protocol::Null, Protocol::Bulk))?;
+        Ok(())
+    }
+}
+
+/// Pop elements from the first non-empty list among the given keys.
+#[derive(Debug)]
+pub struct LMPop {
+    keys: Vec<Bytes>,
+    end: ListEnd,
+    count: usize,
+}
+
+impl LMPop {
+    /// Parse a `LMPop` instance from a received frame.
+    ///
+    /// The `LMPOP` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// LMPOP numkeys key [key ...] LEFT|RIGHT [COUNT count]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<LMPop> {
+        let keys = parse_numkeys(parse)?;
+        let end = ListEnd::parse(parse)?;
+        let count = parse_mpop_count(parse)?;
+        Ok(LMPop { keys, end, count })
+    }
+
+    /// Pop from the first list, the reply of the command. Returns `None` if
+    /// every list is empty.
+    fn pop(&self, server: &RedisServer, db: &mut Db) -> Result<Option<Protocol>> {
+        for key in &self.keys {
+            if let Some(elements) = pop_elements(db, key, self.end, self.count)? {
+                server.incr_dirty(elements.len() as u64);
+                return Ok(Some(Protocol::Array(vec![Protocol::Bulk(key.clone()), bulk_array(elements)])));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Apply the `LMPop` command and reply with the key popped from and its
+    /// elements, nil if every list is empty.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let response = self.pop(server, &mut server.keyspace.lock()[dst.db])?;
+        dst.connection.write_protocol(&response.unwrap_or(Protocol::NullArray))?;
+        Ok(())
+    }
+}
+
+/// Remove and return the first element, `BLPOP`, or the last one, `BRPOP`,
+/// of the first non-empty list, blocking until there is one.
+#[derive(Debug)]
+pub struct BPop {
+    keys: Vec<Bytes>,
+    end: ListEnd,
+    timeout: Option<i64>,
+}
+
+impl BPop {
+    /// Parse a `BPop` instance from a received frame.
+    ///
+    /// The `BLPOP` or `BRPOP` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// BLPOP key [key ...] timeout
+    /// BRPOP key [key ...] timeout
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, end: ListEnd) -> Result<BPop> {
+        let mut keys = vec![];
+        for key in parse.remaining_bytes() {
+            keys.push(key?);
+        }
+        let timeout = keys.pop().ok_or_else(|| RedisError::WrongArity(String::new()))?;
+        let timeout = parse_timeout(&timeout)?;
+        Ok(BPop { keys, end, timeout })
+    }
+
+    /// Apply the `BPop` command and reply with the key popped from and the
+    /// element, or block the client.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        for key in &self.keys {
+            if let Some(element) = pop_elements(db, key, self.end, 1)?.and_then(|mut elements| elements.pop()) {
+                drop(dbs);
+                server.incr_dirty(1);
+                dst.connection.write_protocol(&bulk_array([key.clone(), element]))?;
+                return Ok(());
+            }
+        }
+        drop(dbs);
+
+        let keys = self.keys.clone();
+        let timeout = self.timeout;
+        let command = match self.end {
+            ListEnd::Left => Command::BLPop(self),
+            ListEnd::Right => Command::BRPop(self),
+        };
+        block_for_keys(server, dst, keys, "list", timeout, command)
+    }
+}
+
+/// `LMOVE`, blocking until source holds an element.
+#[derive(Debug)]
+pub struct BLMove {
+    lmove: LMove,
+    timeout: Option<i64>,
+}
+
+impl BLMove {
+    /// Parse a `BLMove` instance from a received frame.
+    ///
+    /// The `BLMOVE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// BLMOVE source destination LEFT|RIGHT LEFT|RIGHT timeout
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<BLMove> {
+        let lmove = LMove::parse_frames(parse)?;
+        let timeout = parse_timeout(&parse.next_bytes()?)?;
+        Ok(BLMove { lmove, timeout })
+    }
+
+    /// Apply the `BLMove` command and reply with the element moved, or block
+    /// the client.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let value = self.lmove.move_element(server, &mut server.keyspace.lock()[dst.db])?;
+        match value {
+            Some(value) => {
+                dst.connection.write_protocol(&Protocol::Bulk(value))?;
+                Ok(())
+            }
+            None => {
+                let keys = vec![self.lmove.source.clone()];
+                block_for_keys(server, dst, keys, "list", self.timeout, Command::BLMove(self))
+            }
+        }
+    }
+}
+
+/// `LMPOP`, blocking until one of the lists holds an element.
+#[derive(Debug)]
+pub struct BLMPop {
+    lmpop: LMPop,
+    timeout: Option<i64>,
+}
+
+impl BLMPop {
+    /// Parse a `BLMPop` instance from a received frame.
+    ///
+    /// The `BLMPOP` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// BLMPOP timeout numkeys key [key ...] LEFT|RIGHT [COUNT count]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<BLMPop> {
+        let timeout = parse_timeout(&parse.next_bytes()?)?;
+        let lmpop = LMPop::parse_frames(parse)?;
+        Ok(BLMPop { lmpop, timeout })
+    }
+
+    /// Apply the `BLMPop` command and reply with the key popped from and its
+    /// elements, or block the client.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let response = self.lmpop.pop(server, &mut server.keyspace.lock()[dst.db])?;
+        match response {
+            Some(response) => {
+                dst.connection.write_protocol(&response)?;
+                Ok(())
+            }
+            None => {
+                let keys = self.lmpop.keys.clone();
+                block_for_keys(server, dst, keys, "list", self.timeout, Command::BLMPop(self))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_are_clamped() {
+        assert_eq!(range_of(0, -1, 5), (0, 5));
+        assert_eq!(range_of(-100, 1, 5), (0, 2));
+        assert_eq!(range_of(3, 100, 5), (3, 2));
+        assert_eq!(range_of(4, 2, 5), (0, 0));
+        assert_eq!(range_of(5, 10, 5), (0, 0));
+        assert_eq!(range_of(0, -1, 0), (0, 0));
+        assert_eq!(index_of(-1, 5), Some(4));
+        assert_eq!(index_of(-6, 5), None);
+        assert_eq!(index_of(5, 5), None);
+    }
+}
@@ -1,16 +1,223 @@
-use resp::{self, Result, protocol::Protocol, parse::Parser};
-use crate::command::{ping::Ping, unknown::Unknown};
-use crate::connection::Connection;
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::command::{
+    append::Append, dbsize::DbSize, del::{Del, Exists}, expire::{Expire, Persist, Ttl}, flush::{FlushAll, FlushDb},
+    get::{Get, GetDel, GetEx, MGet}, getrange::GetRange,
+    hash::{
+        HDel, HExists, HExpire, HGet, HGetAll, HIncrBy, HIncrByFloat, HLen, HMGet, HPersist, HRandField, HScan, HSet, HStrlen, HTtl,
+    },
+    hello::Hello, incr::{IncrBy, IncrByFloat}, info::Info,
+    introspection::CommandIntrospection, keys::{Keys, RandomKey, Scan, Type},
+    list::{BLMPop, BLMove, BPop, LIndex, LInsert, LLen, LMPop, LMove, LPos, LRange, LRem, LSet, LTrim, ListEnd, Pop, Push},
+    object::Object, ping::Ping,
+    rename::{Copy, Move, Rename}, select::Select, set::{ExpireUnit, MSet, Set, SetEx, SetNx}, setrange::SetRange,
+    sets::{SAdd, SInterCard, SIsMember, SMIsMember, SMembers, SMove, SPop, SRandMember, SRem, SScan, SetOp, SetOperation},
+    strlen::Strlen, swapdb::SwapDb, table::lookup_command, unknown::Unknown,
+    zset::{BZMPop, BZPop, ZAdd, ZCard, ZMPop, ZPop, ZScore},
+};
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::server::RedisServer;
 
+pub(crate) mod append;
+pub(crate) mod dbsize;
+pub(crate) mod del;
+pub(crate) mod expire;
+pub(crate) mod flush;
+pub(crate) mod get;
+pub(crate) mod getrange;
+pub(crate) mod hash;
+pub(crate) mod hello;
+pub(crate) mod incr;
+pub(crate) mod info;
+pub(crate) mod introspection;
+pub(crate) mod keys;
+pub(crate) mod list;
+pub(crate) mod object;
 pub(crate) mod ping;
+pub(crate) mod rename;
+pub(crate) mod select;
 pub(crate) mod set;
+pub(crate) mod setrange;
+pub(crate) mod sets;
+pub(crate) mod strlen;
+pub(crate) mod swapdb;
+pub(crate) mod table;
 pub(crate) mod unknown;
+pub(crate) mod zset;
+
+/// Make sure a string about to grow to `size` bytes stays within the
+/// configured `proto-max-bulk-len`, Redis' `checkStringLength`.
+pub(crate) fn check_string_length(server: &RedisServer, size: usize) -> Result<()> {
+    if size > server.config.proto_max_bulk_len {
+        Err(RedisError::Err("string exceeds maximum allowed size (proto-max-bulk-len)".to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Parse the `numkeys key [key ...]` arguments of `LMPOP` and `ZMPOP`. At
+/// least one argument must follow the keys.
+pub(crate) fn parse_numkeys(parse: &mut Parser) -> Result<Vec<Bytes>> {
+    let numkeys = parse
+        .next_i64()
+        .ok()
+        .and_then(|numkeys| usize::try_from(numkeys).ok())
+        .filter(|numkeys| *numkeys > 0)
+        .ok_or_else(|| RedisError::Err("numkeys should be greater than 0".to_string()))?;
+    if numkeys >= parse.remaining() {
+        return Err(RedisError::syntax());
+    }
+    let mut keys = Vec::with_capacity(numkeys);
+    for _ in 0..numkeys {
+        keys.push(parse.next_bytes()?);
+    }
+    Ok(keys)
+}
+
+/// Parse the optional `COUNT count` ending `LMPOP` and `ZMPOP`, 1 if not
+/// given.
+pub(crate) fn parse_mpop_count(parse: &mut Parser) -> Result<usize> {
+    let mut count = None;
+    while let Some(option) = parse.next_keyword()? {
+        match &option[..] {
+            "COUNT" if count.is_none() && parse.remaining() > 0 => {
+                let value = parse
+                    .next_i64()
+                    .ok()
+                    .and_then(|count| usize::try_from(count).ok())
+                    .filter(|count| *count > 0)
+                    .ok_or_else(|| RedisError::Err("count should be greater than 0".to_string()))?;
+                count = Some(value);
+            }
+            _ => return Err(RedisError::syntax()),
+        }
+    }
+    Ok(count.unwrap_or(1))
+}
 
 #[derive(Debug)]
 pub(crate) enum Command {
     Ping(Ping),
-    // Get,
-    // Set
+    Info(Info),
+    CommandIntrospection(CommandIntrospection),
+    Hello(Hello),
+    Select(Select),
+    SwapDb(SwapDb),
+    DbSize(DbSize),
+    FlushDb(FlushDb),
+    FlushAll(FlushAll),
+    Get(Get),
+    GetDel(GetDel),
+    GetEx(GetEx),
+    MGet(MGet),
+    Set(Set),
+    SetNx(SetNx),
+    SetEx(SetEx),
+    PSetEx(SetEx),
+    MSet(MSet),
+    MSetNx(MSet),
+    Append(Append),
+    Strlen(Strlen),
+    GetRange(GetRange),
+    SetRange(SetRange),
+    Incr(IncrBy),
+    Decr(IncrBy),
+    IncrBy(IncrBy),
+    DecrBy(IncrBy),
+    IncrByFloat(IncrByFloat),
+    Expire(Expire),
+    PExpire(Expire),
+    ExpireAt(Expire),
+    PExpireAt(Expire),
+    Ttl(Ttl),
+    PTtl(Ttl),
+    ExpireTime(Ttl),
+    PExpireTime(Ttl),
+    Persist(Persist),
+    Del(Del),
+    Unlink(Del),
+    Exists(Exists),
+    Touch(Exists),
+    Type(Type),
+    Rename(Rename),
+    RenameNx(Rename),
+    Copy(Copy),
+    Move(Move),
+    RandomKey(RandomKey),
+    Keys(Keys),
+    Scan(Scan),
+    Object(Object),
+    LPush(Push),
+    RPush(Push),
+    LPushX(Push),
+    RPushX(Push),
+    LPop(Pop),
+    RPop(Pop),
+    LRange(LRange),
+    LIndex(LIndex),
+    LSet(LSet),
+    LInsert(LInsert),
+    LRem(LRem),
+    LTrim(LTrim),
+    LLen(LLen),
+    LPos(LPos),
+    LMove(LMove),
+    LMPop(LMPop),
+    BLPop(BPop),
+    BRPop(BPop),
+    BLMove(BLMove),
+    BLMPop(BLMPop),
+    ZAdd(ZAdd),
+    ZCard(ZCard),
+    ZScore(ZScore),
+    ZPopMin(ZPop),
+    ZPopMax(ZPop),
+    ZMPop(ZMPop),
+    BZPopMin(BZPop),
+    BZPopMax(BZPop),
+    BZMPop(BZMPop),
+    HSet(HSet),
+    HSetNx(HSet),
+    HGet(HGet),
+    HMGet(HMGet),
+    HDel(HDel),
+    HExists(HExists),
+    HLen(HLen),
+    HKeys(HGetAll),
+    HVals(HGetAll),
+    HGetAll(HGetAll),
+    HIncrBy(HIncrBy),
+    HIncrByFloat(HIncrByFloat),
+    HStrlen(HStrlen),
+    HRandField(HRandField),
+    HScan(HScan),
+    HExpire(HExpire),
+    HPExpire(HExpire),
+    HExpireAt(HExpire),
+    HPExpireAt(HExpire),
+    HTtl(HTtl),
+    HPTtl(HTtl),
+    HExpireTime(HTtl),
+    HPExpireTime(HTtl),
+    HPersist(HPersist),
+    SAdd(SAdd),
+    SRem(SRem),
+    SIsMember(SIsMember),
+    SMIsMember(SMIsMember),
+    SMembers(SMembers),
+    SCard(SMembers),
+    SPop(SPop),
+    SRandMember(SRandMember),
+    SMove(SMove),
+    SInter(SetOperation),
+    SUnion(SetOperation),
+    SDiff(SetOperation),
+    SInterStore(SetOperation),
+    SUnionStore(SetOperation),
+    SDiffStore(SetOperation),
+    SInterCard(SInterCard),
+    SScan(SScan),
     Unknown(Unknown),
 }
 
@@ -22,7 +229,8 @@ impl Command {
     ///
     /// # Returns
     ///
-    /// On success, the command value is returned, otherwise, `Err` is returned.
+    /// On success, the command value is returned, otherwise, `Err` is returned
+    /// with the error to reply, such as a wrong number of arguments.
     pub fn from_protocol(protocol: Protocol) -> Result<Command> {
         // The protocol value is decorated with `Parse`. `Parse` provides a
         // "cursor" like API which makes parsing the command easier.
@@ -36,47 +244,388 @@ impl Command {
         // matching.
         let command_name = parse.next_string()?.to_lowercase();
 
+        // Commands with the wrong number of arguments are refused before
+        // being parsed, Redis' `processCommand`.
+        if let Some(spec) = lookup_command(&command_name) {
+            if !spec.check_arity(parse.remaining() + 1) {
+                return Err(RedisError::WrongArity(spec.name.to_string()));
+            }
+        }
+
+        Self::parse_command(&command_name, &mut parse).map_err(|err| match err {
+            // Commands of variable arity may still miss an argument, as in
+            // `MSET key`.
+            RedisError::WrongArity(name) if name.is_empty() => RedisError::WrongArity(command_name.clone()),
+            err => err,
+        })
+    }
+
+    fn parse_command(command_name: &str, parse: &mut Parser) -> Result<Command> {
         // Match the command name, delegating the rest of the parsing to the
         // specific command.
-        let command = match &command_name[..] {
-            "ping" => Command::Ping(Ping::parse_frames(&mut parse)?),
+        let command = match command_name {
+            "ping" => Command::Ping(Ping::parse_frames(parse)?),
+            "info" => Command::Info(Info::parse_frames(parse)?),
+            "command" => Command::CommandIntrospection(CommandIntrospection::parse_frames(parse)?),
+            "hello" => Command::Hello(Hello::parse_frames(parse)?),
+            "select" => Command::Select(Select::parse_frames(parse)?),
+            "swapdb" => Command::SwapDb(SwapDb::parse_frames(parse)?),
+            "dbsize" => Command::DbSize(DbSize::parse_frames(parse)?),
+            "flushdb" => Command::FlushDb(FlushDb::parse_frames(parse)?),
+            "flushall" => Command::FlushAll(FlushAll::parse_frames(parse)?),
+            "get" => Command::Get(Get::parse_frames(parse)?),
+            "getdel" => Command::GetDel(GetDel::parse_frames(parse)?),
+            "getex" => Command::GetEx(GetEx::parse_frames(parse)?),
+            "mget" => Command::MGet(MGet::parse_frames(parse)?),
+            "set" => Command::Set(Set::parse_frames(parse)?),
+            "setnx" => Command::SetNx(SetNx::parse_frames(parse)?),
+            "setex" => Command::SetEx(SetEx::parse_frames(parse, ExpireUnit::Seconds)?),
+            "psetex" => Command::PSetEx(SetEx::parse_frames(parse, ExpireUnit::Milliseconds)?),
+            "mset" => Command::MSet(MSet::parse_frames(parse, false)?),
+            "msetnx" => Command::MSetNx(MSet::parse_frames(parse, true)?),
+            "append" => Command::Append(Append::parse_frames(parse)?),
+            "strlen" => Command::Strlen(Strlen::parse_frames(parse)?),
+            "getrange" => Command::GetRange(GetRange::parse_frames(parse)?),
+            "setrange" => Command::SetRange(SetRange::parse_frames(parse)?),
+            "incr" => Command::Incr(IncrBy::parse_frames(parse, command_name)?),
+            "decr" => Command::Decr(IncrBy::parse_frames(parse, command_name)?),
+            "incrby" => Command::IncrBy(IncrBy::parse_frames(parse, command_name)?),
+            "decrby" => Command::DecrBy(IncrBy::parse_frames(parse, command_name)?),
+            "incrbyfloat" => Command::IncrByFloat(IncrByFloat::parse_frames(parse)?),
+            "expire" => Command::Expire(Expire::parse_frames(parse, command_name)?),
+            "pexpire" => Command::PExpire(Expire::parse_frames(parse, command_name)?),
+            "expireat" => Command::ExpireAt(Expire::parse_frames(parse, command_name)?),
+            "pexpireat" => Command::PExpireAt(Expire::parse_frames(parse, command_name)?),
+            "ttl" => Command::Ttl(Ttl::parse_frames(parse, command_name)?),
+            "pttl" => Command::PTtl(Ttl::parse_frames(parse, command_name)?),
+            "expiretime" => Command::ExpireTime(Ttl::parse_frames(parse, command_name)?),
+            "pexpiretime" => Command::PExpireTime(Ttl::parse_frames(parse, command_name)?),
+            "persist" => Command::Persist(Persist::parse_frames(parse)?),
+            "del" => Command::Del(Del::parse_frames(parse)?),
+            "unlink" => Command::Unlink(Del::parse_frames(parse)?),
+            "exists" => Command::Exists(Exists::parse_frames(parse)?),
+            "touch" => Command::Touch(Exists::parse_frames(parse)?),
+            "type" => Command::Type(Type::parse_frames(parse)?),
+            "rename" => Command::Rename(Rename::parse_frames(parse, false)?),
+            "renamenx" => Command::RenameNx(Rename::parse_frames(parse, true)?),
+            "copy" => Command::Copy(Copy::parse_frames(parse)?),
+            "move" => Command::Move(Move::parse_frames(parse)?),
+            "randomkey" => Command::RandomKey(RandomKey::parse_frames(parse)?),
+            "keys" => Command::Keys(Keys::parse_frames(parse)?),
+            "scan" => Command::Scan(Scan::parse_frames(parse)?),
+            "object" => Command::Object(Object::parse_frames(parse)?),
+            "lpush" => Command::LPush(Push::parse_frames(parse, ListEnd::Left, false)?),
+            "rpush" => Command::RPush(Push::parse_frames(parse, ListEnd::Right, false)?),
+            "lpushx" => Command::LPushX(Push::parse_frames(parse, ListEnd::Left, true)?),
+            "rpushx" => Command::RPushX(Push::parse_frames(parse, ListEnd::Right, true)?),
+            "lpop" => Command::LPop(Pop::parse_frames(parse, ListEnd::Left)?),
+            "rpop" => Command::RPop(Pop::parse_frames(parse, ListEnd::Right)?),
+            "lrange" => Command::LRange(LRange::parse_frames(parse)?),
+            "lindex" => Command::LIndex(LIndex::parse_frames(parse)?),
+            "lset" => Command::LSet(LSet::parse_frames(parse)?),
+            "linsert" => Command::LInsert(LInsert::parse_frames(parse)?),
+            "lrem" => Command::LRem(LRem::parse_frames(parse)?),
+            "ltrim" => Command::LTrim(LTrim::parse_frames(parse)?),
+            "llen" => Command::LLen(LLen::parse_frames(parse)?),
+            "lpos" => Command::LPos(LPos::parse_frames(parse)?),
+            "lmove" => Command::LMove(LMove::parse_frames(parse)?),
+            "lmpop" => Command::LMPop(LMPop::parse_frames(parse)?),
+            "blpop" => Command::BLPop(BPop::parse_frames(parse, ListEnd::Left)?),
+            "brpop" => Command::BRPop(BPop::parse_frames(parse, ListEnd::Right)?),
+            "blmove" => Command::BLMove(BLMove::parse_frames(parse)?),
+            "blmpop" => Command::BLMPop(BLMPop::parse_frames(parse)?),
+            "zadd" => Command::ZAdd(ZAdd::parse_frames(parse)?),
+            "zcard" => Command::ZCard(ZCard::parse_frames(parse)?),
+            "zscore" => Command::ZScore(ZScore::parse_frames(parse)?),
+            "zpopmin" => Command::ZPopMin(ZPop::parse_frames(parse, false)?),
+            "zpopmax" => Command::ZPopMax(ZPop::parse_frames(parse, true)?),
+            "zmpop" => Command::ZMPop(ZMPop::parse_frames(parse)?),
+            "bzpopmin" => Command::BZPopMin(BZPop::parse_frames(parse, false)?),
+            "bzpopmax" => Command::BZPopMax(BZPop::parse_frames(parse, true)?),
+            "bzmpop" => Command::BZMPop(BZMPop::parse_frames(parse)?),
+            "hset" => Command::HSet(HSet::parse_frames(parse, false)?),
+            "hsetnx" => Command::HSetNx(HSet::parse_frames(parse, true)?),
+            "hget" => Command::HGet(HGet::parse_frames(parse)?),
+            "hmget" => Command::HMGet(HMGet::parse_frames(parse)?),
+            "hdel" => Command::HDel(HDel::parse_frames(parse)?),
+            "hexists" => Command::HExists(HExists::parse_frames(parse)?),
+            "hlen" => Command::HLen(HLen::parse_frames(parse)?),
+            "hkeys" => Command::HKeys(HGetAll::parse_frames(parse, true, false)?),
+            "hvals" => Command::HVals(HGetAll::parse_frames(parse, false, true)?),
+            "hgetall" => Command::HGetAll(HGetAll::parse_frames(parse, true, true)?),
+            "hincrby" => Command::HIncrBy(HIncrBy::parse_frames(parse)?),
+            "hincrbyfloat" => Command::HIncrByFloat(HIncrByFloat::parse_frames(parse)?),
+            "hstrlen" => Command::HStrlen(HStrlen::parse_frames(parse)?),
+            "hrandfield" => Command::HRandField(HRandField::parse_frames(parse)?),
+            "hscan" => Command::HScan(HScan::parse_frames(parse)?),
+            "hexpire" => Command::HExpire(HExpire::parse_frames(parse, command_name)?),
+            "hpexpire" => Command::HPExpire(HExpire::parse_frames(parse, command_name)?),
+            "hexpireat" => Command::HExpireAt(HExpire::parse_frames(parse, command_name)?),
+            "hpexpireat" => Command::HPExpireAt(HExpire::parse_frames(parse, command_name)?),
+            "httl" => Command::HTtl(HTtl::parse_frames(parse, command_name)?),
+            "hpttl" => Command::HPTtl(HTtl::parse_frames(parse, command_name)?),
+            "hexpiretime" => Command::HExpireTime(HTtl::parse_frames(parse, command_name)?),
+            "hpexpiretime" => Command::HPExpireTime(HTtl::parse_frames(parse, command_name)?),
+            "hpersist" => Command::HPersist(HPersist::parse_frames(parse)?),
+            "sadd" => Command::SAdd(SAdd::parse_frames(parse)?),
+            "srem" => Command::SRem(SRem::parse_frames(parse)?),
+            "sismember" => Command::SIsMember(SIsMember::parse_frames(parse)?),
+            "smismember" => Command::SMIsMember(SMIsMember::parse_frames(parse)?),
+            "smembers" => Command::SMembers(SMembers::parse_frames(parse, false)?),
+            "scard" => Command::SCard(SMembers::parse_frames(parse, true)?),
+            "spop" => Command::SPop(SPop::parse_frames(parse)?),
+            "srandmember" => Command::SRandMember(SRandMember::parse_frames(parse)?),
+            "smove" => Command::SMove(SMove::parse_frames(parse)?),
+            "sinter" => Command::SInter(SetOperation::parse_frames(parse, SetOp::Inter, false)?),
+            "sunion" => Command::SUnion(SetOperation::parse_frames(parse, SetOp::Union, false)?),
+            "sdiff" => Command::SDiff(SetOperation::parse_frames(parse, SetOp::Diff, false)?),
+            "sinterstore" => Command::SInterStore(SetOperation::parse_frames(parse, SetOp::Inter, true)?),
+            "sunionstore" => Command::SUnionStore(SetOperation::parse_frames(parse, SetOp::Union, true)?),
+            "sdiffstore" => Command::SDiffStore(SetOperation::parse_frames(parse, SetOp::Diff, true)?),
+            "sintercard" => Command::SInterCard(SInterCard::parse_frames(parse)?),
+            "sscan" => Command::SScan(SScan::parse_frames(parse)?),
             _ => {
                 // The command is not recognized and an Unknown command is
                 // returned.
                 //
-                // `return` is called here to skip the `finish()` call below. As
-                // the command is not recognized, there is most likely
-                // unconsumed fields remaining in the `Parse` instance.
+                // `return` is called here to skip the check for leftover
+                // arguments below. As the command is not recognized, there
+                // are most likely unconsumed fields remaining in the `Parse`
+                // instance.
                 return Ok(Command::Unknown(Unknown::new(command_name)));
             }
         };
 
-        // Check if there is any remaining unconsumed fields in the `Parse`
-        // value. If fields remain, this indicates an unexpected protocol format
-        // and an error is returned.
-        parse.finish()?;
+        // Arguments left over mean the command was given too many.
+        if parse.remaining() > 0 {
+            return Err(RedisError::WrongArity(command_name.to_string()));
+        }
 
         // The command has been successfully parsed
         Ok(command)
     }
 
-    /// Apply the command to the specified `Db` instance.
+    /// Apply the command on behalf of `client`.
     ///
-    /// The response is written to `dst`. This is called by the server in order
-    /// to execute a received command.
-    pub(crate) fn apply(self, connection: &mut Connection) -> Result<()> {
+    /// The response is written to the client's connection. This is called by
+    /// the server in order to execute a received command.
+    pub(crate) fn apply(self, server: &RedisServer, client: &mut Client) -> Result<()> {
         use Command::*;
 
         match self {
-            Ping(cmd) => cmd.apply(connection),
-            Unknown(cmd) => cmd.apply(connection),
+            Ping(cmd) => cmd.apply(client),
+            Info(cmd) => cmd.apply(server, client),
+            CommandIntrospection(cmd) => cmd.apply(client),
+            Hello(cmd) => cmd.apply(client),
+            Select(cmd) => cmd.apply(server, client),
+            SwapDb(cmd) => cmd.apply(server, client),
+            DbSize(cmd) => cmd.apply(server, client),
+            FlushDb(cmd) => cmd.apply(server, client),
+            FlushAll(cmd) => cmd.apply(server, client),
+            Get(cmd) => cmd.apply(server, client),
+            GetDel(cmd) => cmd.apply(server, client),
+            GetEx(cmd) => cmd.apply(server, client),
+            MGet(cmd) => cmd.apply(server, client),
+            Set(cmd) => cmd.apply(server, client),
+            SetNx(cmd) => cmd.apply(server, client),
+            SetEx(cmd) | PSetEx(cmd) => cmd.apply(server, client),
+            MSet(cmd) | MSetNx(cmd) => cmd.apply(server, client),
+            Append(cmd) => cmd.apply(server, client),
+            Strlen(cmd) => cmd.apply(server, client),
+            GetRange(cmd) => cmd.apply(server, client),
+            SetRange(cmd) => cmd.apply(server, client),
+            Incr(cmd) | Decr(cmd) | IncrBy(cmd) | DecrBy(cmd) => cmd.apply(server, client),
+            IncrByFloat(cmd) => cmd.apply(server, client),
+            Expire(cmd) | PExpire(cmd) | ExpireAt(cmd) | PExpireAt(cmd) => cmd.apply(server, client),
+            Ttl(cmd) | PTtl(cmd) | ExpireTime(cmd) | PExpireTime(cmd) => cmd.apply(server, client),
+            Persist(cmd) => cmd.apply(server, client),
+            Del(cmd) | Unlink(cmd) => cmd.apply(server, client),
+            Exists(cmd) | Touch(cmd) => cmd.apply(server, client),
+            Type(cmd) => cmd.apply(server, client),
+            Rename(cmd) | RenameNx(cmd) => cmd.apply(server, client),
+            Copy(cmd) => cmd.apply(server, client),
+            Move(cmd) => cmd.apply(server, client),
+            RandomKey(cmd) => cmd.apply(server, client),
+            Keys(cmd) => cmd.apply(server, client),
+            Scan(cmd) => cmd.apply(server, client),
+            Object(cmd) => cmd.apply(server, client),
+            LPush(cmd) | RPush(cmd) | LPushX(cmd) | RPushX(cmd) => cmd.apply(server, client),
+            LPop(cmd) | RPop(cmd) => cmd.apply(server, client),
+            LRange(cmd) => cmd.apply(server, client),
+            LIndex(cmd) => cmd.apply(server, client),
+            LSet(cmd) => cmd.apply(server, client),
+            LInsert(cmd) => cmd.apply(server, client),
+            LRem(cmd) => cmd.apply(server, client),
+            LTrim(cmd) => cmd.apply(server, client),
+            LLen(cmd) => cmd.apply(server, client),
+            LPos(cmd) => cmd.apply(server, client),
+            LMove(cmd) => cmd.apply(server, client),
+            LMPop(cmd) => cmd.apply(server, client),
+            BLPop(cmd) | BRPop(cmd) => cmd.apply(server, client),
+            BLMove(cmd) => cmd.apply(server, client),
+            BLMPop(cmd) => cmd.apply(server, client),
+            ZAdd(cmd) => cmd.apply(server, client),
+            ZCard(cmd) => cmd.apply(server, client),
+            ZScore(cmd) => cmd.apply(server, client),
+            ZPopMin(cmd) | ZPopMax(cmd) => cmd.apply(server, client),
+            ZMPop(cmd) => cmd.apply(server, client),
+            BZPopMin(cmd) | BZPopMax(cmd) => cmd.apply(server, client),
+            BZMPop(cmd) => cmd.apply(server, client),
+            HSet(cmd) | HSetNx(cmd) => cmd.apply(server, client),
+            HGet(cmd) => cmd.apply(server, client),
+            HMGet(cmd) => cmd.apply(server, client),
+            HDel(cmd) => cmd.apply(server, client),
+            HExists(cmd) => cmd.apply(server, client),
+            HLen(cmd) => cmd.apply(server, client),
+            HKeys(cmd) | HVals(cmd) | HGetAll(cmd) => cmd.apply(server, client),
+            HIncrBy(cmd) => cmd.apply(server, client),
+            HIncrByFloat(cmd) => cmd.apply(server, client),
+            HStrlen(cmd) => cmd.apply(server, client),
+            HRandField(cmd) => cmd.apply(server, client),
+            HScan(cmd) => cmd.apply(server, client),
+            HExpire(cmd) | HPExpire(cmd) | HExpireAt(cmd) | HPExpireAt(cmd) => cmd.apply(server, client),
+            HTtl(cmd) | HPTtl(cmd) | HExpireTime(cmd) | HPExpireTime(cmd) => cmd.apply(server, client),
+            HPersist(cmd) => cmd.apply(server, client),
+            SAdd(cmd) => cmd.apply(server, client),
+            SRem(cmd) => cmd.apply(server, client),
+            SIsMember(cmd) => cmd.apply(server, client),
+            SMIsMember(cmd) => cmd.apply(server, client),
+            SMembers(cmd) | SCard(cmd) => cmd.apply(server, client),
+            SPop(cmd) => cmd.apply(server, client),
+            SRandMember(cmd) => cmd.apply(server, client),
+            SMove(cmd) => cmd.apply(server, client),
+            SInter(cmd) | SUnion(cmd) | SDiff(cmd) => cmd.apply(server, client),
+            SInterStore(cmd) | SUnionStore(cmd) | SDiffStore(cmd) => cmd.apply(server, client),
+            SInterCard(cmd) => cmd.apply(server, client),
+            SScan(cmd) => cmd.apply(server, client),
+            Unknown(cmd) => cmd.apply(client),
         }
     }
 
     /// Returns the command name
+    #[allow(dead_code)]
     pub(crate) fn get_name(&self) -> &str {
         match self {
             Command::Ping(_) => "ping",
+            Command::Info(_) => "info",
+            Command::CommandIntrospection(_) => "command",
+            Command::Hello(_) => "hello",
+            Command::Select(_) => "select",
+            Command::SwapDb(_) => "swapdb",
+            Command::DbSize(_) => "dbsize",
+            Command::FlushDb(_) => "flushdb",
+            Command::FlushAll(_) => "flushall",
+            Command::Get(_) => "get",
+            Command::GetDel(_) => "getdel",
+            Command::GetEx(_) => "getex",
+            Command::MGet(_) => "mget",
+            Command::Set(_) => "set",
+            Command::SetNx(_) => "setnx",
+            Command::SetEx(_) => "setex",
+            Command::PSetEx(_) => "psetex",
+            Command::MSet(_) => "mset",
+            Command::MSetNx(_) => "msetnx",
+            Command::Append(_) => "append",
+            Command::Strlen(_) => "strlen",
+            Command::GetRange(_) => "getrange",
+            Command::SetRange(_) => "setrange",
+            Command::Incr(_) => "incr",
+            Command::Decr(_) => "decr",
+            Command::IncrBy(_) => "incrby",
+            Command::DecrBy(_) => "decrby",
+            Command::IncrByFloat(_) => "incrbyfloat",
+            Command::Expire(_) => "expire",
+            Command::PExpire(_) => "pexpire",
+            Command::ExpireAt(_) => "expireat",
+            Command::PExpireAt(_) => "pexpireat",
+            Command::Ttl(_) => "ttl",
+            Command::PTtl(_) => "pttl",
+            Command::ExpireTime(_) => "expiretime",
+            Command::PExpireTime(_) => "pexpiretime",
+            Command::Persist(_) => "persist",
+            Command::Del(_) => "del",
+            Command::Unlink(_) => "unlink",
+            Command::Exists(_) => "exists",
+            Command::Touch(_) => "touch",
+            Command::Type(_) => "type",
+            Command::Rename(_) => "rename",
+            Command::RenameNx(_) => "renamenx",
+            Command::Copy(_) => "copy",
+            Command::Move(_) => "move",
+            Command::RandomKey(_) => "randomkey",
+            Command::Keys(_) => "keys",
+            Command::Scan(_) => "scan",
+            Command::Object(_) => "object",
+            Command::LPush(_) => "lpush",
+            Command::RPush(_) => "rpush",
+            Command::LPushX(_) => "lpushx",
+            Command::RPushX(_) => "rpushx",
+            Command::LPop(_) => "lpop",
+            Command::RPop(_) => "rpop",
+            Command::LRange(_) => "lrange",
+            Command::LIndex(_) => "lindex",
+            Command::LSet(_) => "lset",
+            Command::LInsert(_) => "linsert",
+            Command::LRem(_) => "lrem",
+            Command::LTrim(_) => "ltrim",
+            Command::LLen(_) => "llen",
+            Command::LPos(_) => "lpos",
+            Command::LMove(_) => "lmove",
+            Command::LMPop(_) => "lmpop",
+            Command::BLPop(_) => "blpop",
+            Command::BRPop(_) => "brpop",
+            Command::BLMove(_) => "blmove",
+            Command::BLMPop(_) => "blmpop",
+            Command::ZAdd(_) => "zadd",
+            Command::ZCard(_) => "zcard",
+            Command::ZScore(_) => "zscore",
+            Command::ZPopMin(_) => "zpopmin",
+            Command::ZPopMax(_) => "zpopmax",
+            Command::ZMPop(_) => "zmpop",
+            Command::BZPopMin(_) => "bzpopmin",
+            Command::BZPopMax(_) => "bzpopmax",
+            Command::BZMPop(_) => "bzmpop",
+            Command::HSet(_) => "hset",
+            Command::HSetNx(_) => "hsetnx",
+            Command::HGet(_) => "hget",
+            Command::HMGet(_) => "hmget",
+            Command::HDel(_) => "hdel",
+            Command::HExists(_) => "hexists",
+            Command::HLen(_) => "hlen",
+            Command::HKeys(_) => "hkeys",
+            Command::HVals(_) => "hvals",
+            Command::HGetAll(_) => "hgetall",
+            Command::HIncrBy(_) => "hincrby",
+            Command::HIncrByFloat(_) => "hincrbyfloat",
+            Command::HStrlen(_) => "hstrlen",
+            Command::HRandField(_) => "hrandfield",
+            Command::HScan(_) => "hscan",
+            Command::HExpire(_) => "hexpire",
+            Command::HPExpire(_) => "hpexpire",
+            Command::HExpireAt(_) => "hexpireat",
+            Command::HPExpireAt(_) => "hpexpireat",
+            Command::HTtl(_) => "httl",
+            Command::HPTtl(_) => "hpttl",
+            Command::HExpireTime(_) => "hexpiretime",
+            Command::HPExpireTime(_) => "hpexpiretime",
+            Command::HPersist(_) => "hpersist",
+            Command::SAdd(_) => "sadd",
+            Command::SRem(_) => "srem",
+            Command::SIsMember(_) => "sismember",
+            Command::SMIsMember(_) => "smismember",
+            Command::SMembers(_) => "smembers",
+            Command::SCard(_) => "scard",
+            Command::SPop(_) => "spop",
+            Command::SRandMember(_) => "srandmember",
+            Command::SMove(_) => "smove",
+            Command::SInter(_) => "sinter",
+            Command::SUnion(_) => "sunion",
+            Command::SDiff(_) => "sdiff",
+            Command::SInterStore(_) => "sinterstore",
+            Command::SUnionStore(_) => "sunionstore",
+            Command::SDiffStore(_) => "sdiffstore",
+            Command::SInterCard(_) => "sintercard",
+            Command::SScan(_) => "sscan",
             Command::Unknown(cmd) => cmd.get_name(),
         }
     }
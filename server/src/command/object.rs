@@ -0,0 +1,42 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::server::RedisServer;
+
+/// Inspect the internals of the value stored at a key.
+#[derive(Debug)]
+pub struct Object {
+    key: Bytes,
+}
+
+impl Object {
+    /// Parse an `Object` instance from a received frame.
+    ///
+    /// The `OBJECT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// OBJECT ENCODING key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Object> {
+        match parse.next_keyword()?.as_deref() {
+            Some("ENCODING") if parse.remaining() == 1 => Ok(Object { key: parse.next_bytes()? }),
+            Some("ENCODING") => Err(RedisError::WrongArity("object|encoding".to_string())),
+            other => Err(RedisError::Err(format!(
+                "unknown subcommand '{}'. Try OBJECT HELP.",
+                other.unwrap_or_default().to_lowercase()
+            ))),
+        }
+    }
+
+    /// Apply the `Object` command and reply with the name of the encoding of
+    /// the value, nil if the key does not exist.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let encoding = server.keyspace.lock()[dst.db].lookup_key(&self.key).map(|value| value.encoding_name());
+        let response = encoding.map_or(Protocol::Null, |encoding| Protocol::Bulk(Bytes::from_static(encoding.as_bytes())));
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
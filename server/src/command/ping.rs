@@ -1,6 +1,7 @@
-use resp::{self, Result, protocol::Protocol, parse::Parser, parse::ParseError};
+use resp::{protocol::Protocol, parse::Parser};
 use bytes::Bytes;
-use crate::connection::Connection;
+use crate::client::Client;
+use crate::error::Result;
 
 /// Returns PONG if no argument is provided, otherwise
 /// return a copy of the argument as a bulk.
@@ -40,10 +41,9 @@ impl Ping {
     /// PING [message]
     /// ```
     pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Ping> {
-        match parse.next_bytes() {
-            Ok(msg) => Ok(Ping::new(Some(msg))),
-            Err(ParseError::EndOfStream) => Ok(Ping::default()),
-            Err(e) => Err(e.into()),
+        match parse.remaining() {
+            0 => Ok(Ping::default()),
+            _ => Ok(Ping::new(Some(parse.next_bytes()?))),
         }
     }
 
@@ -52,7 +52,7 @@ impl Ping {
     /// The response is written to `dst`. This is called by the server in order
     /// to execute a received command.
     // #[instrument(skip(self, dst))]
-    pub(crate) fn apply(self, dst: &mut Connection) -> Result<()> {
+    pub(crate) fn apply(self, dst: &mut Client) -> Result<()> {
         let response = match self.msg {
             None => Protocol::Simple("PONG".to_string()),
             Some(msg) => Protocol::Bulk(msg),
@@ -61,7 +61,7 @@ impl Ping {
         // debug!(?response);
 
         // Write the response back to the client
-        dst.write_protocol(&response)?;
+        dst.connection.write_protocol(&response)?;
 
         Ok(())
     }
@@ -70,6 +70,7 @@ impl Ping {
     ///
     /// This is called by the client when encoding a `Ping` command to send
     /// to the server.
+    #[allow(dead_code)]
     pub(crate) fn into_frame(self) -> Protocol {
         let mut frame = Protocol::array();
         frame.push_bulk(Bytes::from("ping".as_bytes()));
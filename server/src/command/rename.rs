@@ -0,0 +1,197 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::server::RedisServer;
+
+/// Reply of `RENAMENX`, `COPY` and `MOVE`: 1 if done, 0 if not.
+fn done_reply(done: bool) -> Protocol {
+    Protocol::Integer(done as i64)
+}
+
+/// Check `db` is the index of a database.
+fn check_db_index(server: &RedisServer, db: i64) -> Result<usize> {
+    usize::try_from(db)
+        .ok()
+        .filter(|db| *db < server.config.databases)
+        .ok_or_else(|| RedisError::Err("DB index is out of range".to_string()))
+}
+
+/// Rename key to newkey, `RENAME`, or only if newkey does not exist,
+/// `RENAMENX`. The time to live moves along with the value.
+#[derive(Debug)]
+pub struct Rename {
+    key: Bytes,
+    newkey: Bytes,
+    nx: bool,
+}
+
+impl Rename {
+    /// Parse a `Rename` instance from a received frame.
+    ///
+    /// The `RENAME` or `RENAMENX` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// RENAME key newkey
+    /// RENAMENX key newkey
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, nx: bool) -> Result<Rename> {
+        let key = parse.next_bytes()?;
+        let newkey = parse.next_bytes()?;
+        Ok(Rename { key, newkey, nx })
+    }
+
+    /// Apply the `Rename` command and reply `OK`, or 1 or 0 for `RENAMENX`.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+
+        if db.lookup_key(&self.key).is_none() {
+            return Err(RedisError::Err("no such key".to_string()));
+        }
+        let renamed = if self.key == self.newkey {
+            // Nothing to do, though `RENAMENX` reports the key exists.
+            !self.nx
+        } else if self.nx && db.lookup_key(&self.newkey).is_some() {
+            false
+        } else {
+            if let Some((value, expire)) = db.take_key(&self.key) {
+                db.set_key(self.newkey.clone(), value, false);
+                if let Some(when) = expire {
+                    db.set_expire(self.newkey, when);
+                }
+            }
+            server.incr_dirty(1);
+            true
+        };
+        drop(dbs);
+
+        let response = if self.nx { done_reply(renamed) } else { Protocol::Simple("OK".to_string()) };
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
+
+/// Copy the value of source to destination, in the selected database or
+/// another one, with its time to live.
+#[derive(Debug)]
+pub struct Copy {
+    source: Bytes,
+    destination: Bytes,
+    db: Option<i64>,
+    replace: bool,
+}
+
+impl Copy {
+    /// Parse a `Copy` instance from a received frame.
+    ///
+    /// The `COPY` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// COPY source destination [DB destination-db] [REPLACE]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Copy> {
+        let source = parse.next_bytes()?;
+        let destination = parse.next_bytes()?;
+        let mut copy = Copy { source, destination, db: None, replace: false };
+
+        while let Some(option) = parse.next_keyword()? {
+            match &option[..] {
+                "REPLACE" => copy.replace = true,
+                "DB" if parse.remaining() > 0 => copy.db = Some(parse.next_i64()?),
+                _ => return Err(RedisError::syntax()),
+            }
+        }
+
+        Ok(copy)
+    }
+
+    /// Apply the `Copy` command and reply 1 if the value was copied, 0 if
+    /// source does not exist or destination does and `REPLACE` is not given.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let target_db = match self.db {
+            Some(db) => check_db_index(server, db)?,
+            None => dst.db,
+        };
+        if target_db == dst.db && self.source == self.destination {
+            return Err(RedisError::Err("source and destination objects are the same".to_string()));
+        }
+
+        let mut dbs = server.keyspace.lock();
+        let Some(value) = dbs[dst.db].lookup_key(&self.source).cloned() else {
+            drop(dbs);
+            dst.connection.write_protocol(&done_reply(false))?;
+            return Ok(());
+        };
+        let expire = dbs[dst.db].get_expire(&self.source);
+
+        let db = &mut dbs[target_db];
+        let copied = self.replace || db.lookup_key(&self.destination).is_none();
+        if copied {
+            db.set_key(self.destination.clone(), value, false);
+            if let Some(when) = expire {
+                db.set_expire(self.destination, when);
+            }
+            server.incr_dirty(1);
+        }
+        drop(dbs);
+
+        dst.connection.write_protocol(&done_reply(copied))?;
+        Ok(())
+    }
+}
+
+/// Move key from the selected database to another one, with its time to
+/// live.
+#[derive(Debug)]
+pub struct Move {
+    key: Bytes,
+    db: i64,
+}
+
+impl Move {
+    /// Parse a `Move` instance from a received frame.
+    ///
+    /// The `MOVE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// MOVE key db
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Move> {
+        let key = parse.next_bytes()?;
+        let db = parse.next_i64()?;
+        Ok(Move { key, db })
+    }
+
+    /// Apply the `Move` command and reply 1 if the key was moved, 0 if it does
+    /// not exist or already exists in the target database.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let target_db = check_db_index(server, self.db)?;
+        if target_db == dst.db {
+            return Err(RedisError::Err("source and destination objects are the same".to_string()));
+        }
+
+        let mut dbs = server.keyspace.lock();
+        let moved = dbs[dst.db].lookup_key(&self.key).is_some() && dbs[target_db].lookup_key(&self.key).is_none();
+        if moved {
+            if let Some((value, expire)) = dbs[dst.db].take_key(&self.key) {
+                let db = &mut dbs[target_db];
+                db.set_key(self.key.clone(), value, false);
+                if let Some(when) = expire {
+                    db.set_expire(self.key, when);
+                }
+            }
+            server.incr_dirty(1);
+        }
+        drop(dbs);
+
+        dst.connection.write_protocol(&done_reply(moved))?;
+        Ok(())
+    }
+}
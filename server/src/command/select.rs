@@ -0,0 +1,45 @@
+use resp::{protocol::Protocol, parse::Parser};
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::server::RedisServer;
+use crate::util::string2ll;
+
+/// Select the logical database the following commands of the connection
+/// operate on.
+#[derive(Debug)]
+pub struct Select {
+    index: String,
+}
+
+impl Select {
+    /// Parse a `Select` instance from a received frame.
+    ///
+    /// The `SELECT` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SELECT index
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Select> {
+        let index = parse.next_string()?;
+        Ok(Select { index })
+    }
+
+    /// Apply the `Select` command to `dst`.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let response = match string2ll(self.index.as_bytes()) {
+            None => RedisError::not_integer().into(),
+            Some(index) if index < 0 || index as usize >= server.config.databases => {
+                Protocol::Error("ERR DB index is out of range".to_string())
+            }
+            Some(index) => {
+                dst.db = index as usize;
+                Protocol::Simple("OK".to_string())
+            }
+        };
+
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::db::object::RedisObject;
+use crate::server::RedisServer;
+use crate::util::mstime;
+
+/// Unit of an expire time given to a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExpireUnit {
+    Seconds,
+    Milliseconds,
+}
+
+/// Convert the expire time given to a command into a UNIX time in
+/// milliseconds, Redis' `getExpireMillisecondsOrReply`.
+///
+/// `absolute` tells whether the argument is a UNIX time (`EXAT`, `PXAT`) or
+/// relative to now (`EX`, `PX`). Non-positive times and times that would not
+/// fit once converted are rejected.
+pub(crate) fn parse_expire_time(expire: i64, unit: ExpireUnit, absolute: bool, command: &str) -> Result<i64> {
+    let invalid = || RedisError::Err(format!("invalid expire time in '{}' command", command));
+
+    if expire <= 0 {
+        return Err(invalid());
+    }
+    let mut when = match unit {
+        ExpireUnit::Seconds => expire.checked_mul(1000).ok_or_else(invalid)?,
+        ExpireUnit::Milliseconds => expire,
+    };
+    if !absolute {
+        when = when.checked_add(mstime()).ok_or_else(invalid)?;
+    }
+    Ok(when)
+}
+
+/// Condition on the existence of the key for `SET` to happen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SetCondition {
+    Always,
+    /// `NX`
+    IfNotExists,
+    /// `XX`
+    IfExists,
+}
+
+/// Set key to hold the string value. If key already holds a value, it is
+/// overwritten, regardless of its type.
+#[derive(Debug)]
+pub struct Set {
+    key: Bytes,
+    value: Bytes,
+    condition: SetCondition,
+    /// UNIX time in milliseconds at which the key expires
+    expire_at: Option<i64>,
+    /// keep the time to live of an existing key
+    keep_ttl: bool,
+    /// reply with the previous value
+    get: bool,
+}
+
+impl Set {
+    fn new(key: Bytes, value: Bytes) -> Set {
+        Set {
+            key,
+            value,
+            condition: SetCondition::Always,
+            expire_at: None,
+            keep_ttl: false,
+            get: false,
+        }
+    }
+
+    /// Parse a `Set` instance from a received frame.
+    ///
+    /// The `SET` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SET key value [NX | XX] [GET] [EX seconds | PX milliseconds |
+    ///   EXAT unix-time-seconds | PXAT unix-time-milliseconds | KEEPTTL]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Set> {
+        let key = parse.next_bytes()?;
+        let value = parse.next_bytes()?;
+        let mut set = Set::new(key, value);
+
+        while let Some(option) = parse.next_keyword()? {
+            let has_expire = set.expire_at.is_some() || set.keep_ttl;
+
+            match &option[..] {
+                "NX" if set.condition != SetCondition::IfExists => set.condition = SetCondition::IfNotExists,
+                "XX" if set.condition != SetCondition::IfNotExists => set.condition = SetCondition::IfExists,
+                "GET" => set.get = true,
+                "KEEPTTL" if !has_expire => set.keep_ttl = true,
+                "EX" | "PX" | "EXAT" | "PXAT" if !has_expire && parse.remaining() > 0 => {
+                    let unit = if option.starts_with('E') { ExpireUnit::Seconds } else { ExpireUnit::Milliseconds };
+                    set.expire_at = Some(parse_expire_time(parse.next_i64()?, unit, option.ends_with("AT"), "set")?);
+                }
+                _ => return Err(RedisError::syntax()),
+            }
+        }
+
+        Ok(set)
+    }
+
+    /// Apply the `Set` command and reply `OK`, or nil when the `NX` / `XX`
+    /// condition is not met.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        self.set_generic(server, dst, Protocol::Simple("OK".to_string()), Protocol::Null)
+    }
+
+    /// Shared by all the variants of `SET`, which only differ by their
+    /// replies, Redis' `setGenericCommand`.
+    ///
+    /// With `GET`, the reply is the previous value whatever the outcome, and
+    /// nothing is done if the key holds something other than a string.
+    fn set_generic(self, server: &RedisServer, dst: &mut Client, ok_reply: Protocol, abort_reply: Protocol) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+
+        // `None` for a missing key, `Some(None)` for a key of another type.
+        let old = db.lookup_key(&self.key).map(|value| value.string_value());
+        let old_reply = match &old {
+            Some(Some(value)) => Protocol::Bulk(value.clone()),
+            _ => Protocol::Null,
+        };
+
+        let response = if self.get && matches!(old, Some(None)) {
+            RedisError::WrongType.into()
+        } else if (self.condition == SetCondition::IfNotExists && old.is_some())
+            || (self.condition == SetCondition::IfExists && old.is_none()) {
+            if self.get { old_reply } else { abort_reply }
+        } else {
+            db.set_key(self.key.clone(), RedisObject::string(self.value), self.keep_ttl);
+            if let Some(when) = self.expire_at {
+                db.set_expire(self.key, when);
+            }
+            server.incr_dirty(1);
+            if self.get { old_reply } else { ok_reply }
+        };
+        drop(dbs);
+
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
+
+/// Set key to hold the string value if it does not exist.
+#[derive(Debug)]
+pub struct SetNx {
+    set: Set,
+}
+
+impl SetNx {
+    /// Parse a `SetNx` instance from a received frame.
+    ///
+    /// The `SETNX` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SETNX key value
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<SetNx> {
+        let mut set = Set::new(parse.next_bytes()?, parse.next_bytes()?);
+        set.condition = SetCondition::IfNotExists;
+        Ok(SetNx { set })
+    }
+
+    /// Apply the `SetNx` command and reply 1 if the key was set, 0 otherwise.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        self.set.set_generic(server, dst, Protocol::Integer(1), Protocol::Integer(0))
+    }
+}
+
+/// Set key to hold the string value and expire after a number of seconds,
+/// `SETEX`, or of milliseconds, `PSETEX`.
+#[derive(Debug)]
+pub struct SetEx {
+    set: Set,
+}
+
+impl SetEx {
+    /// Parse a `SetEx` instance from a received frame.
+    ///
+    /// The `SETEX` or `PSETEX` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SETEX key seconds value
+    /// PSETEX key milliseconds value
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, unit: ExpireUnit) -> Result<SetEx> {
+        let command = match unit {
+            ExpireUnit::Seconds => "setex",
+            ExpireUnit::Milliseconds => "psetex",
+        };
+        let key = parse.next_bytes()?;
+        let expire = parse.next_i64()?;
+        let value = parse.next_bytes()?;
+
+        let mut set = Set::new(key, value);
+        set.expire_at = Some(parse_expire_time(expire, unit, false, command)?);
+        Ok(SetEx { set })
+    }
+
+    /// Apply the `SetEx` command and reply `OK`.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        self.set.apply(server, dst)
+    }
+}
+
+/// Set the given keys to their respective values, `MSET`, or only if none
+/// of them exists, `MSETNX`.
+#[derive(Debug)]
+pub struct MSet {
+    pairs: Vec<(Bytes, Bytes)>,
+    nx: bool,
+}
+
+impl MSet {
+    /// Parse a `MSet` instance from a received frame.
+    ///
+    /// The `MSET` or `MSETNX` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// MSET key value [key value ...]
+    /// MSETNX key value [key value ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, nx: bool) -> Result<MSet> {
+        let mut pairs = vec![(parse.next_bytes()?, parse.next_bytes()?)];
+        while parse.remaining() > 0 {
+            pairs.push((parse.next_bytes()?, parse.next_bytes()?));
+        }
+        Ok(MSet { pairs, nx })
+    }
+
+    /// Apply the `MSet` command and reply `OK`, or for `MSETNX` 1 if the keys
+    /// were set and 0 otherwise.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+
+        if self.nx && self.pairs.iter().any(|(key, _)| db.lookup_key(key).is_some()) {
+            drop(dbs);
+            dst.connection.write_protocol(&Protocol::Integer(0))?;
+            return Ok(());
+        }
+
+        let count = self.pairs.len() as u64;
+        for (key, value) in self.pairs {
+            db.set_key(key, RedisObject::string(value), false);
+        }
+        server.incr_dirty(count);
+        drop(dbs);
+
+        let response = if self.nx { Protocol::Integer(1) } else { Protocol::Simple("OK".to_string()) };
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
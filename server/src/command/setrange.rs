@@ -0,0 +1,75 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::{Bytes, BytesMut};
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::command::check_string_length;
+use crate::db::object::RedisObject;
+use crate::server::RedisServer;
+
+/// Overwrite part of the string value of key starting at `offset`, padding
+/// it with zero bytes if needed.
+#[derive(Debug)]
+pub struct SetRange {
+    key: Bytes,
+    offset: usize,
+    value: Bytes,
+}
+
+impl SetRange {
+    /// Parse a `SetRange` instance from a received frame.
+    ///
+    /// The `SETRANGE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SETRANGE key offset value
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<SetRange> {
+        let key = parse.next_bytes()?;
+        let offset = usize::try_from(parse.next_i64()?).map_err(|_| RedisError::Err("offset is out of range".to_string()))?;
+        let value = parse.next_bytes()?;
+        Ok(SetRange { key, offset, value })
+    }
+
+    /// Apply the `SetRange` command and reply with the new length of the
+    /// string. An empty value leaves the key untouched.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+
+        let old = match db.lookup_key(&self.key).map(|value| value.string_value()) {
+            Some(None) => {
+                drop(dbs);
+                dst.connection.write_protocol(&RedisError::WrongType.into())?;
+                return Ok(());
+            }
+            Some(Some(old)) => Some(old),
+            None => None,
+        };
+
+        let old_len = old.as_ref().map_or(0, |old| old.len());
+        let response = if self.value.is_empty() {
+            Protocol::Integer(old_len as i64)
+        } else {
+            match check_string_length(server, self.offset.saturating_add(self.value.len())) {
+                Err(err) => err.into(),
+                Ok(()) => {
+                    let len = old_len.max(self.offset + self.value.len());
+                    let mut value = BytesMut::zeroed(len);
+                    if let Some(old) = &old {
+                        value[..old_len].copy_from_slice(old);
+                    }
+                    value[self.offset..self.offset + self.value.len()].copy_from_slice(&self.value);
+                    db.set_key(self.key, RedisObject::RawString(value.freeze()), true);
+                    server.incr_dirty(1);
+                    Protocol::Integer(len as i64)
+                }
+            }
+        };
+        drop(dbs);
+
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
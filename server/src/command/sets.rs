@@ -0,0 +1,674 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::command::keys::ScanOptions;
+use crate::db::{keyspace::Db, object::RedisObject, set::Set};
+use crate::error::{RedisError, Result};
+use crate::server::RedisServer;
+use crate::util::random;
+
+/// The set stored at key, `None` if the key does not exist.
+fn lookup_set<'a>(db: &'a mut Db, key: &[u8]) -> Result<Option<&'a mut Set>> {
+    match db.lookup_key(key) {
+        None => Ok(None),
+        Some(RedisObject::Set(set)) => Ok(Some(set)),
+        Some(_) => Err(RedisError::WrongType),
+    }
+}
+
+/// An empty set, encoded as an intset.
+fn new_set(server: &RedisServer) -> Set {
+    let config = &server.config;
+    Set::new(config.set_max_intset_entries, config.set_max_listpack_entries, config.set_max_listpack_value)
+}
+
+/// The set stored at key, created empty if the key does not exist, encoded
+/// for `size_hint` members the first being `member`.
+fn lookup_or_create_set<'a>(
+    server: &RedisServer,
+    db: &'a mut Db,
+    key: &Bytes,
+    member: &[u8],
+    size_hint: usize,
+) -> Result<&'a mut Set> {
+    if lookup_set(db, key)?.is_none() {
+        let mut set = new_set(server);
+        set.presize(member, size_hint);
+        db.set_key(key.clone(), RedisObject::Set(set), false);
+    }
+    Ok(lookup_set(db, key)?.expect("the set was just created"))
+}
+
+fn members_reply(members: impl Iterator<Item = Bytes>) -> Protocol {
+    Protocol::Set(members.map(Protocol::Bulk).collect())
+}
+
+/// Parse the `member [member ...]` ending a command.
+fn parse_members(parse: &mut Parser) -> Result<Vec<Bytes>> {
+    let mut members = vec![parse.next_bytes()?];
+    for member in parse.remaining_bytes() {
+        members.push(member?);
+    }
+    Ok(members)
+}
+
+/// Add members to a set.
+#[derive(Debug)]
+pub struct SAdd {
+    key: Bytes,
+    members: Vec<Bytes>,
+}
+
+impl SAdd {
+    /// Parse a `SAdd` instance from a received frame.
+    ///
+    /// The `SADD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SADD key member [member ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<SAdd> {
+        let key = parse.next_bytes()?;
+        let members = parse_members(parse)?;
+        Ok(SAdd { key, members })
+    }
+
+    /// Apply the `SAdd` command and reply with the number of members added.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let set = lookup_or_create_set(server, &mut dbs[dst.db], &self.key, &self.members[0], self.members.len())?;
+        let added = self.members.into_iter().filter(|member| set.insert(member.clone())).count();
+        drop(dbs);
+        server.incr_dirty(added as u64);
+
+        dst.connection.write_protocol(&Protocol::Integer(added as i64))?;
+        Ok(())
+    }
+}
+
+/// Remove members from a set.
+#[derive(Debug)]
+pub struct SRem {
+    key: Bytes,
+    members: Vec<Bytes>,
+}
+
+impl SRem {
+    /// Parse a `SRem` instance from a received frame.
+    ///
+    /// The `SREM` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SREM key member [member ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<SRem> {
+        let key = parse.next_bytes()?;
+        let members = parse_members(parse)?;
+        Ok(SRem { key, members })
+    }
+
+    /// Apply the `SRem` command and reply with the number of members
+    /// removed. The key is deleted with its last member.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        let mut removed = 0;
+        if let Some(set) = lookup_set(db, &self.key)? {
+            removed = self.members.iter().filter(|member| set.remove(member)).count();
+            if set.is_empty() {
+                db.delete_key(&self.key);
+            }
+        }
+        drop(dbs);
+        server.incr_dirty(removed as u64);
+
+        dst.connection.write_protocol(&Protocol::Integer(removed as i64))?;
+        Ok(())
+    }
+}
+
+/// Determine whether a member belongs to a set.
+#[derive(Debug)]
+pub struct SIsMember {
+    key: Bytes,
+    member: Bytes,
+}
+
+impl SIsMember {
+    /// Parse a `SIsMember` instance from a received frame.
+    ///
+    /// The `SISMEMBER` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SISMEMBER key member
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<SIsMember> {
+        let key = parse.next_bytes()?;
+        let member = parse.next_bytes()?;
+        Ok(SIsMember { key, member })
+    }
+
+    /// Apply the `SIsMember` command and reply with 1 if the member belongs
+    /// to the set, 0 otherwise.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let exists = lookup_set(&mut server.keyspace.lock()[dst.db], &self.key)?.is_some_and(|set| set.contains(&self.member));
+        dst.connection.write_protocol(&Protocol::Integer(exists as i64))?;
+        Ok(())
+    }
+}
+
+/// Determine whether members belong to a set.
+#[derive(Debug)]
+pub struct SMIsMember {
+    key: Bytes,
+    members: Vec<Bytes>,
+}
+
+impl SMIsMember {
+    /// Parse a `SMIsMember` instance from a received frame.
+    ///
+    /// The `SMISMEMBER` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SMISMEMBER key member [member ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<SMIsMember> {
+        let key = parse.next_bytes()?;
+        let members = parse_members(parse)?;
+        Ok(SMIsMember { key, members })
+    }
+
+    /// Apply the `SMIsMember` command and reply with 1 or 0 for every
+    /// member.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let set = lookup_set(&mut dbs[dst.db], &self.key)?;
+        let replies = self
+            .members
+            .iter()
+            .map(|member| Protocol::Integer(set.as_ref().is_some_and(|set| set.contains(member)) as i64))
+            .collect();
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Array(replies))?;
+        Ok(())
+    }
+}
+
+/// Return every member of a set, `SMEMBERS`, or their number, `SCARD`.
+#[derive(Debug)]
+pub struct SMembers {
+    key: Bytes,
+    card: bool,
+}
+
+impl SMembers {
+    /// Parse a `SMembers` instance from a received frame.
+    ///
+    /// The `SMEMBERS` or `SCARD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SMEMBERS key
+    /// SCARD key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, card: bool) -> Result<SMembers> {
+        let key = parse.next_bytes()?;
+        Ok(SMembers { key, card })
+    }
+
+    /// Apply the `SMembers` command and reply with the members, or their
+    /// number. A missing key is an empty set.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let set = lookup_set(&mut dbs[dst.db], &self.key)?;
+        let response = match set {
+            _ if self.card => Protocol::Integer(set.map_or(0, |set| set.len()) as i64),
+            None => Protocol::Set(vec![]),
+            Some(set) => members_reply(set.iter()),
+        };
+        drop(dbs);
+
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
+
+/// Remove and return random members of a set.
+#[derive(Debug)]
+pub struct SPop {
+    key: Bytes,
+    count: Option<usize>,
+}
+
+impl SPop {
+    /// Parse a `SPop` instance from a received frame.
+    ///
+    /// The `SPOP` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SPOP key [count]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<SPop> {
+        let key = parse.next_bytes()?;
+        let count = match parse.remaining() {
+            0 => None,
+            1 => Some(
+                parse
+                    .next_i64()
+                    .ok()
+                    .and_then(|count| usize::try_from(count).ok())
+                    .ok_or_else(|| RedisError::Err("value is out of range, must be positive".to_string()))?,
+            ),
+            _ => return Err(RedisError::syntax()),
+        };
+        Ok(SPop { key, count })
+    }
+
+    /// Apply the `SPop` command and reply with a random member, or a set of
+    /// up to `count` distinct members. The key is deleted with its last
+    /// member.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        let mut members = vec![];
+        if let Some(set) = lookup_set(db, &self.key)? {
+            let count = self.count.unwrap_or(1);
+            if count >= set.len() {
+                members = set.iter().collect();
+                db.delete_key(&self.key);
+            } else {
+                members = (0..count).filter_map(|_| set.pop_random()).collect();
+            }
+        }
+        drop(dbs);
+        server.incr_dirty(members.len() as u64);
+
+        let response = match self.count {
+            None => members.pop().map_or(Protocol::Null, Protocol::Bulk),
+            Some(_) => members_reply(members.into_iter()),
+        };
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
+
+/// Return random members of a set.
+#[derive(Debug)]
+pub struct SRandMember {
+    key: Bytes,
+    count: Option<i64>,
+}
+
+impl SRandMember {
+    /// Parse a `SRandMember` instance from a received frame.
+    ///
+    /// The `SRANDMEMBER` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SRANDMEMBER key [count]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<SRandMember> {
+        let key = parse.next_bytes()?;
+        let count = match parse.remaining() {
+            0 => None,
+            1 => Some(parse.next_i64()?),
+            _ => return Err(RedisError::syntax()),
+        };
+        if count == Some(i64::MIN) {
+            return Err(RedisError::Err("value is out of range".to_string()));
+        }
+        Ok(SRandMember { key, count })
+    }
+
+    /// Apply the `SRandMember` command and reply with a random member, or an
+    /// array of `count` members, distinct if `count` is positive and
+    /// possibly repeated if negative.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let set = lookup_set(&mut dbs[dst.db], &self.key)?;
+        let Some(count) = self.count else {
+            let member = set.and_then(|set| set.random_member());
+            drop(dbs);
+            dst.connection.write_protocol(&member.map_or(Protocol::Null, Protocol::Bulk))?;
+            return Ok(());
+        };
+
+        let members: Vec<Bytes> = match set {
+            None => vec![],
+            Some(set) if count < 0 => (0..count.unsigned_abs()).filter_map(|_| set.random_member()).collect(),
+            Some(set) => {
+                // Shuffle the first `count` members in, Fisher-Yates.
+                let mut members: Vec<Bytes> = set.iter().collect();
+                let count = members.len().min(count as usize);
+                for i in 0..count {
+                    let j = i + random() as usize % (members.len() - i);
+                    members.swap(i, j);
+                }
+                members.truncate(count);
+                members
+            }
+        };
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Array(members.into_iter().map(Protocol::Bulk).collect()))?;
+        Ok(())
+    }
+}
+
+/// Move a member from a set to another.
+#[derive(Debug)]
+pub struct SMove {
+    source: Bytes,
+    destination: Bytes,
+    member: Bytes,
+}
+
+impl SMove {
+    /// Parse a `SMove` instance from a received frame.
+    ///
+    /// The `SMOVE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SMOVE source destination member
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<SMove> {
+        let source = parse.next_bytes()?;
+        let destination = parse.next_bytes()?;
+        let member = parse.next_bytes()?;
+        Ok(SMove { source, destination, member })
+    }
+
+    /// Apply the `SMove` command and reply with 1 if the member was moved, 0
+    /// if it does not belong to the source. The source is deleted with its
+    /// last member.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        // Both keys are checked before anything is moved.
+        let exists = lookup_set(db, &self.source)?.is_some();
+        lookup_set(db, &self.destination)?;
+
+        let moved = if !exists {
+            false
+        } else if self.source == self.destination {
+            lookup_set(db, &self.source)?.is_some_and(|set| set.contains(&self.member))
+        } else {
+            let source = lookup_set(db, &self.source)?.expect("the source exists");
+            if source.remove(&self.member) {
+                if source.is_empty() {
+                    db.delete_key(&self.source);
+                }
+                lookup_or_create_set(server, db, &self.destination, &self.member, 1)?.insert(self.member);
+                true
+            } else {
+                false
+            }
+        };
+        drop(dbs);
+        if moved && self.source != self.destination {
+            server.incr_dirty(1);
+        }
+
+        dst.connection.write_protocol(&Protocol::Integer(moved as i64))?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum SetOp {
+    Inter,
+    Union,
+    Diff,
+}
+
+/// Members of every set at `keys`, smallest set first. `None` if a key
+/// does not exist.
+fn intersect(db: &mut Db, keys: &[Bytes]) -> Result<Option<Vec<Bytes>>> {
+    let mut lens = Vec::with_capacity(keys.len());
+    for key in keys {
+        lens.push(lookup_set(db, key)?.map(|set| set.len()));
+    }
+    let Some(lens) = lens.into_iter().collect::<Option<Vec<_>>>() else {
+        return Ok(None);
+    };
+    let smallest = (0..keys.len()).min_by_key(|i| lens[*i]).expect("at least one key");
+    let mut members: Vec<Bytes> = lookup_set(db, &keys[smallest])?.expect("the key exists").iter().collect();
+    for (i, key) in keys.iter().enumerate() {
+        if i != smallest && !members.is_empty() {
+            let set = lookup_set(db, key)?.expect("the key exists");
+            members.retain(|member| set.contains(member));
+        }
+    }
+    Ok(Some(members))
+}
+
+/// Intersection, `SINTER`, union, `SUNION`, or difference, `SDIFF`, of
+/// sets, stored at `destination` by the `STORE` variants.
+#[derive(Debug)]
+pub struct SetOperation {
+    op: SetOp,
+    destination: Option<Bytes>,
+    keys: Vec<Bytes>,
+}
+
+impl SetOperation {
+    /// Parse a `SetOperation` instance from a received frame.
+    ///
+    /// The command name has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SINTER key [key ...]
+    /// SUNION key [key ...]
+    /// SDIFF key [key ...]
+    /// SINTERSTORE destination key [key ...]
+    /// SUNIONSTORE destination key [key ...]
+    /// SDIFFSTORE destination key [key ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, op: SetOp, store: bool) -> Result<SetOperation> {
+        let destination = if store { Some(parse.next_bytes()?) } else { None };
+        let keys = parse_members(parse)?;
+        Ok(SetOperation { op, destination, keys })
+    }
+
+    /// The resulting set, Redis' `sinterGenericCommand` and
+    /// `sunionDiffGenericCommand`. Missing keys are empty sets.
+    fn combine(&self, server: &RedisServer, db: &mut Db) -> Result<Set> {
+        let mut result = new_set(server);
+        match self.op {
+            SetOp::Inter => {
+                for member in intersect(db, &self.keys)?.unwrap_or_default() {
+                    result.insert(member);
+                }
+            }
+            SetOp::Union => {
+                for key in &self.keys {
+                    if let Some(set) = lookup_set(db, key)? {
+                        for member in set.iter() {
+                            result.insert(member);
+                        }
+                    }
+                }
+            }
+            SetOp::Diff => {
+                // Every key is checked, even once the difference is empty.
+                for (i, key) in self.keys.iter().enumerate() {
+                    let Some(set) = lookup_set(db, key)? else { continue };
+                    if i == 0 {
+                        for member in set.iter() {
+                            result.insert(member);
+                        }
+                    } else if !result.is_empty() {
+                        for member in set.iter() {
+                            result.remove(&member);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Apply the `SetOperation` command and reply with the resulting set,
+    /// or its cardinality once stored. An empty result deletes the
+    /// destination.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        let result = self.combine(server, db)?;
+        let Some(destination) = self.destination else {
+            let response = members_reply(result.iter());
+            drop(dbs);
+            dst.connection.write_protocol(&response)?;
+            return Ok(());
+        };
+
+        let len = result.len();
+        if result.is_empty() {
+            db.delete_key(&destination);
+        } else {
+            db.set_key(destination, RedisObject::Set(result), false);
+        }
+        drop(dbs);
+        server.incr_dirty(1);
+
+        dst.connection.write_protocol(&Protocol::Integer(len as i64))?;
+        Ok(())
+    }
+}
+
+/// Return the cardinality of the intersection of sets.
+#[derive(Debug)]
+pub struct SInterCard {
+    keys: Vec<Bytes>,
+    /// Stop counting at `limit` members, 0 for no limit.
+    limit: usize,
+}
+
+impl SInterCard {
+    /// Parse a `SInterCard` instance from a received frame.
+    ///
+    /// The `SINTERCARD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SINTERCARD numkeys key [key ...] [LIMIT limit]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<SInterCard> {
+        let numkeys = parse
+            .next_i64()
+            .ok()
+            .and_then(|numkeys| usize::try_from(numkeys).ok())
+            .filter(|numkeys| *numkeys > 0)
+            .ok_or_else(|| RedisError::Err("numkeys should be greater than 0".to_string()))?;
+        if numkeys > parse.remaining() {
+            return Err(RedisError::Err("Number of keys can't be greater than number of args".to_string()));
+        }
+        let mut keys = Vec::with_capacity(numkeys);
+        for _ in 0..numkeys {
+            keys.push(parse.next_bytes()?);
+        }
+
+        let mut limit = 0;
+        while let Some(option) = parse.next_keyword()? {
+            match &option[..] {
+                "LIMIT" if parse.remaining() > 0 => {
+                    limit = parse
+                        .next_i64()
+                        .ok()
+                        .and_then(|limit| usize::try_from(limit).ok())
+                        .ok_or_else(|| RedisError::Err("LIMIT can't be negative".to_string()))?;
+                }
+                _ => return Err(RedisError::syntax()),
+            }
+        }
+        Ok(SInterCard { keys, limit })
+    }
+
+    /// Apply the `SInterCard` command and reply with the cardinality of the
+    /// intersection, at most `limit` if given.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let len = intersect(&mut server.keyspace.lock()[dst.db], &self.keys)?.map_or(0, |members| members.len());
+        let len = if self.limit > 0 { len.min(self.limit) } else { len };
+        dst.connection.write_protocol(&Protocol::Integer(len as i64))?;
+        Ok(())
+    }
+}
+
+/// Incrementally iterate the members of a set.
+#[derive(Debug)]
+pub struct SScan {
+    key: Bytes,
+    options: ScanOptions,
+}
+
+impl SScan {
+    /// Parse a `SScan` instance from a received frame.
+    ///
+    /// The `SSCAN` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SSCAN key cursor [MATCH pattern] [COUNT count]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<SScan> {
+        let key = parse.next_bytes()?;
+        let mut options = ScanOptions::parse_cursor(parse)?;
+        while let Some(option) = parse.next_keyword()? {
+            if !options.parse_option(&option, parse)? {
+                return Err(RedisError::syntax());
+            }
+        }
+        Ok(SScan { key, options })
+    }
+
+    /// Apply the `SScan` command and reply with the next cursor and a batch
+    /// of members. A set still encoded as an intset or a listpack is
+    /// returned in a single batch.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let mut elements = vec![];
+        let mut cursor = 0;
+        if let Some(set) = lookup_set(&mut dbs[dst.db], &self.key)? {
+            cursor = self.options.cursor;
+            let mut max_iterations = self.options.max_iterations();
+            loop {
+                cursor = set.scan(cursor, |member| {
+                    if self.options.matches(member) {
+                        elements.push(Protocol::Bulk(member.clone()));
+                    }
+                });
+                max_iterations -= 1;
+                if cursor == 0 || max_iterations == 0 || elements.len() >= self.options.count {
+                    break;
+                }
+            }
+        }
+        drop(dbs);
+
+        dst.connection.write_protocol(&Protocol::Array(vec![
+            Protocol::Bulk(Bytes::from(cursor.to_string())),
+            Protocol::Array(elements),
+        ]))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use resp::{protocol::Protocol, parse::Parser};
+use bytes::Bytes;
+use crate::client::Client;
+use crate::error::{RedisError, Result};
+use crate::server::RedisServer;
+
+/// Return the length of the string value of key, 0 if it does not exist.
+#[derive(Debug)]
+pub struct Strlen {
+    key: Bytes,
+}
+
+impl Strlen {
+    /// Parse a `Strlen` instance from a received frame.
+    ///
+    /// The `STRLEN` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// STRLEN key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<Strlen> {
+        let key = parse.next_bytes()?;
+        Ok(Strlen { key })
+    }
+
+    /// Apply the `Strlen` command and reply with the length.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let value = server.keyspace.lock()[dst.db].lookup_key(&self.key).map(|value| value.string_value());
+        let response = match value {
+            None => Protocol::Integer(0),
+            Some(None) => RedisError::WrongType.into(),
+            Some(Some(value)) => Protocol::Integer(value.len() as i64),
+        };
+
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
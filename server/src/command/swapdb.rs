@@ -0,0 +1,51 @@
+use resp::{protocol::Protocol, parse::Parser};
+use crate::client::Client;
+use crate::error::Result;
+use crate::db::keyspace::swap_databases;
+use crate::server::RedisServer;
+use crate::util::string2ll;
+
+/// Swap two logical databases. Clients connected to either one immediately
+/// see the contents of the other.
+#[derive(Debug)]
+pub struct SwapDb {
+    index1: String,
+    index2: String,
+}
+
+impl SwapDb {
+    /// Parse a `SwapDb` instance from a received frame.
+    ///
+    /// The `SWAPDB` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// SWAPDB index1 index2
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<SwapDb> {
+        let index1 = parse.next_string()?;
+        let index2 = parse.next_string()?;
+        Ok(SwapDb { index1, index2 })
+    }
+
+    /// Apply the `SwapDb` command and reply to `dst`.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let databases = server.config.databases as i64;
+        let response = match (string2ll(self.index1.as_bytes()), string2ll(self.index2.as_bytes())) {
+            (None, _) => Protocol::Error("ERR invalid first DB index".to_string()),
+            (_, None) => Protocol::Error("ERR invalid second DB index".to_string()),
+            (Some(id1), Some(id2)) if !(0..databases).contains(&id1) || !(0..databases).contains(&id2) => {
+                Protocol::Error("ERR DB index is out of range".to_string())
+            }
+            (Some(id1), Some(id2)) => {
+                swap_databases(&mut server.keyspace.lock(), id1 as usize, id2 as usize);
+                server.incr_dirty(1);
+                Protocol::Simple("OK".to_string())
+            }
+        };
+
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,366 @@
+//! The command table, Redis' `redisCommandTable`.
+//!
+//! Every command the server knows is described here: how many arguments it
+//! takes, what it does to the dataset, where its keys are and which ACL
+//! categories it belongs to. Arity is checked against it before a command is
+//! parsed, and `COMMAND` exposes it to clients.
+
+use ahash::AHashMap;
+use bytes::Bytes;
+use lazy_static::lazy_static;
+use resp::util::string2ll;
+
+/// The command may modify the dataset.
+pub(crate) const CMD_WRITE: u32 = 1 << 0;
+/// The command never modifies the dataset.
+pub(crate) const CMD_READONLY: u32 = 1 << 1;
+/// The command may grow memory usage, refused once `maxmemory` is reached.
+pub(crate) const CMD_DENYOOM: u32 = 1 << 2;
+/// Administrative command.
+pub(crate) const CMD_ADMIN: u32 = 1 << 3;
+/// Publish/subscribe related command.
+pub(crate) const CMD_PUBSUB: u32 = 1 << 4;
+/// The command is not allowed in scripts.
+pub(crate) const CMD_NOSCRIPT: u32 = 1 << 5;
+/// The command is allowed while the dataset is loading.
+pub(crate) const CMD_LOADING: u32 = 1 << 6;
+/// The command is allowed on a replica with stale data.
+pub(crate) const CMD_STALE: u32 = 1 << 7;
+/// The command runs in constant or logarithmic time.
+pub(crate) const CMD_FAST: u32 = 1 << 8;
+/// The command may block the client.
+pub(crate) const CMD_BLOCKING: u32 = 1 << 9;
+/// The keys cannot be found from the legacy key positions, set along with a
+/// `Keynum` spec.
+pub(crate) const CMD_MOVABLE_KEYS: u32 = 1 << 10;
+
+/// Names of the flags, as listed by `COMMAND INFO`.
+const FLAG_NAMES: [(u32, &str); 11] = [
+    (CMD_WRITE, "write"),
+    (CMD_READONLY, "readonly"),
+    (CMD_DENYOOM, "denyoom"),
+    (CMD_ADMIN, "admin"),
+    (CMD_PUBSUB, "pubsub"),
+    (CMD_NOSCRIPT, "noscript"),
+    (CMD_LOADING, "loading"),
+    (CMD_STALE, "stale"),
+    (CMD_FAST, "fast"),
+    (CMD_BLOCKING, "blocking"),
+    (CMD_MOVABLE_KEYS, "movablekeys"),
+];
+
+/// ACL categories, in the order Redis lists them.
+const ACL_CATEGORIES: [&str; 21] = [
+    "keyspace", "read", "write", "set", "sortedset", "list", "hash", "string", "bitmap", "hyperloglog",
+    "geo", "stream", "pubsub", "admin", "fast", "slow", "blocking", "dangerous", "connection",
+    "transaction", "scripting",
+];
+
+/// Keys preceded by their count, Redis' `KSPEC_FK_KEYNUM` key spec with its
+/// search beginning at a fixed index.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Keynum {
+    /// Position of the number of keys, the command name included.
+    pub(crate) index: i64,
+    /// Position of the first key, relative to `index`.
+    pub(crate) first_key: i64,
+}
+
+/// Description of a command, Redis' `struct redisCommand`.
+#[derive(Debug)]
+pub(crate) struct CommandSpec {
+    pub(crate) name: &'static str,
+    /// Number of arguments, the command name included. A negative arity
+    /// `-n` means at least `n`.
+    pub(crate) arity: i64,
+    /// `CMD_*` flags.
+    pub(crate) flags: u32,
+    /// Position of the first key, 0 if the command takes no key.
+    pub(crate) first_key: i64,
+    /// Position of the last key, negative to count from the end.
+    pub(crate) last_key: i64,
+    /// Step between two keys, 2 for `key value` pairs.
+    pub(crate) key_step: i64,
+    /// Where the keys are when their number is an argument, in which case
+    /// the legacy positions above are all 0.
+    pub(crate) keynum: Option<Keynum>,
+    /// ACL categories other than the ones implied by the flags.
+    acl_categories: &'static [&'static str],
+    group: &'static str,
+    since: &'static str,
+    summary: &'static str,
+}
+
+impl CommandSpec {
+    #[allow(clippy::too_many_arguments)]
+    const fn new(
+        name: &'static str,
+        arity: i64,
+        flags: u32,
+        (first_key, last_key, key_step): (i64, i64, i64),
+        acl_categories: &'static [&'static str],
+        group: &'static str,
+        since: &'static str,
+        summary: &'static str,
+    ) -> Self {
+        Self { name, arity, flags, first_key, last_key, key_step, keynum: None, acl_categories, group, since, summary }
+    }
+
+    /// The same command with its keys found through `keynum`, which makes
+    /// them movable.
+    const fn with_keynum(self, keynum: Keynum) -> Self {
+        Self { flags: self.flags | CMD_MOVABLE_KEYS, keynum: Some(keynum), ..self }
+    }
+
+    /// Returns `true` if the command takes keys, Redis' `doesCommandHaveKeys`.
+    pub(crate) fn has_keys(&self) -> bool {
+        self.first_key != 0 || self.keynum.is_some()
+    }
+
+    /// Returns `true` if `argc` arguments, the command name included, satisfy
+    /// the arity.
+    pub(crate) fn check_arity(&self, argc: usize) -> bool {
+        let argc = argc as i64;
+        if self.arity >= 0 { argc == self.arity } else { argc >= -self.arity }
+    }
+
+    /// Names of the flags set.
+    pub(crate) fn flag_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        FLAG_NAMES.iter().filter(|(flag, _)| self.flags & flag != 0).map(|(_, name)| *name)
+    }
+
+    /// ACL categories of the command, including the ones implied by its
+    /// flags as Redis' `setImplicitACLCategories` adds them.
+    pub(crate) fn acl_categories(&self) -> Vec<&'static str> {
+        let implied = |category: &str| match category {
+            "write" => self.flags & CMD_WRITE != 0,
+            "read" => self.flags & CMD_READONLY != 0,
+            "admin" => self.flags & CMD_ADMIN != 0,
+            "dangerous" => self.flags & CMD_ADMIN != 0,
+            "pubsub" => self.flags & CMD_PUBSUB != 0,
+            "fast" => self.flags & CMD_FAST != 0,
+            "slow" => self.flags & CMD_FAST == 0,
+            _ => false,
+        };
+        ACL_CATEGORIES
+            .iter()
+            .copied()
+            .filter(|category| implied(category) || self.acl_categories.contains(category))
+            .collect()
+    }
+
+    /// The keys among `args`, the command name included, Redis'
+    /// `getKeysFromCommandWithSpecs`. Returns `None` if the number of keys is
+    /// not a count or more keys are announced than there are arguments.
+    pub(crate) fn get_keys(&self, args: &[Bytes]) -> Option<Vec<Bytes>> {
+        if let Some(Keynum { index, first_key }) = self.keynum {
+            let numkeys = args.get(index as usize).and_then(|arg| string2ll(arg)).filter(|n| *n >= 0)?;
+            let first = (index + first_key) as usize;
+            return args.get(first..first + numkeys as usize).map(<[Bytes]>::to_vec);
+        }
+        if self.first_key == 0 {
+            return Some(vec![]);
+        }
+        let last = if self.last_key < 0 { args.len() as i64 + self.last_key } else { self.last_key };
+        let keys = (self.first_key..=last)
+            .step_by(self.key_step as usize)
+            .filter_map(|pos| args.get(pos as usize).cloned())
+            .collect();
+        Some(keys)
+    }
+
+    pub(crate) fn group(&self) -> &'static str {
+        self.group
+    }
+
+    pub(crate) fn since(&self) -> &'static str {
+        self.since
+    }
+
+    pub(crate) fn summary(&self) -> &'static str {
+        self.summary
+    }
+}
+
+const NO_KEYS: (i64, i64, i64) = (0, 0, 0);
+const ONE_KEY: (i64, i64, i64) = (1, 1, 1);
+const ALL_KEYS: (i64, i64, i64) = (1, -1, 1);
+const KEY_VALUE_PAIRS: (i64, i64, i64) = (1, -1, 2);
+/// Number of keys as the first argument, followed by the keys.
+const NUMKEYS_FIRST: Keynum = Keynum { index: 1, first_key: 1 };
+/// Number of keys as the second argument, after a timeout.
+const NUMKEYS_SECOND: Keynum = Keynum { index: 2, first_key: 1 };
+
+/// Every command, in no particular order.
+const COMMANDS: &[CommandSpec] = &[
+    // connection
+    CommandSpec::new("ping", -1, CMD_FAST, NO_KEYS, &["connection"], "connection", "1.0.0", "Returns the server's liveliness response."),
+    CommandSpec::new("hello", -1, CMD_NOSCRIPT | CMD_LOADING | CMD_STALE | CMD_FAST, NO_KEYS, &["connection"], "connection", "6.0.0", "Handshakes with the Redis server."),
+    CommandSpec::new("select", 2, CMD_LOADING | CMD_STALE | CMD_FAST, NO_KEYS, &["connection"], "connection", "1.0.0", "Changes the selected database."),
+    // server
+    CommandSpec::new("info", -1, CMD_LOADING | CMD_STALE, NO_KEYS, &["dangerous"], "server", "1.0.0", "Returns information and statistics about the server."),
+    CommandSpec::new("command", -1, CMD_LOADING | CMD_STALE, NO_KEYS, &["connection"], "server", "2.8.13", "Returns detailed information about all commands."),
+    CommandSpec::new("dbsize", 1, CMD_READONLY | CMD_FAST, NO_KEYS, &["keyspace"], "server", "1.0.0", "Returns the number of keys in the database."),
+    CommandSpec::new("swapdb", 3, CMD_WRITE | CMD_FAST, NO_KEYS, &["keyspace", "dangerous"], "server", "4.0.0", "Swaps two Redis databases."),
+    CommandSpec::new("flushdb", -1, CMD_WRITE, NO_KEYS, &["keyspace", "dangerous"], "server", "1.0.0", "Removes all keys from the current database."),
+    CommandSpec::new("flushall", -1, CMD_WRITE, NO_KEYS, &["keyspace", "dangerous"], "server", "1.0.0", "Removes all keys from all databases."),
+    // generic
+    CommandSpec::new("expire", -3, CMD_WRITE | CMD_FAST, ONE_KEY, &["keyspace"], "generic", "1.0.0", "Sets the expiration time of a key in seconds."),
+    CommandSpec::new("pexpire", -3, CMD_WRITE | CMD_FAST, ONE_KEY, &["keyspace"], "generic", "2.6.0", "Sets the expiration time of a key in milliseconds."),
+    CommandSpec::new("expireat", -3, CMD_WRITE | CMD_FAST, ONE_KEY, &["keyspace"], "generic", "1.2.0", "Sets the expiration time of a key to a Unix timestamp."),
+    CommandSpec::new("pexpireat", -3, CMD_WRITE | CMD_FAST, ONE_KEY, &["keyspace"], "generic", "2.6.0", "Sets the expiration time of a key to a Unix milliseconds timestamp."),
+    CommandSpec::new("ttl", 2, CMD_READONLY | CMD_FAST, ONE_KEY, &["keyspace"], "generic", "1.0.0", "Returns the expiration time in seconds of a key."),
+    CommandSpec::new("pttl", 2, CMD_READONLY | CMD_FAST, ONE_KEY, &["keyspace"], "generic", "2.6.0", "Returns the expiration time in milliseconds of a key."),
+    CommandSpec::new("expiretime", 2, CMD_READONLY | CMD_FAST, ONE_KEY, &["keyspace"], "generic", "7.0.0", "Returns the expiration time of a key as a Unix timestamp."),
+    CommandSpec::new("pexpiretime", 2, CMD_READONLY | CMD_FAST, ONE_KEY, &["keyspace"], "generic", "7.0.0", "Returns the expiration time of a key as a Unix milliseconds timestamp."),
+    CommandSpec::new("del", -2, CMD_WRITE, ALL_KEYS, &["keyspace"], "generic", "1.0.0", "Deletes one or more keys."),
+    CommandSpec::new("unlink", -2, CMD_WRITE | CMD_FAST, ALL_KEYS, &["keyspace"], "generic", "4.0.0", "Asynchronously deletes one or more keys."),
+    CommandSpec::new("exists", -2, CMD_READONLY | CMD_FAST, ALL_KEYS, &["keyspace"], "generic", "1.0.0", "Determines whether one or more keys exist."),
+    CommandSpec::new("touch", -2, CMD_READONLY | CMD_FAST, ALL_KEYS, &["keyspace"], "generic", "3.2.1", "Returns the number of existing keys out of those specified after updating the time they were last accessed."),
+    CommandSpec::new("type", 2, CMD_READONLY | CMD_FAST, ONE_KEY, &["keyspace"], "generic", "1.0.0", "Determines the type of value stored at a key."),
+    CommandSpec::new("rename", 3, CMD_WRITE, (1, 2, 1), &["keyspace"], "generic", "1.0.0", "Renames a key and overwrites the destination."),
+    CommandSpec::new("renamenx", 3, CMD_WRITE | CMD_FAST, (1, 2, 1), &["keyspace"], "generic", "1.0.0", "Renames a key only when the target key name doesn't exist."),
+    CommandSpec::new("copy", -3, CMD_WRITE | CMD_DENYOOM, (1, 2, 1), &["keyspace"], "generic", "6.2.0", "Copies the value of a key to a new key."),
+    CommandSpec::new("move", 3, CMD_WRITE | CMD_FAST, ONE_KEY, &["keyspace"], "generic", "1.0.0", "Moves a key to another database."),
+    CommandSpec::new("randomkey", 1, CMD_READONLY, NO_KEYS, &["keyspace"], "generic", "1.0.0", "Returns a random key name from the database."),
+    CommandSpec::new("keys", 2, CMD_READONLY, NO_KEYS, &["keyspace", "dangerous"], "generic", "1.0.0", "Returns all key names that match a pattern."),
+    CommandSpec::new("scan", -2, CMD_READONLY, NO_KEYS, &["keyspace"], "generic", "2.8.0", "Iterates over the key names in the database."),
+    CommandSpec::new("persist", 2, CMD_WRITE | CMD_FAST, ONE_KEY, &["keyspace"], "generic", "2.2.0", "Removes the expiration time of a key."),
+    CommandSpec::new("object", -2, CMD_READONLY, (2, 2, 1), &["keyspace"], "generic", "2.2.3", "Returns the internal encoding of a Redis object."),
+    // string
+    CommandSpec::new("get", 2, CMD_READONLY | CMD_FAST, ONE_KEY, &["string"], "string", "1.0.0", "Returns the string value of a key."),
+    CommandSpec::new("getdel", 2, CMD_WRITE | CMD_FAST, ONE_KEY, &["string"], "string", "6.2.0", "Returns the string value of a key after deleting the key."),
+    CommandSpec::new("getex", -2, CMD_WRITE | CMD_FAST, ONE_KEY, &["string"], "string", "6.2.0", "Returns the string value of a key after setting its expiration time."),
+    CommandSpec::new("mget", -2, CMD_READONLY | CMD_FAST, ALL_KEYS, &["string"], "string", "1.0.0", "Atomically returns the string values of one or more keys."),
+    CommandSpec::new("set", -3, CMD_WRITE | CMD_DENYOOM, ONE_KEY, &["string"], "string", "1.0.0", "Sets the string value of a key, ignoring its type. The key is created if it doesn't exist."),
+    CommandSpec::new("setnx", 3, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["string"], "string", "1.0.0", "Set the string value of a key only when the key doesn't exist."),
+    CommandSpec::new("setex", 4, CMD_WRITE | CMD_DENYOOM, ONE_KEY, &["string"], "string", "2.0.0", "Sets the string value and expiration time of a key. Creates the key if it doesn't exist."),
+    CommandSpec::new("psetex", 4, CMD_WRITE | CMD_DENYOOM, ONE_KEY, &["string"], "string", "2.6.0", "Sets both string value and expiration time in milliseconds of a key. The key is created if it doesn't exist."),
+    CommandSpec::new("mset", -3, CMD_WRITE | CMD_DENYOOM, KEY_VALUE_PAIRS, &["string"], "string", "1.0.1", "Atomically creates or modifies the string values of one or more keys."),
+    CommandSpec::new("msetnx", -3, CMD_WRITE | CMD_DENYOOM, KEY_VALUE_PAIRS, &["string"], "string", "1.0.1", "Atomically modifies the string values of one or more keys only when all keys don't exist."),
+    CommandSpec::new("append", 3, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["string"], "string", "2.0.0", "Appends a string to the value of a key. Creates the key if it doesn't exist."),
+    CommandSpec::new("strlen", 2, CMD_READONLY | CMD_FAST, ONE_KEY, &["string"], "string", "2.2.0", "Returns the length of a string value."),
+    CommandSpec::new("getrange", 4, CMD_READONLY, ONE_KEY, &["string"], "string", "2.4.0", "Returns a substring of the string stored at a key."),
+    CommandSpec::new("setrange", 4, CMD_WRITE | CMD_DENYOOM, ONE_KEY, &["string"], "string", "2.2.0", "Overwrites a part of a string value with another by an offset. Creates the key if it doesn't exist."),
+    CommandSpec::new("incr", 2, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["string"], "string", "1.0.0", "Increments the integer value of a key by one. Uses 0 as initial value if the key doesn't exist."),
+    CommandSpec::new("decr", 2, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["string"], "string", "1.0.0", "Decrements the integer value of a key by one. Uses 0 as initial value if the key doesn't exist."),
+    CommandSpec::new("incrby", 3, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["string"], "string", "1.0.0", "Increments the integer value of a key by a number. Uses 0 as initial value if the key doesn't exist."),
+    CommandSpec::new("decrby", 3, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["string"], "string", "1.0.0", "Decrements a number from the integer value of a key. Uses 0 as initial value if the key doesn't exist."),
+    CommandSpec::new("incrbyfloat", 3, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["string"], "string", "2.6.0", "Increment the floating point value of a key by a number. Uses 0 as initial value if the key doesn't exist."),
+    // list
+    CommandSpec::new("lpush", -3, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["list"], "list", "1.0.0", "Prepends one or more elements to a list. Creates the key if it doesn't exist."),
+    CommandSpec::new("rpush", -3, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["list"], "list", "1.0.0", "Appends one or more elements to a list. Creates the key if it doesn't exist."),
+    CommandSpec::new("lpushx", -3, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["list"], "list", "2.2.0", "Prepends one or more elements to a list only when the list exists."),
+    CommandSpec::new("rpushx", -3, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["list"], "list", "2.2.0", "Appends an element to a list only when the list exists."),
+    CommandSpec::new("lpop", -2, CMD_WRITE | CMD_FAST, ONE_KEY, &["list"], "list", "1.0.0", "Returns the first elements in a list after removing it. Deletes the list if the last element was popped."),
+    CommandSpec::new("rpop", -2, CMD_WRITE | CMD_FAST, ONE_KEY, &["list"], "list", "1.0.0", "Returns and removes the last elements of a list. Deletes the list if the last element was popped."),
+    CommandSpec::new("lrange", 4, CMD_READONLY, ONE_KEY, &["list"], "list", "1.0.0", "Returns a range of elements from a list."),
+    CommandSpec::new("lindex", 3, CMD_READONLY, ONE_KEY, &["list"], "list", "1.0.0", "Returns an element from a list by its index."),
+    CommandSpec::new("lset", 4, CMD_WRITE | CMD_DENYOOM, ONE_KEY, &["list"], "list", "1.0.0", "Sets the value of an element in a list by its index."),
+    CommandSpec::new("linsert", 5, CMD_WRITE | CMD_DENYOOM, ONE_KEY, &["list"], "list", "2.2.0", "Inserts an element before or after another element in a list."),
+    CommandSpec::new("lrem", 4, CMD_WRITE, ONE_KEY, &["list"], "list", "1.0.0", "Removes elements from a list. Deletes the list if the last element was removed."),
+    CommandSpec::new("ltrim", 4, CMD_WRITE, ONE_KEY, &["list"], "list", "1.0.0", "Removes elements from both ends a list. Deletes the list if all elements were trimmed."),
+    CommandSpec::new("llen", 2, CMD_READONLY | CMD_FAST, ONE_KEY, &["list"], "list", "1.0.0", "Returns the length of a list."),
+    CommandSpec::new("lpos", -3, CMD_READONLY, ONE_KEY, &["list"], "list", "6.0.6", "Returns the index of matching elements in a list."),
+    CommandSpec::new("lmove", 5, CMD_WRITE | CMD_DENYOOM, (1, 2, 1), &["list"], "list", "6.2.0", "Returns an element after popping it from one list and pushing it to another. Deletes the list if the last element was moved."),
+    CommandSpec::new("lmpop", -4, CMD_WRITE, NO_KEYS, &["list"], "list", "7.0.0", "Returns multiple elements from a list after removing them. Deletes the list if the last element was popped.").with_keynum(NUMKEYS_FIRST),
+    CommandSpec::new("blpop", -3, CMD_WRITE | CMD_BLOCKING, (1, -2, 1), &["list", "blocking"], "list", "2.0.0", "Removes and returns the first element in a list. Blocks until an element is available otherwise. Deletes the list if the last element was popped."),
+    CommandSpec::new("brpop", -3, CMD_WRITE | CMD_BLOCKING, (1, -2, 1), &["list", "blocking"], "list", "2.0.0", "Removes and returns the last element in a list. Blocks until an element is available otherwise. Deletes the list if the last element was popped."),
+    CommandSpec::new("blmove", 6, CMD_WRITE | CMD_DENYOOM | CMD_BLOCKING, (1, 2, 1), &["list", "blocking"], "list", "6.2.0", "Pops an element from a list, pushes it to another list and returns it. Blocks until an element is available otherwise. Deletes the list if the last element was moved."),
+    CommandSpec::new("blmpop", -5, CMD_WRITE | CMD_BLOCKING, NO_KEYS, &["list", "blocking"], "list", "7.0.0", "Pops the first element from one of multiple lists. Blocks until an element is available otherwise. Deletes the list if the last element was popped.").with_keynum(NUMKEYS_SECOND),
+    // hash
+    CommandSpec::new("hset", -4, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["hash"], "hash", "2.0.0", "Creates or modifies the value of a field in a hash."),
+    CommandSpec::new("hsetnx", 4, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["hash"], "hash", "2.0.0", "Sets the value of a field in a hash only when the field doesn't exist."),
+    CommandSpec::new("hget", 3, CMD_READONLY | CMD_FAST, ONE_KEY, &["hash"], "hash", "2.0.0", "Returns the value of a field in a hash."),
+    CommandSpec::new("hmget", -3, CMD_READONLY | CMD_FAST, ONE_KEY, &["hash"], "hash", "2.0.0", "Returns the values of all fields in a hash."),
+    CommandSpec::new("hdel", -3, CMD_WRITE | CMD_FAST, ONE_KEY, &["hash"], "hash", "2.0.0", "Deletes one or more fields and their values from a hash. Deletes the hash if no fields remain."),
+    CommandSpec::new("hexists", 3, CMD_READONLY | CMD_FAST, ONE_KEY, &["hash"], "hash", "2.0.0", "Determines whether a field exists in a hash."),
+    CommandSpec::new("hlen", 2, CMD_READONLY | CMD_FAST, ONE_KEY, &["hash"], "hash", "2.0.0", "Returns the number of fields in a hash."),
+    CommandSpec::new("hkeys", 2, CMD_READONLY, ONE_KEY, &["hash"], "hash", "2.0.0", "Returns all fields in a hash."),
+    CommandSpec::new("hvals", 2, CMD_READONLY, ONE_KEY, &["hash"], "hash", "2.0.0", "Returns all values in a hash."),
+    CommandSpec::new("hgetall", 2, CMD_READONLY, ONE_KEY, &["hash"], "hash", "2.0.0", "Returns all fields and values in a hash."),
+    CommandSpec::new("hincrby", 4, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["hash"], "hash", "2.0.0", "Increments the integer value of a field in a hash by a number. Uses 0 as initial value if the field doesn't exist."),
+    CommandSpec::new("hincrbyfloat", 4, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["hash"], "hash", "2.6.0", "Increments the floating point value of a field by a number. Uses 0 as initial value if the field doesn't exist."),
+    CommandSpec::new("hstrlen", 3, CMD_READONLY | CMD_FAST, ONE_KEY, &["hash"], "hash", "3.2.0", "Returns the length of the value of a field."),
+    CommandSpec::new("hrandfield", -2, CMD_READONLY, ONE_KEY, &["hash"], "hash", "6.2.0", "Returns one or more random fields from a hash."),
+    CommandSpec::new("hscan", -3, CMD_READONLY, ONE_KEY, &["hash"], "hash", "2.8.0", "Iterates over fields and values of a hash."),
+    CommandSpec::new("hexpire", -6, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["hash"], "hash", "7.4.0", "Set expiry for hash field using relative time to expire (seconds)"),
+    CommandSpec::new("hpexpire", -6, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["hash"], "hash", "7.4.0", "Set expiry for hash field using relative time to expire (milliseconds)"),
+    CommandSpec::new("hexpireat", -6, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["hash"], "hash", "7.4.0", "Set expiry for hash field using an absolute Unix timestamp (seconds)"),
+    CommandSpec::new("hpexpireat", -6, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["hash"], "hash", "7.4.0", "Set expiry for hash field using an absolute Unix timestamp (milliseconds)"),
+    CommandSpec::new("httl", -5, CMD_READONLY | CMD_FAST, ONE_KEY, &["hash"], "hash", "7.4.0", "Returns the TTL in seconds of a hash field."),
+    CommandSpec::new("hpttl", -5, CMD_READONLY | CMD_FAST, ONE_KEY, &["hash"], "hash", "7.4.0", "Returns the TTL in milliseconds of a hash field."),
+    CommandSpec::new("hexpiretime", -5, CMD_READONLY | CMD_FAST, ONE_KEY, &["hash"], "hash", "7.4.0", "Returns the expiration time of a hash field as a Unix timestamp, in seconds."),
+    CommandSpec::new("hpexpiretime", -5, CMD_READONLY | CMD_FAST, ONE_KEY, &["hash"], "hash", "7.4.0", "Returns the expiration time of a hash field as a Unix timestamp, in msec."),
+    CommandSpec::new("hpersist", -5, CMD_WRITE | CMD_FAST, ONE_KEY, &["hash"], "hash", "7.4.0", "Removes the expiration time for each specified field"),
+    // set
+    CommandSpec::new("sadd", -3, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["set"], "set", "1.0.0", "Adds one or more members to a set. Creates the key if it doesn't exist."),
+    CommandSpec::new("srem", -3, CMD_WRITE | CMD_FAST, ONE_KEY, &["set"], "set", "1.0.0", "Removes one or more members from a set. Deletes the set if the last member was removed."),
+    CommandSpec::new("sismember", 3, CMD_READONLY | CMD_FAST, ONE_KEY, &["set"], "set", "1.0.0", "Determines whether a member belongs to a set."),
+    CommandSpec::new("smismember", -3, CMD_READONLY | CMD_FAST, ONE_KEY, &["set"], "set", "6.2.0", "Determines whether multiple members belong to a set."),
+    CommandSpec::new("smembers", 2, CMD_READONLY, ONE_KEY, &["set"], "set", "1.0.0", "Returns all members of a set."),
+    CommandSpec::new("scard", 2, CMD_READONLY | CMD_FAST, ONE_KEY, &["set"], "set", "1.0.0", "Returns the number of members in a set."),
+    CommandSpec::new("spop", -2, CMD_WRITE | CMD_FAST, ONE_KEY, &["set"], "set", "1.0.0", "Returns one or more random members from a set after removing them. Deletes the set if the last member was popped."),
+    CommandSpec::new("srandmember", -2, CMD_READONLY, ONE_KEY, &["set"], "set", "1.0.0", "Get one or multiple random members from a set"),
+    CommandSpec::new("smove", 4, CMD_WRITE | CMD_FAST, (1, 2, 1), &["set"], "set", "1.0.0", "Moves a member from one set to another."),
+    CommandSpec::new("sinter", -2, CMD_READONLY, ALL_KEYS, &["set"], "set", "1.0.0", "Returns the intersect of multiple sets."),
+    CommandSpec::new("sunion", -2, CMD_READONLY, ALL_KEYS, &["set"], "set", "1.0.0", "Returns the union of multiple sets."),
+    CommandSpec::new("sdiff", -2, CMD_READONLY, ALL_KEYS, &["set"], "set", "1.0.0", "Returns the difference of multiple sets."),
+    CommandSpec::new("sinterstore", -3, CMD_WRITE | CMD_DENYOOM, ALL_KEYS, &["set"], "set", "1.0.0", "Stores the intersect of multiple sets in a key."),
+    CommandSpec::new("sunionstore", -3, CMD_WRITE | CMD_DENYOOM, ALL_KEYS, &["set"], "set", "1.0.0", "Stores the union of multiple sets in a key."),
+    CommandSpec::new("sdiffstore", -3, CMD_WRITE | CMD_DENYOOM, ALL_KEYS, &["set"], "set", "1.0.0", "Stores the difference of multiple sets in a key."),
+    CommandSpec::new("sintercard", -3, CMD_READONLY, NO_KEYS, &["set"], "set", "7.0.0", "Returns the number of members of the intersect of multiple sets.").with_keynum(NUMKEYS_FIRST),
+    CommandSpec::new("sscan", -3, CMD_READONLY, ONE_KEY, &["set"], "set", "2.8.0", "Iterates over members of a set."),
+    // sorted set
+    CommandSpec::new("zadd", -4, CMD_WRITE | CMD_DENYOOM | CMD_FAST, ONE_KEY, &["sortedset"], "sorted_set", "1.2.0", "Adds one or more members to a sorted set, or updates their scores. Creates the key if it doesn't exist."),
+    CommandSpec::new("zcard", 2, CMD_READONLY | CMD_FAST, ONE_KEY, &["sortedset"], "sorted_set", "1.2.0", "Returns the number of members in a sorted set."),
+    CommandSpec::new("zscore", 3, CMD_READONLY | CMD_FAST, ONE_KEY, &["sortedset"], "sorted_set", "1.2.0", "Returns the score of a member in a sorted set."),
+    CommandSpec::new("zpopmin", -2, CMD_WRITE | CMD_FAST, ONE_KEY, &["sortedset"], "sorted_set", "5.0.0", "Returns the lowest-scoring members from a sorted set after removing them. Deletes the sorted set if the last member was popped."),
+    CommandSpec::new("zpopmax", -2, CMD_WRITE | CMD_FAST, ONE_KEY, &["sortedset"], "sorted_set", "5.0.0", "Returns the highest-scoring members from a sorted set after removing them. Deletes the sorted set if the last member was popped."),
+    CommandSpec::new("zmpop", -4, CMD_WRITE, NO_KEYS, &["sortedset"], "sorted_set", "7.0.0", "Returns the highest- or lowest-scoring members from one or more sorted sets after removing them. Deletes the sorted set if the last member was popped.").with_keynum(NUMKEYS_FIRST),
+    CommandSpec::new("bzpopmin", -3, CMD_WRITE | CMD_FAST | CMD_BLOCKING, (1, -2, 1), &["sortedset", "blocking"], "sorted_set", "5.0.0", "Removes and returns the member with the lowest score from one or more sorted sets. Blocks until a member is available otherwise. Deletes the sorted set if the last element was popped."),
+    CommandSpec::new("bzpopmax", -3, CMD_WRITE | CMD_FAST | CMD_BLOCKING, (1, -2, 1), &["sortedset", "blocking"], "sorted_set", "5.0.0", "Removes and returns the member with the highest score from one or more sorted sets. Blocks until a member is available otherwise. Deletes the sorted set if the last element was popped."),
+    CommandSpec::new("bzmpop", -5, CMD_WRITE | CMD_BLOCKING, NO_KEYS, &["sortedset", "blocking"], "sorted_set", "7.0.0", "Removes and returns a member by score from one or more sorted sets. Blocks until a member is available otherwise. Deletes the sorted set if the last element was popped.").with_keynum(NUMKEYS_SECOND),
+];
+
+lazy_static! {
+    static ref COMMAND_TABLE: AHashMap<&'static str, &'static CommandSpec> =
+        COMMANDS.iter().map(|spec| (spec.name, spec)).collect();
+}
+
+/// Look a command up by its lower-cased name, Redis' `lookupCommand`.
+pub(crate) fn lookup_command(name: &str) -> Option<&'static CommandSpec> {
+    COMMAND_TABLE.get(name).copied()
+}
+
+/// Every command, sorted by name.
+pub(crate) fn all_commands() -> Vec<&'static CommandSpec> {
+    let mut commands: Vec<_> = COMMANDS.iter().collect();
+    commands.sort_by_key(|spec| spec.name);
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_unique_and_lowercase() {
+        assert_eq!(COMMAND_TABLE.len(), COMMANDS.len());
+        assert!(COMMANDS.iter().all(|spec| spec.name == spec.name.to_lowercase()));
+    }
+
+    #[test]
+    fn arity_and_keys() {
+        let mset = lookup_command("mset").unwrap();
+        assert!(!mset.check_arity(2));
+        assert!(mset.check_arity(5));
+        let args: Vec<Bytes> = ["mset", "a", "1", "b", "2"].into_iter().map(Bytes::from).collect();
+        assert_eq!(mset.get_keys(&args).unwrap(), vec!["a", "b"]);
+
+        let get = lookup_command("get").unwrap();
+        assert!(get.check_arity(2) && !get.check_arity(3));
+        assert_eq!(get.acl_categories(), vec!["read", "string", "fast"]);
+        assert_eq!(lookup_command("set").unwrap().acl_categories(), vec!["write", "string", "slow"]);
+    }
+}
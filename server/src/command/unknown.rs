@@ -1,5 +1,5 @@
-use resp::{Result, protocol::Protocol};
-use crate::connection::Connection;
+use crate::client::Client;
+use crate::error::{RedisError, Result};
 
 /// Represents an "unknown" command. This is not a real `Redis` command.
 #[derive(Debug)]
@@ -17,6 +17,7 @@ impl Unknown {
     }
 
     /// Returns the command name
+    #[allow(dead_code)]
     pub(crate) fn get_name(&self) -> &str {
         &self.command_name
     }
@@ -25,12 +26,12 @@ impl Unknown {
     ///
     /// This usually means the command is not yet implemented by `rudis`.
     // #[instrument(skip(self, dst))]
-    pub(crate) fn apply(self, dst: &mut Connection) -> Result<()> {
-        let response = Protocol::Error(format!("ERR unknown command '{}'", self.command_name));
+    pub(crate) fn apply(self, dst: &mut Client) -> Result<()> {
+        let response = RedisError::Err(format!("unknown command '{}'", self.command_name)).into();
 
         // debug!(?response);
 
-        dst.write_protocol(&response).expect("TODO: panic message");
+        dst.connection.write_protocol(&response)?;
         Ok(())
     }
 }
@@ -0,0 +1,418 @@
+use resp::{protocol::{Protocol, ProtocolVersion}, parse::Parser};
+use bytes::Bytes;
+use crate::blocked::{block_for_keys, parse_timeout};
+use crate::client::Client;
+use crate::command::{parse_mpop_count, parse_numkeys, Command};
+use crate::db::{keyspace::Db, object::RedisObject, zset::ZSet};
+use crate::error::{RedisError, Result};
+use crate::server::RedisServer;
+
+/// The sorted set stored at key, `None` if the key does not exist.
+fn lookup_zset<'a>(db: &'a mut Db, key: &[u8]) -> Result<Option<&'a mut ZSet>> {
+    match db.lookup_key(key) {
+        None => Ok(None),
+        Some(RedisObject::ZSet(zset)) => Ok(Some(zset)),
+        Some(_) => Err(RedisError::WrongType),
+    }
+}
+
+/// Pop up to `count` members with the lowest, or the highest if `max`,
+/// scores from the sorted set at key, deleting the key once the set is
+/// empty. Returns `None` if the key does not exist.
+fn pop_members(db: &mut Db, key: &[u8], max: bool, count: usize) -> Result<Option<Vec<(Bytes, f64)>>> {
+    let Some(zset) = lookup_zset(db, key)? else {
+        return Ok(None);
+    };
+    let count = count.min(zset.len());
+    let members = (0..count).filter_map(|_| if max { zset.pop_max() } else { zset.pop_min() }).collect();
+    if zset.is_empty() {
+        db.delete_key(key);
+    }
+    Ok(Some(members))
+}
+
+/// Parse `MIN` or `MAX`, returning whether it is `MAX`.
+fn parse_min_max(parse: &mut Parser) -> Result<bool> {
+    match &parse.next_string()?.to_uppercase()[..] {
+        "MIN" => Ok(false),
+        "MAX" => Ok(true),
+        _ => Err(RedisError::syntax()),
+    }
+}
+
+fn pair(member: Bytes, score: f64) -> [Protocol; 2] {
+    [Protocol::Bulk(member), Protocol::Double(score)]
+}
+
+/// Members and their scores as an array of `[member, score]` arrays.
+fn nested_pairs(members: Vec<(Bytes, f64)>) -> Protocol {
+    Protocol::Array(members.into_iter().map(|(member, score)| Protocol::Array(pair(member, score).into())).collect())
+}
+
+/// Add members with their scores to a sorted set, or update their scores.
+#[derive(Debug)]
+pub struct ZAdd {
+    key: Bytes,
+    members: Vec<(f64, Bytes)>,
+    nx: bool,
+    xx: bool,
+    gt: bool,
+    lt: bool,
+    ch: bool,
+    incr: bool,
+}
+
+impl ZAdd {
+    /// Parse a `ZAdd` instance from a received frame.
+    ///
+    /// The `ZADD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZADD key [NX | XX] [GT | LT] [CH] [INCR] score member [score member ...]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<ZAdd> {
+        let key = parse.next_bytes()?;
+        let (mut nx, mut xx, mut gt, mut lt, mut ch, mut incr) = (false, false, false, false, false, false);
+        loop {
+            if parse.next_if_keyword("NX") {
+                nx = true;
+            } else if parse.next_if_keyword("XX") {
+                xx = true;
+            } else if parse.next_if_keyword("GT") {
+                gt = true;
+            } else if parse.next_if_keyword("LT") {
+                lt = true;
+            } else if parse.next_if_keyword("CH") {
+                ch = true;
+            } else if parse.next_if_keyword("INCR") {
+                incr = true;
+            } else {
+                break;
+            }
+        }
+
+        let remaining = parse.remaining();
+        if remaining == 0 || !remaining.is_multiple_of(2) {
+            return Err(RedisError::syntax());
+        }
+        if nx && xx {
+            return Err(RedisError::Err("XX and NX options at the same time are not compatible".to_string()));
+        }
+        if (gt || lt) && (nx || (gt && lt)) {
+            return Err(RedisError::Err("GT, LT, and/or NX options at the same time are not compatible".to_string()));
+        }
+        if incr && remaining > 2 {
+            return Err(RedisError::Err("INCR option supports a single increment-element pair".to_string()));
+        }
+
+        let mut members = Vec::with_capacity(remaining / 2);
+        for _ in 0..remaining / 2 {
+            let score = parse.next_f64()?;
+            members.push((score, parse.next_bytes()?));
+        }
+        Ok(ZAdd { key, members, nx, xx, gt, lt, ch, incr })
+    }
+
+    /// Apply the `ZAdd` command and reply with the number of members added,
+    /// or also updated with `CH`. `INCR` replies with the new score of the
+    /// member, nil if the options prevented the update.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        let exists = lookup_zset(db, &self.key)?.is_some();
+        if !exists && !self.xx {
+            db.set_key(self.key.clone(), RedisObject::ZSet(ZSet::default()), false);
+        }
+
+        let (mut added, mut updated) = (0, 0);
+        let mut incremented = None;
+        if let Some(zset) = lookup_zset(db, &self.key)? {
+            for (score, member) in self.members {
+                match zset.score(&member) {
+                    Some(current) => {
+                        if self.nx {
+                            continue;
+                        }
+                        let score = if self.incr { current + score } else { score };
+                        if score.is_nan() {
+                            return Err(RedisError::Err("resulting score is not a number (NaN)".to_string()));
+                        }
+                        if (self.lt && score >= current) || (self.gt && score <= current) {
+                            continue;
+                        }
+                        incremented = Some(score);
+                        if score != current {
+                            zset.insert(member, score);
+                            updated += 1;
+                        }
+                    }
+                    None if self.xx => {}
+                    None => {
+                        zset.insert(member, score);
+                        incremented = Some(score);
+                        added += 1;
+                    }
+                }
+            }
+        }
+        drop(dbs);
+        server.incr_dirty(added + updated);
+
+        let response = if self.incr {
+            incremented.map_or(Protocol::Null, Protocol::Double)
+        } else {
+            Protocol::Integer((if self.ch { added + updated } else { added }) as i64)
+        };
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
+
+/// Return the number of members of a sorted set.
+#[derive(Debug)]
+pub struct ZCard {
+    key: Bytes,
+}
+
+impl ZCard {
+    /// Parse a `ZCard` instance from a received frame.
+    ///
+    /// The `ZCARD` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZCARD key
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<ZCard> {
+        let key = parse.next_bytes()?;
+        Ok(ZCard { key })
+    }
+
+    /// Apply the `ZCard` command and reply with the number of members, 0 if
+    /// the key does not exist.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let len = lookup_zset(&mut server.keyspace.lock()[dst.db], &self.key)?.map_or(0, |zset| zset.len());
+        dst.connection.write_protocol(&Protocol::Integer(len as i64))?;
+        Ok(())
+    }
+}
+
+/// Return the score of a member of a sorted set.
+#[derive(Debug)]
+pub struct ZScore {
+    key: Bytes,
+    member: Bytes,
+}
+
+impl ZScore {
+    /// Parse a `ZScore` instance from a received frame.
+    ///
+    /// The `ZSCORE` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZSCORE key member
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<ZScore> {
+        let key = parse.next_bytes()?;
+        let member = parse.next_bytes()?;
+        Ok(ZScore { key, member })
+    }
+
+    /// Apply the `ZScore` command and reply with the score, nil if the key or
+    /// the member does not exist.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let score = lookup_zset(&mut server.keyspace.lock()[dst.db], &self.key)?.and_then(|zset| zset.score(&self.member));
+        dst.connection.write_protocol(&score.map_or(Protocol::Null, Protocol::Double))?;
+        Ok(())
+    }
+}
+
+/// Remove and return the members with the lowest, `ZPOPMIN`, or the
+/// highest, `ZPOPMAX`, scores of a sorted set.
+#[derive(Debug)]
+pub struct ZPop {
+    key: Bytes,
+    max: bool,
+    count: Option<u64>,
+}
+
+impl ZPop {
+    /// Parse a `ZPop` instance from a received frame.
+    ///
+    /// The `ZPOPMIN` or `ZPOPMAX` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZPOPMIN key [count]
+    /// ZPOPMAX key [count]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, max: bool) -> Result<ZPop> {
+        let key = parse.next_bytes()?;
+        let count = if parse.remaining() > 0 { Some(parse.next_u64()?) } else { None };
+        Ok(ZPop { key, max, count })
+    }
+
+    /// Apply the `ZPop` command and reply with the members followed by their
+    /// scores. With a count, RESP3 replies an array of `[member, score]`
+    /// arrays instead.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let count = self.count.map_or(1, |count| usize::try_from(count).unwrap_or(usize::MAX));
+        let members = pop_members(&mut server.keyspace.lock()[dst.db], &self.key, self.max, count)?.unwrap_or_default();
+        server.incr_dirty(members.len() as u64);
+
+        let response = if self.count.is_some() && dst.connection.version() == ProtocolVersion::Resp3 {
+            nested_pairs(members)
+        } else {
+            Protocol::Array(members.into_iter().flat_map(|(member, score)| pair(member, score)).collect())
+        };
+        dst.connection.write_protocol(&response)?;
+        Ok(())
+    }
+}
+
+/// Remove and return members from the first non-empty sorted set among the
+/// given keys.
+#[derive(Debug)]
+pub struct ZMPop {
+    keys: Vec<Bytes>,
+    max: bool,
+    count: usize,
+}
+
+impl ZMPop {
+    /// Parse a `ZMPop` instance from a received frame.
+    ///
+    /// The `ZMPOP` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// ZMPOP numkeys key [key ...] MIN|MAX [COUNT count]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<ZMPop> {
+        let keys = parse_numkeys(parse)?;
+        let max = parse_min_max(parse)?;
+        let count = parse_mpop_count(parse)?;
+        Ok(ZMPop { keys, max, count })
+    }
+
+    /// Pop from the first sorted set, the reply of the command. Returns
+    /// `None` if every sorted set is empty.
+    fn pop(&self, server: &RedisServer, db: &mut Db) -> Result<Option<Protocol>> {
+        for key in &self.keys {
+            if let Some(members) = pop_members(db, key, self.max, self.count)? {
+                server.incr_dirty(members.len() as u64);
+                return Ok(Some(Protocol::Array(vec![Protocol::Bulk(key.clone()), nested_pairs(members)])));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Apply the `ZMPop` command and reply with the key popped from and its
+    /// `[member, score]` pairs, nil if every sorted set is empty.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let response = self.pop(server, &mut server.keyspace.lock()[dst.db])?;
+        dst.connection.write_protocol(&response.unwrap_or(Protocol::NullArray))?;
+        Ok(())
+    }
+}
+
+/// Remove and return the member with the lowest, `BZPOPMIN`, or the
+/// highest, `BZPOPMAX`, score of the first non-empty sorted set, blocking
+/// until there is one.
+#[derive(Debug)]
+pub struct BZPop {
+    keys: Vec<Bytes>,
+    max: bool,
+    timeout: Option<i64>,
+}
+
+impl BZPop {
+    /// Parse a `BZPop` instance from a received frame.
+    ///
+    /// The `BZPOPMIN` or `BZPOPMAX` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// BZPOPMIN key [key ...] timeout
+    /// BZPOPMAX key [key ...] timeout
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser, max: bool) -> Result<BZPop> {
+        let mut keys = vec![];
+        for key in parse.remaining_bytes() {
+            keys.push(key?);
+        }
+        let timeout = keys.pop().ok_or_else(|| RedisError::WrongArity(String::new()))?;
+        let timeout = parse_timeout(&timeout)?;
+        Ok(BZPop { keys, max, timeout })
+    }
+
+    /// Apply the `BZPop` command and reply with the key popped from, the
+    /// member and its score, or block the client.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let mut dbs = server.keyspace.lock();
+        let db = &mut dbs[dst.db];
+        for key in &self.keys {
+            if let Some((member, score)) = pop_members(db, key, self.max, 1)?.and_then(|mut members| members.pop()) {
+                drop(dbs);
+                server.incr_dirty(1);
+                let [member, score] = pair(member, score);
+                dst.connection.write_protocol(&Protocol::Array(vec![Protocol::Bulk(key.clone()), member, score]))?;
+                return Ok(());
+            }
+        }
+        drop(dbs);
+
+        let keys = self.keys.clone();
+        let timeout = self.timeout;
+        let command = if self.max { Command::BZPopMax(self) } else { Command::BZPopMin(self) };
+        block_for_keys(server, dst, keys, "zset", timeout, command)
+    }
+}
+
+/// `ZMPOP`, blocking until one of the sorted sets holds a member.
+#[derive(Debug)]
+pub struct BZMPop {
+    zmpop: ZMPop,
+    timeout: Option<i64>,
+}
+
+impl BZMPop {
+    /// Parse a `BZMPop` instance from a received frame.
+    ///
+    /// The `BZMPOP` string has already been consumed.
+    ///
+    /// # Format
+    ///
+    /// ```text
+    /// BZMPOP timeout numkeys key [key ...] MIN|MAX [COUNT count]
+    /// ```
+    pub(crate) fn parse_frames(parse: &mut Parser) -> Result<BZMPop> {
+        let timeout = parse_timeout(&parse.next_bytes()?)?;
+        let zmpop = ZMPop::parse_frames(parse)?;
+        Ok(BZMPop { zmpop, timeout })
+    }
+
+    /// Apply the `BZMPop` command and reply with the key popped from and its
+    /// `[member, score]` pairs, or block the client.
+    pub(crate) fn apply(self, server: &RedisServer, dst: &mut Client) -> Result<()> {
+        let response = self.zmpop.pop(server, &mut server.keyspace.lock()[dst.db])?;
+        match response {
+            Some(response) => {
+                dst.connection.write_protocol(&response)?;
+                Ok(())
+            }
+            None => {
+                let keys = self.zmpop.keys.clone();
+                block_for_keys(server, dst, keys, "zset", self.timeout, Command::BZMPop(self))
+            }
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! Server configuration.
+//!
+//! The configuration is read the way `redis-server` reads it: an optional
+//! config file path followed by `--directive value...` overrides, which are
+//! applied after the file as if they were appended to it.
+
+use std::fs;
+use log::LevelFilter;
+use resp::request::RequestLimits;
+use crate::client::{ClientBufferLimit, ClientClass};
+
+/// Minimum and maximum values accepted for `hz`.
+pub(crate) const CONFIG_MIN_HZ: u32 = 1;
+pub(crate) const CONFIG_MAX_HZ: u32 = 500;
+
+/// Save the dataset after `seconds` if at least `changes` writes happened.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SaveParam {
+    pub(crate) seconds: u64,
+    pub(crate) changes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct RedisServerConfig {
+    pub(crate) port: i32,
+
+    /// Frequency of the server cron, in calls per second.
+    pub(crate) hz: u32,
+
+    /// Raise `hz` while many clients are connected.
+    pub(crate) dynamic_hz: bool,
+
+    /// Close clients idle for that many seconds, never if zero.
+    pub(crate) timeout: u64,
+
+    /// Number of logical databases.
+    pub(crate) databases: usize,
+
+    /// Spend some cron time rehashing the keyspace tables.
+    pub(crate) activerehashing: bool,
+
+    /// Effort, from 1 to 10, the active expire cycle puts into reclaiming
+    /// expired keys, trading CPU for memory.
+    pub(crate) active_expire_effort: u32,
+
+    /// Messages of the server log at this level or above are written.
+    pub(crate) verbosity: LevelFilter,
+
+    /// Points at which the dataset is saved in the background. There is no
+    /// persistence yet: they are accepted, but none by default, and ignored.
+    pub(crate) save_params: Vec<SaveParam>,
+
+    /// Output buffer limits, indexed by `ClientClass`.
+    pub(crate) client_output_buffer_limits: [ClientBufferLimit; ClientClass::COUNT],
+
+    /// Largest bulk string accepted in a request, and largest string value.
+    pub(crate) proto_max_bulk_len: usize,
+
+    /// Largest number of arguments of a multibulk request.
+    pub(crate) proto_max_multibulk_len: usize,
+
+    /// Largest inline request, and largest header line of a multibulk one.
+    pub(crate) proto_inline_max_size: usize,
+
+    /// Largest amount of request data buffered for a client, past which it
+    /// is closed, Redis' `client_max_querybuf_len`.
+    pub(crate) client_max_querybuf_len: usize,
+
+    /// Size of the nodes of a list: positive, the number of entries;
+    /// negative, a size in bytes from 4kb (-1) to 64kb (-5).
+    pub(crate) list_max_listpack_size: i32,
+
+    /// Largest number of fields of a hash encoded as a listpack.
+    pub(crate) hash_max_listpack_entries: usize,
+
+    /// Largest field or value, in bytes, of a hash encoded as a listpack.
+    pub(crate) hash_max_listpack_value: usize,
+
+    /// Largest number of members of a set of integers encoded as an intset.
+    pub(crate) set_max_intset_entries: usize,
+
+    /// Largest number of members of a set encoded as a listpack.
+    pub(crate) set_max_listpack_entries: usize,
+
+    /// Largest member, in bytes, of a set encoded as a listpack.
+    pub(crate) set_max_listpack_value: usize,
+}
+
+impl Default for RedisServerConfig {
+    fn default() -> Self {
+        Self {
+            port: 6379,
+            hz: 10,
+            dynamic_hz: true,
+            timeout: 0,
+            databases: 16,
+            activerehashing: true,
+            active_expire_effort: 1,
+            verbosity: LevelFilter::Info,
+            save_params: Vec::new(),
+            client_output_buffer_limits: [
+                // normal
+                ClientBufferLimit::default(),
+                // replica
+                ClientBufferLimit {
+                    hard_limit_bytes: 256 * 1024 * 1024,
+                    soft_limit_bytes: 64 * 1024 * 1024,
+                    soft_limit_seconds: 60,
+                },
+                // pubsub
+                ClientBufferLimit {
+                    hard_limit_bytes: 32 * 1024 * 1024,
+                    soft_limit_bytes: 8 * 1024 * 1024,
+                    soft_limit_seconds: 60,
+                },
+            ],
+            proto_max_bulk_len: 512 * 1024 * 1024,
+            proto_max_multibulk_len: RequestLimits::default().max_multibulk_len,
+            proto_inline_max_size: RequestLimits::default().max_inline_size,
+            client_max_querybuf_len: 1024 * 1024 * 1024,
+            list_max_listpack_size: -2,
+            hash_max_listpack_entries: 128,
+            hash_max_listpack_value: 64,
+            set_max_intset_entries: 512,
+            set_max_listpack_entries: 128,
+            set_max_listpack_value: 64,
+        }
+    }
+}
+
+impl RedisServerConfig {
+    /// Build the configuration from the command line arguments, program name
+    /// excluded.
+    ///
+    /// ```text
+    /// server [/path/to/redis.conf] [--port 7777] [--client-output-buffer-limit pubsub 64mb 16mb 60]
+    /// ```
+    pub(crate) fn load(args: impl IntoIterator<Item = String>) -> Result<RedisServerConfig, String> {
+        let mut args = args.into_iter().peekable();
+        let mut text = String::new();
+
+        if let Some(path) = args.next_if(|arg| !arg.starts_with("--")) {
+            text = fs::read_to_string(&path)
+                .map_err(|e| format!("Fatal error, can't open config file '{}': {}", path, e))?;
+            text.push('\n');
+        }
+
+        // Every `--directive` starts a new line, its values follow on the same
+        // line.
+        for arg in args {
+            match arg.strip_prefix("--") {
+                Some(directive) => {
+                    text.push('\n');
+                    text.push_str(directive);
+                }
+                None => {
+                    text.push(' ');
+                    text.push_str(&arg);
+                }
+            }
+        }
+
+        let mut config = RedisServerConfig::default();
+        let mut save_params_loaded = false;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let argv: Vec<&str> = line.split_whitespace().collect();
+            let name = argv[0].to_lowercase();
+
+            // The first `save` directive replaces the default save points,
+            // the following ones add to it.
+            if name == "save" && !save_params_loaded {
+                config.save_params.clear();
+                save_params_loaded = true;
+            }
+
+            config.set(&name, &argv[1..]).map_err(|err| {
+                format!("*** FATAL CONFIG FILE ERROR ***\nat line {}\n>>> '{}'\n{}", i + 1, line, err)
+            })?;
+        }
+
+        Ok(config)
+    }
+
+    /// Limits applied to the requests of clients.
+    pub(crate) fn request_limits(&self) -> RequestLimits {
+        RequestLimits {
+            max_bulk_len: self.proto_max_bulk_len,
+            max_multibulk_len: self.proto_max_multibulk_len,
+            max_inline_size: self.proto_inline_max_size,
+        }
+    }
+
+    /// Apply a single directive.
+    fn set(&mut self, name: &str, args: &[&str]) -> Result<(), String> {
+        match (name, args) {
+            ("port", [port]) => {
+                self.port = port
+                    .parse()
+                    .ok()
+                    .filter(|port| (0..=65535).contains(port))
+                    .ok_or("Invalid port")?;
+            }
+            ("hz", [hz]) => {
+                let hz: u32 = hz.parse().map_err(|_| "argument couldn't be parsed into an integer")?;
+                self.hz = hz.clamp(CONFIG_MIN_HZ, CONFIG_MAX_HZ);
+            }
+            ("dynamic-hz", [value]) => self.dynamic_hz = yesnotoi(value)?,
+            ("timeout", [timeout]) => {
+                self.timeout = timeout.parse().map_err(|_| "argument couldn't be parsed into an integer")?;
+            }
+            ("databases", [databases]) => {
+                self.databases = databases.parse().ok().filter(|n| *n >= 1).ok_or("Invalid number of databases")?;
+            }
+            ("activerehashing", [value]) => self.activerehashing = yesnotoi(value)?,
+            ("loglevel", [value]) => {
+                self.verbosity = match value.to_lowercase().as_str() {
+                    "debug" => LevelFilter::Trace,
+                    "verbose" => LevelFilter::Debug,
+                    "notice" => LevelFilter::Info,
+                    "warning" => LevelFilter::Warn,
+                    "nothing" => LevelFilter::Off,
+                    _ => return Err("Invalid log level. Must be one of debug, verbose, notice, warning, nothing".into()),
+                };
+            }
+            ("active-expire-effort", [value]) => {
+                self.active_expire_effort =
+                    value.parse().ok().filter(|effort| (1..=10).contains(effort)).ok_or("argument must be between 1 and 10 inclusive")?;
+            }
+            // `save ""` disables saving.
+            ("save", [""]) | ("save", ["\"\""]) => self.save_params.clear(),
+            ("save", args) if !args.is_empty() && args.len() % 2 == 0 => {
+                for pair in args.chunks(2) {
+                    match (pair[0].parse(), pair[1].parse()) {
+                        (Ok(seconds), Ok(changes)) => self.save_params.push(SaveParam { seconds, changes }),
+                        _ => return Err("Invalid save parameters".into()),
+                    }
+                }
+            }
+            ("client-output-buffer-limit", args) if !args.is_empty() && args.len() % 4 == 0 => {
+                // Several classes may be given at once, four values each.
+                for chunk in args.chunks(4) {
+                    let class = ClientClass::from_name(chunk[0]).ok_or("Invalid client class specified in buffer limit configuration.")?;
+                    let hard_limit_bytes = memtoull(chunk[1]);
+                    let soft_limit_bytes = memtoull(chunk[2]);
+                    let soft_limit_seconds = chunk[3].parse().ok();
+                    match (hard_limit_bytes, soft_limit_bytes, soft_limit_seconds) {
+                        (Some(hard_limit_bytes), Some(soft_limit_bytes), Some(soft_limit_seconds)) => {
+                            self.client_output_buffer_limits[class as usize] = ClientBufferLimit {
+                                hard_limit_bytes,
+                                soft_limit_bytes,
+                                soft_limit_seconds,
+                            };
+                        }
+                        _ => return Err("Error in hard, soft or soft_seconds setting in buffer limit configuration.".into()),
+                    }
+                }
+            }
+            ("proto-max-bulk-len", [value]) => {
+                self.proto_max_bulk_len = memtoull(value)
+                    .filter(|len| *len >= 1024 * 1024)
+                    .ok_or("argument must be a memory value of at least 1mb")?;
+            }
+            ("proto-max-multibulk-len", [value]) => {
+                self.proto_max_multibulk_len = value
+                    .parse()
+                    .ok()
+                    .filter(|len| (1..=i32::MAX as usize).contains(len))
+                    .ok_or("argument must be between 1 and 2147483647 inclusive")?;
+            }
+            ("proto-inline-max-size", [value]) => {
+                self.proto_inline_max_size = memtoull(value)
+                    .filter(|size| *size >= 1024)
+                    .ok_or("argument must be a memory value of at least 1kb")?;
+            }
+            ("client-query-buffer-limit", [value]) => {
+                self.client_max_querybuf_len = memtoull(value)
+                    .filter(|len| *len >= 1024 * 1024)
+                    .ok_or("argument must be a memory value of at least 1mb")?;
+            }
+            // The name before Redis 7 is kept as an alias.
+            ("list-max-listpack-size", [value]) | ("list-max-ziplist-size", [value]) => {
+                self.list_max_listpack_size = value
+                    .parse()
+                    .ok()
+                    .filter(|size| *size >= -5)
+                    .ok_or("argument must be between -5 and 2147483647")?;
+            }
+            ("hash-max-listpack-entries", [value]) | ("hash-max-ziplist-entries", [value]) => {
+                self.hash_max_listpack_entries = value.parse().map_err(|_| "argument couldn't be parsed into an integer")?;
+            }
+            ("hash-max-listpack-value", [value]) | ("hash-max-ziplist-value", [value]) => {
+                self.hash_max_listpack_value = memtoull(value).ok_or("argument must be a memory value")?;
+            }
+            ("set-max-intset-entries", [value]) => {
+                self.set_max_intset_entries = value.parse().map_err(|_| "argument couldn't be parsed into an integer")?;
+            }
+            ("set-max-listpack-entries", [value]) => {
+                self.set_max_listpack_entries = value.parse().map_err(|_| "argument couldn't be parsed into an integer")?;
+            }
+            ("set-max-listpack-value", [value]) => {
+                self.set_max_listpack_value = memtoull(value).ok_or("argument must be a memory value")?;
+            }
+            _ => return Err("Bad directive or wrong number of arguments".into()),
+        }
+        Ok(())
+    }
+}
+
+/// Parse a `yes`/`no` boolean directive.
+fn yesnotoi(value: &str) -> Result<bool, String> {
+    match &value.to_lowercase()[..] {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        _ => Err("argument must be 'yes' or 'no'".into()),
+    }
+}
+
+/// Convert a memory amount such as `1gb` or `64mb` into bytes.
+///
+/// Units are case insensitive: `k`, `m` and `g` are powers of 1000, `kb`, `mb`
+/// and `gb` powers of 1024. A plain number is taken as bytes.
+pub(crate) fn memtoull(value: &str) -> Option<usize> {
+    let value = value.to_lowercase();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (digits, unit) = value.split_at(split);
+
+    let mul: usize = match unit {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+
+    digits.parse::<usize>().ok()?.checked_mul(mul)
+}
@@ -1,8 +1,14 @@
-use resp::{self, Result, protocol::Protocol};
+use resp::{self, Result, protocol::{Protocol, ProtocolVersion}, request::{RequestDecoder, RequestLimits}};
 
 use bytes::{Buf, BytesMut};
-use std::io::{self, Cursor, Read, Write};
+use std::io::{self, ErrorKind, Read, Write};
+use std::time::Instant;
 use mio::net::TcpStream;
+use mio::{Interest, Registry, Token};
+use crate::client::ClientBufferLimit;
+
+/// How much the read buffer grows by for every `read` issued on the socket.
+const READ_CHUNK_SIZE: usize = 16 * 1024;
 
 /// Send and receive `Protocol` values from a remote peer.
 ///
@@ -14,8 +20,10 @@ use mio::net::TcpStream;
 /// up until there are enough bytes to create a full Protocol. Once this happens,
 /// the `Connection` creates the Protocol and returns it to the caller.
 ///
-/// When sending Protocols, the Protocol is first encoded into the write buffer.
-/// The contents of the write buffer are then written to the socket.
+/// When sending Protocols, the Protocol is first encoded into the write buffer,
+/// which acts as the client's output buffer. The event loop drains it with
+/// `flush` right before going to sleep; whatever the socket does not accept
+/// stays buffered and is written once the socket reports itself writable.
 #[derive(Debug)]
 pub struct Connection {
     // The `TcpStream`. It is decorated with a `BufWriter`, which provides write
@@ -27,14 +35,40 @@ pub struct Connection {
 
     // The buffer for reading Protocols.
     buffer: BytesMut,
+
+    // Decoder of the commands in `buffer`, holding the part of a command
+    // received so far.
+    decoder: RequestDecoder,
+
+    // Encoded replies waiting to be written to the socket.
+    write_buffer: BytesMut,
+
+    // Whether the socket is currently registered for writable events, which
+    // is only the case while `write_buffer` could not be flushed in full.
+    writable_interest: bool,
+
+    // Protocol version replies are encoded with, switched by `HELLO`.
+    version: ProtocolVersion,
+
+    // Limits of `write_buffer`, past which the client must be closed.
+    output_limit: ClientBufferLimit,
+
+    // When `write_buffer` went over the soft limit. Cleared as soon as it is
+    // found below the limit again.
+    soft_limit_reached_time: Option<Instant>,
+
+    // Set once `write_buffer` went past its limits, Redis'
+    // `CLIENT_CLOSE_ASAP`. Further replies are dropped.
+    output_limit_reached: bool,
 }
 
 
 
 impl Connection {
-    /// Create a new `Connection`, backed by `socket`. Read and write buffers
-    /// are initialized.
-    pub fn new(socket: TcpStream) -> Connection {
+    /// Create a new `Connection`, backed by `socket`, accepting requests
+    /// within `limits` and buffering replies within `output_limit`. Read and
+    /// write buffers are initialized.
+    pub fn new(socket: TcpStream, limits: RequestLimits, output_limit: ClientBufferLimit) -> Connection {
         Connection {
             // stream: BufWriter::new(socket),
             // Default to a 4KB read buffer. For the use case of mini redis,
@@ -42,198 +76,204 @@ impl Connection {
             // value to their specific use case. There is a high likelihood that
             // a larger read buffer will work better.
             buffer: BytesMut::with_capacity(4 * 1024),
+            decoder: RequestDecoder::with_limits(limits),
+            write_buffer: BytesMut::with_capacity(4 * 1024),
+            writable_interest: false,
+            version: ProtocolVersion::default(),
+            output_limit,
+            soft_limit_reached_time: None,
+            output_limit_reached: false,
             tcp_stream: socket
         }
     }
 
-    /// Read a single `Protocol` value from the underlying stream.
+    /// Read everything currently available on the socket into the read
+    /// buffer.
     ///
-    /// The function waits until it has retrieved enough data to parse a Protocol.
-    /// Any data remaining in the read buffer after the Protocol has been parsed is
-    /// kept there for the next call to `read_protocol`.
+    /// The socket is registered edge-triggered, so a single readable event may
+    /// stand for any number of pipelined commands. The socket is therefore
+    /// drained until it reports `WouldBlock`; stopping earlier would leave
+    /// bytes in the kernel buffer that no further event would announce. The
+    /// only exception is a buffer grown past `limit`, whose client is to be
+    /// closed, see `query_buffer_len`.
     ///
     /// # Returns
     ///
-    /// On success, the received Protocol is returned. If the `TcpStream`
-    /// is closed in a way that doesn't break a Protocol in half, it returns
-    /// `None`. Otherwise, an error is returned.
-    pub fn read_protocol(&mut self) -> Result<Option<Protocol>> {
-        loop {
-            // Attempt to parse a Protocol from the buffered data. If enough data
-            // has been buffered, the Protocol is returned.
-            if let Some(Protocol) = self.parse_protocol()? {
-                return Ok(Some(Protocol));
-            }
+    /// The number of bytes read, along with `true` while the peer keeps the
+    /// connection open or `false` once it has closed its side. Data read
+    /// before the close is kept in the buffer so the caller can still serve it.
+    pub fn fill_buffer(&mut self, limit: usize) -> io::Result<(usize, bool)> {
+        let mut read = 0;
+        while self.buffer.len() <= limit {
+            // Grow the buffer by one chunk and read straight into the new
+            // space, then give back whatever the read did not use.
+            let len = self.buffer.len();
+            self.buffer.resize(len + READ_CHUNK_SIZE, 0);
 
-            // There is not enough buffered data to read a Protocol. Attempt to
-            // read more data from the socket.
-            //
-            // On success, the number of bytes is returned. `0` indicates "end
-            // of stream".
-            if 0 == self.tcp_stream.read(&mut self.buffer).unwrap() {
-                // The remote closed the connection. For this to be a clean
-                // shutdown, there should be no data in the read buffer. If
-                // there is, this means that the peer closed the socket while
-                // sending a Protocol.
-                return if self.buffer.is_empty() {
-                    Ok(None)
-                } else {
-                    Err("connection reset by peer".into())
+            match self.tcp_stream.read(&mut self.buffer[len..]) {
+                Ok(0) => {
+                    self.buffer.truncate(len);
+                    return Ok((read, false));
+                }
+                Ok(n) => {
+                    self.buffer.truncate(len + n);
+                    read += n;
+                }
+                Err(e) => {
+                    self.buffer.truncate(len);
+                    match e.kind() {
+                        ErrorKind::WouldBlock => return Ok((read, true)),
+                        ErrorKind::Interrupted => continue,
+                        _ => return Err(e),
+                    }
                 }
             }
         }
+        Ok((read, true))
+    }
+
+    /// Bytes of requests received and not processed yet.
+    pub fn query_buffer_len(&self) -> usize {
+        self.buffer.len()
     }
 
     /// Tries to parse a Protocol from the buffer. If the buffer contains enough
     /// data, the Protocol is returned and the data removed from the buffer. If not
     /// enough data has been buffered yet, `Ok(None)` is returned. If the
     /// buffered data does not represent a valid Protocol, `Err` is returned.
-    fn parse_protocol(&mut self) -> Result<Option<Protocol>> {
-        // use Protocol::Error::Incomplete;
-
-        // Cursor is used to track the "current" location in the
-        // buffer. Cursor also implements `Buf` from the `bytes` crate
-        // which provides a number of helpful utilities for working
-        // with bytes.
-        let mut buf = Cursor::new(&self.buffer[..]);
-
-        // The first step is to check if enough data has been buffered to parse
-        // a single Protocol. This step is usually much faster than doing a full
-        // parse of the Protocol, and allows us to skip allocating data structures
-        // to hold the Protocol data unless we know the full Protocol has been
-        // received.
-        match Protocol::check(&mut buf) {
-            Ok(_) => {
-                // The `check` function will have advanced the cursor until the
-                // end of the protocol. Since the cursor had position set to zero
-                // before `protocol::check` was called, we obtain the length of the
-                // protocol by checking the cursor position.
-                let len = buf.position() as usize;
-
-                // Reset the position to zero before passing the cursor to
-                // `protocol::parse`.
-                buf.set_position(0);
-
-                // Parse the protocol from the buffer. This allocates the necessary
-                // structures to represent the protocol and returns the protocol
-                // value.
-                //
-                // If the encoded protocol representation is invalid, an error is
-                // returned. This should terminate the **current** connection
-                // but should not impact any other connected client.
-                let protocol = Protocol::parse(&mut buf)?;
-
-                // Discard the parsed data from the read buffer.
-                //
-                // When `advance` is called on the read buffer, all the data
-                // up to `len` is discarded. The details of how this works is
-                // left to `BytesMut`. This is often done by moving an internal
-                // cursor, but it may be done by reallocating and copying data.
-                self.buffer.advance(len);
-
-                // Return the parsed protocol to the caller.
-                Ok(Some(protocol))
-            }
-            // There is not enough data present in the read buffer to parse a
-            // single Protocol. We must wait for more data to be received from the
-            // socket. Reading from the socket will be done in the statement
-            // after this `match`.
-            //
-            // We do not want to return `Err` from here as this "error" is an
-            // expected runtime condition.
-            Err(_Incomplete) => Ok(None),
-            // An error was encountered while parsing the Protocol. The connection
-            // is now in an invalid state. Returning `Err` from here will result
-            // in the connection being closed.
-        }
+    ///
+    /// Only bytes already in the buffer are looked at; call `fill_buffer` to
+    /// pull more from the socket. Calling this in a loop until it returns
+    /// `Ok(None)` yields every pipelined command received so far. The part of
+    /// a command already decoded is consumed from the buffer and kept by the
+    /// decoder, so a large command arriving over many reads is only scanned
+    /// once.
+    pub fn parse_protocol(&mut self) -> Result<Option<Protocol>> {
+        self.decoder.decode(&mut self.buffer)
     }
 
-    /// Write a single `protocol` value to the underlying stream.
+    /// Write a single `protocol` value to the write buffer.
+    ///
+    /// Nothing reaches the socket until `flush` is called. Replies to a batch
+    /// of pipelined commands are accumulated this way and written together,
+    /// instead of issuing several small writes per reply.
     ///
-    /// The `protocol` value is written to the socket using the various `write_*`
-    /// functions provided by `AsyncWrite`. Calling these functions directly on
-    /// a `TcpStream` is **not** advised, as this will result in a large number of
-    /// syscall. However, it is fine to call these functions on a *buffered*
-    /// write stream. The data will be written to the buffer. Once the buffer is
-    /// full, it is flushed to the underlying socket.
+    /// Every reply is checked against the output buffer limits, whatever
+    /// produced it, as Redis' `closeClientOnOutputBufferLimitReached`. Once
+    /// they are reached the reply is dropped, and so are the following ones:
+    /// the client is to be closed, see `output_buffer_limit_reached`.
     pub fn write_protocol(&mut self, protocol: &Protocol) -> io::Result<()> {
-        // Arrays are encoded by encoding each entry. All other protocol types are
-        // considered literals. For now, rudis is not able to encode
-        // recursive protocol structures. See below for more details.
-        match protocol {
-            Protocol::Array(val) => {
-                // Encode the protocol type prefix. For an array, it is `*`.
-                self.tcp_stream.write(&[b'*']).expect("TODO: panic message");
-
-                // Encode the length of the array.
-                self.write_decimal(val.len() as u64)?;
-
-                // Iterate and encode each entry in the array.
-                for entry in &**val {
-                    self.write_value(entry)?;
+        if self.output_limit_reached {
+            return Ok(());
+        }
+        protocol.encode(&mut self.write_buffer, self.version);
+        self.check_output_buffer_limits();
+        Ok(())
+    }
+
+    /// Check the write buffer against the output buffer limits, as Redis'
+    /// `checkClientOutputBufferLimits` does.
+    ///
+    /// The hard limit trips immediately. The soft limit only trips once the
+    /// buffer has stayed over it for longer than the configured number of
+    /// seconds; the time it was first exceeded is recorded and forgotten as
+    /// soon as the buffer is seen below the limit again.
+    fn check_output_buffer_limits(&mut self) {
+        let limit = &self.output_limit;
+        let used = self.write_buffer.len();
+
+        let hard = limit.hard_limit_bytes > 0 && used >= limit.hard_limit_bytes;
+        let mut soft = limit.soft_limit_bytes > 0 && used >= limit.soft_limit_bytes;
+
+        if soft {
+            match self.soft_limit_reached_time {
+                None => {
+                    self.soft_limit_reached_time = Some(Instant::now());
+                    soft = false;
+                }
+                Some(since) => {
+                    soft = since.elapsed().as_secs() > limit.soft_limit_seconds;
                 }
             }
-            // The protocol type is a literal. Encode the value directly.
-            _ => return self.write_value(protocol),
+        } else {
+            self.soft_limit_reached_time = None;
         }
 
-        // Ensure the encoded protocol is written to the socket. The calls above
-        // are to the buffered stream and writes. Calling `flush` writes the
-        // remaining contents of the buffer to the socket.
-        self.tcp_stream.flush()
+        self.output_limit_reached |= hard || soft;
     }
 
-    /// Write a protocol literal to the stream
-    fn write_value(&mut self, protocol: &Protocol) -> io::Result<()> {
-        match protocol {
-            Protocol::Simple(val) => {
-                self.tcp_stream.write(&[b'+']).expect("TODO: panic message");
-                self.tcp_stream.write_all(val.as_bytes())?;
-                self.tcp_stream.write_all(b"\r\n")?;
-            }
-            Protocol::Error(val) => {
-                self.tcp_stream.write(&[b'-']).expect("TODO: panic message");
-                self.tcp_stream.write_all(val.as_bytes())?;
-                self.tcp_stream.write_all(b"\r\n")?;
-            }
-            Protocol::Integer(val) => {
-                self.tcp_stream.write(&[b':']).expect("TODO: panic message");
-                self.write_decimal(*val)?;
-            }
-            Protocol::Null => {
-                self.tcp_stream.write_all(b"$-1\r\n")?;
-            }
-            Protocol::Bulk(val) => {
-                let len = val.len();
-
-                self.tcp_stream.write(&[b'$']).expect("TODO: panic message");
-                self.write_decimal(len as u64)?;
-                self.tcp_stream.write_all(val)?;
-                self.tcp_stream.write_all(b"\r\n")?;
-            }
-            // Encoding an `Array` from within a value cannot be done using a
-            // recursive strategy. In general, async fns do not support
-            // recursion. rudis has not needed to encode nested arrays yet,
-            // so for now it is skipped.
-            Protocol::Array(_val) => unreachable!(),
+    /// Returns `true` once the write buffer went past the output buffer
+    /// limits and the client must be closed. The soft limit is checked again,
+    /// so that a client no longer sent replies still trips it once its time
+    /// is up.
+    pub fn output_buffer_limit_reached(&mut self) -> bool {
+        if !self.output_limit_reached {
+            self.check_output_buffer_limits();
         }
+        self.output_limit_reached
+    }
 
-        Ok(())
+    /// Protocol version spoken with the peer.
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
     }
 
-    /// Write a decimal Protocol to the stream
-    fn write_decimal(&mut self, val: u64) -> io::Result<()> {
-        // use std::io::Write;
+    /// Switch the protocol version the following replies are encoded with.
+    pub fn set_version(&mut self, version: ProtocolVersion) {
+        self.version = version;
+    }
 
-        // Convert the value to a string
-        let mut buf = [0u8; 20];
-        let mut buf = Cursor::new(&mut buf[..]);
-        write!(&mut buf, "{}", val)?;
+    /// Returns `true` if some replies have not been written to the socket yet.
+    pub fn has_pending_replies(&self) -> bool {
+        !self.write_buffer.is_empty()
+    }
 
-        let pos = buf.position() as usize;
-        self.tcp_stream.write_all(&buf.get_ref()[..pos])?;
-        self.tcp_stream.write_all(b"\r\n")?;
+    /// Write as much of the buffered replies as the socket accepts.
+    ///
+    /// The socket is non-blocking, so a slow reader can make it stop accepting
+    /// data at any point. The written prefix is dropped from the buffer and
+    /// the rest is kept for the next attempt.
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes written, along with `true` once the write buffer is
+    /// empty or `false` if the socket would block before everything was
+    /// written.
+    pub fn flush(&mut self) -> io::Result<(usize, bool)> {
+        let mut written = 0;
+        while !self.write_buffer.is_empty() {
+            match self.tcp_stream.write(&self.write_buffer) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.write_buffer.advance(n);
+                    written += n;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok((written, false)),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok((written, true))
+    }
+
+    /// Register or unregister interest in the socket becoming writable.
+    ///
+    /// Writable interest is only wanted while a flush is incomplete: a socket
+    /// with room in its send buffer is writable almost all the time, so keeping
+    /// the interest around would wake the event loop for nothing.
+    pub fn set_writable_interest(&mut self, registry: &Registry, token: Token, writable: bool) -> io::Result<()> {
+        if self.writable_interest == writable {
+            return Ok(());
+        }
 
+        let interest = if writable {
+            Interest::READABLE | Interest::WRITABLE
+        } else {
+            Interest::READABLE
+        };
+        registry.reregister(&mut self.tcp_stream, token, interest)?;
+        self.writable_interest = writable;
         Ok(())
     }
 }
@@ -0,0 +1,128 @@
+use std::io;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use crate::config::CONFIG_MAX_HZ;
+use crate::db::expire::ActiveExpireCycle;
+use crate::eventloop::event::EventID;
+use crate::eventloop::time_event::TimeEventManager;
+use crate::server::RedisServer;
+
+/// Above this number of clients per cron tick, `dynamic-hz` raises the
+/// frequency of the cron so `clients_cron` keeps visiting every client about
+/// once per second.
+const MAX_CLIENTS_PER_CLOCK_TICK: usize = 200;
+
+/// Interval between two metric samples, in milliseconds.
+const STATS_METRIC_SAMPLE_PERIOD: u64 = 100;
+
+/// Databases whose tables are checked for resizing, or whose keys are
+/// sampled for expiration, per cron tick.
+pub(crate) const CRON_DBS_PER_CALL: usize = 16;
+
+/// Periodic tasks of the server, Redis' `serverCron`.
+///
+/// The cron is a time event rescheduling itself every `1000 / hz`
+/// milliseconds. Tasks that should not run on every tick use
+/// `run_with_period`.
+pub(crate) struct ServerCron {
+    server: RedisServer,
+    // Number of times the cron ran.
+    cronloops: u64,
+    // Next databases to resize and to rehash, visited round-robin.
+    resize_db: usize,
+    rehash_db: usize,
+    active_expire: ActiveExpireCycle,
+}
+
+impl ServerCron {
+    /// Register the cron with the time events of the server, to run as soon
+    /// as the event loop starts.
+    pub(crate) fn start(server: &RedisServer) -> io::Result<EventID> {
+        let mut cron = ServerCron {
+            server: server.clone(),
+            cronloops: 0,
+            resize_db: 0,
+            rehash_db: 0,
+            active_expire: ActiveExpireCycle::default(),
+        };
+        server
+            .time_event_manager
+            .clone()
+            .create_time_event(Duration::from_millis(1), Box::new(move |_| cron.run()), None)
+    }
+
+    /// Run one tick of the cron. Returns the milliseconds until the next one.
+    fn run(&mut self) -> i64 {
+        let hz = self.update_hz();
+
+        if self.run_with_period(STATS_METRIC_SAMPLE_PERIOD, hz) {
+            let mut stats = self.server.stats.lock().unwrap();
+            let (ops, input, output) = (stats.total_commands_processed, stats.net_input_bytes, stats.net_output_bytes);
+            stats.instantaneous_ops.track(ops);
+            stats.instantaneous_net_input.track(input);
+            stats.instantaneous_net_output.track(output);
+        }
+
+        let timeout = self.server.config.timeout;
+        let max_idle = (timeout > 0).then(|| Duration::from_secs(timeout));
+        self.server.client_manager.clients_cron(hz, max_idle);
+
+        self.databases_cron(hz);
+
+        self.cronloops += 1;
+        1000 / hz as i64
+    }
+
+    /// Compute the frequency of this tick from the configured one, doubled as
+    /// long as there are too many clients per tick if `dynamic-hz` is set.
+    fn update_hz(&self) -> u32 {
+        let mut hz = self.server.config.hz;
+        if self.server.config.dynamic_hz {
+            let clients = self.server.client_manager.connected_clients();
+            while clients / hz as usize > MAX_CLIENTS_PER_CLOCK_TICK {
+                hz *= 2;
+                if hz > CONFIG_MAX_HZ {
+                    hz = CONFIG_MAX_HZ;
+                    break;
+                }
+            }
+        }
+        self.server.hz.store(hz, Ordering::Relaxed);
+        hz
+    }
+
+    /// Whether a task meant to run every `ms` milliseconds is due on this
+    /// tick, Redis' `run_with_period` macro.
+    fn run_with_period(&self, ms: u64, hz: u32) -> bool {
+        let period = 1000 / hz as u64;
+        ms <= period || self.cronloops.is_multiple_of(ms / period)
+    }
+
+    /// Incremental housekeeping of the keyspace, Redis' `databasesCron`:
+    /// reclaim expired keys, shrink mostly empty tables, then spend a
+    /// millisecond rehashing the first database that needs it.
+    fn databases_cron(&mut self, hz: u32) {
+        let mut dbs = self.server.keyspace.lock();
+
+        let mut stats = self.server.stats.lock().unwrap();
+        self.active_expire.run(&mut dbs, hz, self.server.config.active_expire_effort, &mut stats);
+        drop(stats);
+
+        let dbnum = dbs.len();
+        let dbs_per_call = CRON_DBS_PER_CALL.min(dbnum);
+
+        for _ in 0..dbs_per_call {
+            dbs[self.resize_db].try_resize();
+            self.resize_db = (self.resize_db + 1) % dbnum;
+        }
+
+        if self.server.config.activerehashing {
+            for _ in 0..dbs_per_call {
+                if dbs[self.rehash_db].rehash_ms(1) {
+                    break;
+                }
+                self.rehash_db = (self.rehash_db + 1) % dbnum;
+            }
+        }
+    }
+}
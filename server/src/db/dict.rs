@@ -0,0 +1,420 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+use std::time::{Duration, Instant};
+use ahash::RandomState;
+use crate::util::random;
+
+/// Size of a table the first time it gets buckets.
+const DICT_HT_INITIAL_SIZE: usize = 4;
+
+/// Below this percentage of used buckets, `try_resize` shrinks the table.
+const HASHTABLE_MIN_FILL: usize = 8;
+
+/// Empty buckets visited per bucket to move, so one rehash step stays short
+/// even when the old table is sparse.
+const REHASH_EMPTY_VISITS: usize = 10;
+
+#[derive(Clone)]
+struct Table<K, V> {
+    buckets: Vec<Vec<(K, V)>>,
+    used: usize,
+}
+
+impl<K, V> Table<K, V> {
+    fn empty() -> Self {
+        Self { buckets: Vec::new(), used: 0 }
+    }
+
+    fn with_size(size: usize) -> Self {
+        Self { buckets: (0..size).map(|_| Vec::new()).collect(), used: 0 }
+    }
+
+    fn size(&self) -> usize {
+        self.buckets.len()
+    }
+
+    fn mask(&self) -> usize {
+        self.buckets.len().wrapping_sub(1)
+    }
+}
+
+/// Hash table with incremental rehashing, after Redis' `dict.c`.
+///
+/// Tables are power-of-two arrays of buckets. Growing or shrinking allocates
+/// a second table and entries move over a few buckets at a time, on every
+/// update and from the server cron, so resizing a table with millions of
+/// keys never stalls the event loop. Lookups check both tables meanwhile.
+#[derive(Clone)]
+pub(crate) struct Dict<K, V> {
+    ht: [Table<K, V>; 2],
+    // Next bucket of `ht[0]` to move to `ht[1]`, `None` when not rehashing.
+    rehash_idx: Option<usize>,
+    hash_builder: RandomState,
+}
+
+impl<K, V> Default for Dict<K, V> {
+    fn default() -> Self {
+        Self {
+            ht: [Table::empty(), Table::empty()],
+            rehash_idx: None,
+            hash_builder: RandomState::new(),
+        }
+    }
+}
+
+impl<K, V> fmt::Debug for Dict<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dict")
+            .field("len", &(self.ht[0].used + self.ht[1].used))
+            .field("size", &self.ht[0].size())
+            .field("rehashing", &self.rehash_idx.is_some())
+            .finish()
+    }
+}
+
+impl<K: Hash + Eq, V> Dict<K, V> {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of entries.
+    pub(crate) fn len(&self) -> usize {
+        self.ht[0].used + self.ht[1].used
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn is_rehashing(&self) -> bool {
+        self.rehash_idx.is_some()
+    }
+
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        self.hash_builder.hash_one(key) as usize
+    }
+
+    /// Table and bucket holding `key`, if any, with its position in the
+    /// bucket.
+    fn find<Q>(&self, key: &Q) -> Option<(usize, usize, usize)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.is_empty() {
+            return None;
+        }
+        let hash = self.hash(key);
+        let tables = if self.is_rehashing() { 2 } else { 1 };
+        for table in 0..tables {
+            let idx = hash & self.ht[table].mask();
+            // Buckets of `ht[0]` before `rehash_idx` were already moved.
+            if let Some(bucket) = self.ht[table].buckets.get(idx) {
+                if let Some(pos) = bucket.iter().position(|(k, _)| k.borrow() == key) {
+                    return Some((table, idx, pos));
+                }
+            }
+        }
+        None
+    }
+
+    pub(crate) fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (table, idx, pos) = self.find(key)?;
+        Some(&self.ht[table].buckets[idx][pos].1)
+    }
+
+    pub(crate) fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.rehash_step();
+        let (table, idx, pos) = self.find(key)?;
+        Some(&mut self.ht[table].buckets[idx][pos].1)
+    }
+
+    pub(crate) fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    /// Every entry, in no particular order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.ht.iter().flat_map(|table| table.buckets.iter().flatten()).map(|(key, value)| (key, value))
+    }
+
+    /// A random entry, Redis' `dictGetRandomKey`: a random non-empty bucket,
+    /// then a random entry of the bucket. Entries sharing a bucket are less
+    /// likely to be picked.
+    pub(crate) fn random_entry(&self) -> Option<(&K, &V)> {
+        if self.is_empty() {
+            return None;
+        }
+        let bucket = match self.rehash_idx {
+            // Buckets of `ht[0]` before `rehash_idx` are empty.
+            Some(rehash_idx) => loop {
+                let (size0, size1) = (self.ht[0].size(), self.ht[1].size());
+                let idx = rehash_idx + random() as usize % (size0 + size1 - rehash_idx);
+                let bucket = if idx >= size0 { &self.ht[1].buckets[idx - size0] } else { &self.ht[0].buckets[idx] };
+                if !bucket.is_empty() {
+                    break bucket;
+                }
+            },
+            None => loop {
+                let bucket = &self.ht[0].buckets[random() as usize & self.ht[0].mask()];
+                if !bucket.is_empty() {
+                    break bucket;
+                }
+            },
+        };
+        let (key, value) = &bucket[random() as usize % bucket.len()];
+        Some((key, value))
+    }
+
+    /// Insert or replace the value of `key`, returning the previous value.
+    pub(crate) fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.rehash_step();
+        if let Some((table, idx, pos)) = self.find(&key) {
+            return Some(mem::replace(&mut self.ht[table].buckets[idx][pos].1, value));
+        }
+
+        self.expand_if_needed();
+        // While rehashing, new entries go straight to the new table.
+        let table = if self.is_rehashing() { 1 } else { 0 };
+        let idx = self.hash(&key) & self.ht[table].mask();
+        self.ht[table].buckets[idx].push((key, value));
+        self.ht[table].used += 1;
+        None
+    }
+
+    /// Remove `key`, returning its entry.
+    pub(crate) fn remove<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.rehash_step();
+        let (table, idx, pos) = self.find(key)?;
+        self.ht[table].used -= 1;
+        Some(self.ht[table].buckets[idx].swap_remove(pos))
+    }
+
+    /// Remove every entry and release the tables. Returns the number of
+    /// entries removed.
+    pub(crate) fn clear(&mut self) -> usize {
+        let removed = self.len();
+        self.ht = [Table::empty(), Table::empty()];
+        self.rehash_idx = None;
+        removed
+    }
+
+    /// Visit the entries of a bucket, and return the cursor of the next call,
+    /// Redis' `dictScan`. A full iteration starts and ends with cursor 0.
+    ///
+    /// The cursor is incremented on its reversed bits, so buckets are visited
+    /// in an order that survives the table growing or shrinking between
+    /// calls: every entry present for the whole iteration is returned at
+    /// least once, though some may be returned more than once.
+    pub(crate) fn scan<F>(&self, cursor: usize, mut f: F) -> usize
+    where
+        F: FnMut(&K, &V),
+    {
+        if self.is_empty() {
+            return 0;
+        }
+        let mut visit = |table: &Table<K, V>, idx: usize| {
+            for (key, value) in &table.buckets[idx & table.mask()] {
+                f(key, value);
+            }
+        };
+        let next = |cursor: usize, mask: usize| (cursor | !mask).reverse_bits().wrapping_add(1).reverse_bits();
+
+        if !self.is_rehashing() {
+            visit(&self.ht[0], cursor);
+            return next(cursor, self.ht[0].mask());
+        }
+
+        let (small, large) = if self.ht[0].size() <= self.ht[1].size() {
+            (&self.ht[0], &self.ht[1])
+        } else {
+            (&self.ht[1], &self.ht[0])
+        };
+        visit(small, cursor);
+        // Then every bucket of the larger table the small bucket expands to.
+        let mut cursor = cursor;
+        loop {
+            visit(large, cursor);
+            cursor = next(cursor, large.mask());
+            if cursor & (small.mask() ^ large.mask()) == 0 {
+                return cursor;
+            }
+        }
+    }
+
+    /// Shrink the table if it is mostly empty, `htNeedsResize` followed by
+    /// `dictResize` in Redis. Called from the server cron since removals do
+    /// not shrink the table themselves.
+    pub(crate) fn try_resize(&mut self) {
+        let size = self.ht[0].size();
+        let used = self.ht[0].used;
+        if !self.is_rehashing() && size > DICT_HT_INITIAL_SIZE && used * 100 <= HASHTABLE_MIN_FILL * size {
+            self.resize(used);
+        }
+    }
+
+    /// Rehash for about `ms` milliseconds, in batches of 100 buckets. Returns
+    /// the number of buckets moved.
+    pub(crate) fn rehash_ms(&mut self, ms: u64) -> usize {
+        let start = Instant::now();
+        let mut rehashes = 0;
+        while self.rehash(100) {
+            rehashes += 100;
+            if start.elapsed() > Duration::from_millis(ms) {
+                break;
+            }
+        }
+        rehashes
+    }
+
+    fn expand_if_needed(&mut self) {
+        if self.is_rehashing() {
+            return;
+        }
+        if self.ht[0].size() == 0 {
+            self.resize(DICT_HT_INITIAL_SIZE);
+        } else if self.ht[0].used >= self.ht[0].size() {
+            self.resize(self.ht[0].used + 1);
+        }
+    }
+
+    /// Start moving the entries to a table able to hold `size` of them.
+    fn resize(&mut self, size: usize) {
+        let size = size.max(DICT_HT_INITIAL_SIZE).next_power_of_two();
+        if size == self.ht[0].size() {
+            return;
+        }
+        if self.ht[0].size() == 0 {
+            self.ht[0] = Table::with_size(size);
+            return;
+        }
+        self.ht[1] = Table::with_size(size);
+        self.rehash_idx = Some(0);
+    }
+
+    /// Move up to `n` buckets from the old table to the new one. Returns
+    /// `true` while there is more to move.
+    fn rehash(&mut self, n: usize) -> bool {
+        let Some(mut idx) = self.rehash_idx else {
+            return false;
+        };
+
+        let mut empty_visits = n * REHASH_EMPTY_VISITS;
+        for _ in 0..n {
+            if self.ht[0].used == 0 {
+                break;
+            }
+            while self.ht[0].buckets[idx].is_empty() {
+                idx += 1;
+                empty_visits -= 1;
+                if empty_visits == 0 {
+                    self.rehash_idx = Some(idx);
+                    return true;
+                }
+            }
+
+            let bucket = mem::take(&mut self.ht[0].buckets[idx]);
+            self.ht[0].used -= bucket.len();
+            self.ht[1].used += bucket.len();
+            for (key, value) in bucket {
+                let new_idx = self.hash(&key) & self.ht[1].mask();
+                self.ht[1].buckets[new_idx].push((key, value));
+            }
+            idx += 1;
+        }
+
+        if self.ht[0].used == 0 {
+            self.ht[0] = mem::replace(&mut self.ht[1], Table::empty());
+            self.rehash_idx = None;
+            return false;
+        }
+        self.rehash_idx = Some(idx);
+        true
+    }
+
+    /// One bucket of rehashing, piggybacked on updates.
+    fn rehash_step(&mut self) {
+        self.rehash(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_survive_growing() {
+        let mut dict = Dict::new();
+        for i in 0..1000 {
+            assert_eq!(dict.insert(i, i * 2), None);
+        }
+        assert_eq!(dict.insert(7, 0), Some(14));
+        assert_eq!(dict.len(), 1000);
+        for i in (0..1000).filter(|i| *i != 7) {
+            assert_eq!(dict.get(&i), Some(&(i * 2)));
+        }
+    }
+
+    #[test]
+    fn scan_returns_every_entry_while_resizing() {
+        let mut dict = Dict::new();
+        for i in 0..100 {
+            dict.insert(i, ());
+        }
+        let mut seen = std::collections::HashSet::new();
+        let mut cursor = 0;
+        loop {
+            cursor = dict.scan(cursor, |key, _| {
+                seen.insert(*key);
+            });
+            // Grow the table halfway through.
+            if seen.len() > 50 && dict.len() == 100 {
+                for i in 100..1000 {
+                    dict.insert(i, ());
+                }
+            }
+            if cursor == 0 {
+                break;
+            }
+        }
+        assert!((0..100).all(|i| seen.contains(&i)));
+    }
+
+    #[test]
+    fn shrinks_once_mostly_empty() {
+        let mut dict = Dict::new();
+        for i in 0..1024 {
+            dict.insert(i, ());
+        }
+        dict.rehash_ms(100);
+        for i in 8..1024 {
+            assert!(dict.remove(&i).is_some());
+        }
+
+        dict.try_resize();
+        assert!(dict.is_rehashing());
+        dict.rehash_ms(100);
+        assert!(!dict.is_rehashing());
+        assert_eq!(dict.ht[0].size(), 8);
+        assert!((0..8).all(|i| dict.contains_key(&i)));
+        assert!(!dict.contains_key(&8));
+    }
+}
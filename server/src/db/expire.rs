@@ -0,0 +1,101 @@
+//! Active expiration of keys, after Redis' `expire.c`.
+//!
+//! Expired keys are deleted when accessed, but keys never accessed again
+//! would stay in memory. The server cron therefore samples keys with a time
+//! to live and deletes the expired ones, going on while the samples show
+//! many expired keys and the time budget of the cycle allows. Expired hash
+//! fields are reclaimed by the same cycle, the hashes being ordered by the
+//! time their next field expires.
+
+use std::time::{Duration, Instant};
+use crate::cron::CRON_DBS_PER_CALL;
+use crate::db::keyspace::Db;
+use crate::server::Stats;
+use crate::util::mstime;
+
+/// Keys sampled per database and loop, at the lowest effort.
+const ACTIVE_EXPIRE_CYCLE_KEYS_PER_LOOP: usize = 20;
+
+/// Percentage of the time between two cron ticks a cycle may use, at the
+/// lowest effort.
+const ACTIVE_EXPIRE_CYCLE_SLOW_TIME_PERC: u64 = 25;
+
+/// Percentage of expired keys in a sample under which a database is left
+/// alone until the next cycle, at the lowest effort.
+const ACTIVE_EXPIRE_CYCLE_ACCEPTABLE_STALE: usize = 10;
+
+/// Hash fields deleted per database and cycle at most, Redis'
+/// `HFE_ACTIVE_EXPIRE_CYCLE_FIELDS`.
+const ACTIVE_EXPIRE_CYCLE_FIELDS: usize = 1000;
+
+/// State kept between cycles, Redis' `activeExpireCycle` statics.
+#[derive(Debug, Default)]
+pub(crate) struct ActiveExpireCycle {
+    // Next database to visit.
+    current_db: usize,
+    // Whether the previous cycle stopped on its time limit.
+    timelimit_exit: bool,
+}
+
+impl ActiveExpireCycle {
+    /// Run one slow cycle over `dbs` from a cron running `hz` times per
+    /// second, with `effort` from 1 to 10.
+    pub(crate) fn run(&mut self, dbs: &mut [Db], hz: u32, effort: u32, stats: &mut Stats) {
+        let effort = effort.saturating_sub(1) as usize;
+        let keys_per_loop = ACTIVE_EXPIRE_CYCLE_KEYS_PER_LOOP + ACTIVE_EXPIRE_CYCLE_KEYS_PER_LOOP / 4 * effort;
+        let time_perc = ACTIVE_EXPIRE_CYCLE_SLOW_TIME_PERC + 2 * effort as u64;
+        let acceptable_stale = ACTIVE_EXPIRE_CYCLE_ACCEPTABLE_STALE - effort;
+
+        let dbnum = dbs.len();
+        // Catch up on every database after a cycle that ran out of time.
+        let dbs_per_call = if self.timelimit_exit { dbnum } else { CRON_DBS_PER_CALL.min(dbnum) };
+        let timelimit = Duration::from_micros(time_perc * 1_000_000 / hz as u64 / 100);
+        let start = Instant::now();
+        self.timelimit_exit = false;
+
+        let (mut total_sampled, mut total_expired) = (0, 0);
+        let mut iteration = 0u64;
+        for _ in 0..dbs_per_call {
+            if self.timelimit_exit {
+                break;
+            }
+            let db = &mut dbs[self.current_db % dbnum];
+            self.current_db = self.current_db.wrapping_add(1);
+            db.expire_hash_fields(mstime(), ACTIVE_EXPIRE_CYCLE_FIELDS);
+
+            loop {
+                let num = db.expires_len();
+                if num == 0 {
+                    db.avg_ttl = 0;
+                    break;
+                }
+                iteration += 1;
+
+                let sample = db.sample_expires(num.min(keys_per_loop), mstime());
+                total_sampled += sample.sampled;
+                total_expired += sample.expired;
+
+                if sample.ttl_samples > 0 {
+                    let avg_ttl = sample.ttl_sum / sample.ttl_samples;
+                    // A moving average, so one sample does not swing it.
+                    db.avg_ttl = if db.avg_ttl == 0 { avg_ttl } else { db.avg_ttl / 50 * 49 + avg_ttl / 50 };
+                }
+
+                // Checking the time is not free, do it every 16 loops.
+                if iteration.is_multiple_of(16) && start.elapsed() > timelimit {
+                    self.timelimit_exit = true;
+                    stats.expired_time_cap_reached_count += 1;
+                    break;
+                }
+
+                // Go on while the sample had too many expired keys.
+                if sample.sampled > 0 && sample.expired * 100 / sample.sampled <= acceptable_stale {
+                    break;
+                }
+            }
+        }
+
+        let current_perc = if total_sampled > 0 { total_expired as f64 / total_sampled as f64 } else { 0.0 };
+        stats.expired_stale_perc = current_perc * 0.05 + stats.expired_stale_perc * 0.95;
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::BTreeSet;
+use std::iter;
+use ahash::AHashMap;
+use bytes::Bytes;
+use crate::db::dict::Dict;
+use crate::db::listpack::{Listpack, ListpackEntry};
+use crate::util::random;
+
+#[derive(Debug, Clone)]
+enum Encoding {
+    /// Fields each followed by their value.
+    Listpack(Listpack),
+    Dict(Dict<Bytes, Bytes>),
+}
+
+/// Time to live of the fields of a hash: the UNIX time in milliseconds each
+/// field expires at, and the fields ordered by it, the next to expire first.
+#[derive(Debug, Clone, Default)]
+struct FieldExpires {
+    whens: AHashMap<Bytes, i64>,
+    order: BTreeSet<(i64, Bytes)>,
+}
+
+/// The value of a hash, after Redis' `t_hash.c`.
+///
+/// Small hashes are packed in a listpack, a field then its value, and
+/// searched linearly. Past `max_listpack_entries` fields, or once a field or
+/// a value is longer than `max_listpack_value` bytes, the hash is converted
+/// to a `Dict` for good: the `hash-max-listpack-*` directives trade lookups
+/// in a few dozen entries against the memory of a table per hash.
+///
+/// Fields may have a time to live, kept aside from the encoding and only
+/// allocated once a field gets one, so hashes without any cost a pointer.
+#[derive(Debug, Clone)]
+pub(crate) struct Hash {
+    encoding: Encoding,
+    max_listpack_entries: usize,
+    max_listpack_value: usize,
+    expires: Option<Box<FieldExpires>>,
+}
+
+impl Hash {
+    pub(crate) fn new(max_listpack_entries: usize, max_listpack_value: usize) -> Self {
+        Self { encoding: Encoding::Listpack(Listpack::new()), max_listpack_entries, max_listpack_value, expires: None }
+    }
+
+    /// Number of fields.
+    pub(crate) fn len(&self) -> usize {
+        match &self.encoding {
+            Encoding::Listpack(listpack) => listpack.len() / 2,
+            Encoding::Dict(dict) => dict.len(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Index of the entry of `field` in the listpack.
+    fn listpack_position(listpack: &Listpack, field: &[u8]) -> Option<usize> {
+        listpack.iter().step_by(2).position(|entry| entry.eq_bytes(field)).map(|pair| pair * 2)
+    }
+
+    pub(crate) fn get(&self, field: &[u8]) -> Option<Bytes> {
+        match &self.encoding {
+            Encoding::Listpack(listpack) => {
+                let index = Self::listpack_position(listpack, field)?;
+                listpack.get(index + 1).map(ListpackEntry::to_bytes)
+            }
+            Encoding::Dict(dict) => dict.get(field).cloned(),
+        }
+    }
+
+    pub(crate) fn contains(&self, field: &[u8]) -> bool {
+        match &self.encoding {
+            Encoding::Listpack(listpack) => Self::listpack_position(listpack, field).is_some(),
+            Encoding::Dict(dict) => dict.contains_key(field),
+        }
+    }
+
+    /// Set `field` to `value`. The time to live of an existing field is
+    /// discarded unless `keep_ttl` is set. Returns whether the field is new.
+    pub(crate) fn insert(&mut self, field: Bytes, value: Bytes, keep_ttl: bool) -> bool {
+        if !keep_ttl {
+            self.remove_expire(&field);
+        }
+        if field.len() > self.max_listpack_value || value.len() > self.max_listpack_value {
+            self.convert();
+        }
+        let added = match &mut self.encoding {
+            Encoding::Listpack(listpack) => match Self::listpack_position(listpack, &field) {
+                Some(index) => {
+                    listpack.replace(index + 1, &value);
+                    false
+                }
+                None => {
+                    listpack.push_back(&field);
+                    listpack.push_back(&value);
+                    true
+                }
+            },
+            Encoding::Dict(dict) => dict.insert(field, value).is_none(),
+        };
+        if self.len() > self.max_listpack_entries {
+            self.convert();
+        }
+        added
+    }
+
+    /// Remove `field` along with its time to live. Returns whether it
+    /// existed.
+    pub(crate) fn remove(&mut self, field: &[u8]) -> bool {
+        self.remove_expire(field);
+        match &mut self.encoding {
+            Encoding::Listpack(listpack) => match Self::listpack_position(listpack, field) {
+                Some(index) => {
+                    listpack.remove_range(index, 2);
+                    true
+                }
+                None => false,
+            },
+            Encoding::Dict(dict) => dict.remove(field).is_some(),
+        }
+    }
+
+    /// UNIX time in milliseconds at which `field` expires, if it has a time
+    /// to live.
+    pub(crate) fn get_expire(&self, field: &[u8]) -> Option<i64> {
+        self.expires.as_ref()?.whens.get(field).copied()
+    }
+
+    /// Make an existing `field` expire at `when`, a UNIX time in
+    /// milliseconds.
+    pub(crate) fn set_expire(&mut self, field: Bytes, when: i64) {
+        if !self.contains(&field) {
+            return;
+        }
+        let expires = self.expires.get_or_insert_default();
+        if let Some(old) = expires.whens.insert(field.clone(), when) {
+            expires.order.remove(&(old, field.clone()));
+        }
+        expires.order.insert((when, field));
+    }
+
+    /// Remove the time to live of `field`. Returns `true` if it had one.
+    pub(crate) fn remove_expire(&mut self, field: &[u8]) -> bool {
+        let Some(expires) = self.expires.as_mut() else {
+            return false;
+        };
+        match expires.whens.remove_entry(field) {
+            Some((field, when)) => expires.order.remove(&(when, field)),
+            None => false,
+        }
+    }
+
+    /// The time the next field expires at, if any has a time to live.
+    pub(crate) fn min_expire(&self) -> Option<i64> {
+        self.expires.as_ref()?.order.first().map(|(when, _)| *when)
+    }
+
+    /// Delete up to `limit` fields expired at `now`, the soonest first.
+    /// Returns how many were deleted.
+    pub(crate) fn expire_fields(&mut self, now: i64, limit: usize) -> usize {
+        let mut expired = 0;
+        while expired < limit {
+            match self.expires.as_ref().and_then(|expires| expires.order.first()) {
+                Some((when, field)) if *when <= now => {
+                    let field = field.clone();
+                    self.remove(&field);
+                    expired += 1;
+                }
+                _ => break,
+            }
+        }
+        expired
+    }
+
+    /// Name of the encoding, as replied by `OBJECT ENCODING`. A listpack
+    /// whose fields ever had a time to live is `listpackex`.
+    pub(crate) fn encoding_name(&self) -> &'static str {
+        match &self.encoding {
+            Encoding::Listpack(_) if self.expires.is_some() => "listpackex",
+            Encoding::Listpack(_) => "listpack",
+            Encoding::Dict(_) => "hashtable",
+        }
+    }
+
+    /// Convert the listpack to a `Dict`, Redis' `hashTypeConvert`.
+    fn convert(&mut self) {
+        if let Encoding::Listpack(listpack) = &self.encoding {
+            let mut dict = Dict::new();
+            for (field, value) in Self::listpack_pairs(listpack) {
+                dict.insert(field, value);
+            }
+            self.encoding = Encoding::Dict(dict);
+        }
+    }
+
+    fn listpack_pairs(listpack: &Listpack) -> impl Iterator<Item = (Bytes, Bytes)> + '_ {
+        let mut entries = listpack.iter();
+        iter::from_fn(move || Some((entries.next()?.to_bytes(), entries.next()?.to_bytes())))
+    }
+
+    /// Every field and its value, in no particular order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Bytes, Bytes)> + '_ {
+        let (listpack, dict) = match &self.encoding {
+            Encoding::Listpack(listpack) => (Some(Self::listpack_pairs(listpack)), None),
+            Encoding::Dict(dict) => (None, Some(dict.iter().map(|(field, value)| (field.clone(), value.clone())))),
+        };
+        listpack.into_iter().flatten().chain(dict.into_iter().flatten())
+    }
+
+    /// A random field and its value.
+    pub(crate) fn random_entry(&self) -> Option<(Bytes, Bytes)> {
+        match &self.encoding {
+            Encoding::Listpack(listpack) => {
+                let index = random() as usize % self.len().max(1) * 2;
+                Some((listpack.get(index)?.to_bytes(), listpack.get(index + 1)?.to_bytes()))
+            }
+            Encoding::Dict(dict) => dict.random_entry().map(|(field, value)| (field.clone(), value.clone())),
+        }
+    }
+
+    /// Visit the fields of a bucket and return the next cursor, as
+    /// `Dict::scan`. A listpack is visited at once, with 0 as next cursor.
+    pub(crate) fn scan<F>(&self, cursor: usize, mut f: F) -> usize
+    where
+        F: FnMut(&Bytes, &Bytes),
+    {
+        match &self.encoding {
+            Encoding::Listpack(listpack) => {
+                for (field, value) in Self::listpack_pairs(listpack) {
+                    f(&field, &value);
+                }
+                0
+            }
+            Encoding::Dict(dict) => dict.scan(cursor, f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listpack_converts_past_the_limits() {
+        let mut hash = Hash::new(2, 8);
+        assert!(hash.insert(Bytes::from("a"), Bytes::from("1"), false));
+        assert!(!hash.insert(Bytes::from("a"), Bytes::from("2"), false));
+        assert!(hash.insert(Bytes::from("b"), Bytes::from("3"), false));
+        assert!(matches!(hash.encoding, Encoding::Listpack(_)));
+        assert_eq!(hash.get(b"a"), Some(Bytes::from("2")));
+        assert!(hash.remove(b"a"));
+        assert_eq!(hash.iter().collect::<Vec<_>>(), [(Bytes::from("b"), Bytes::from("3"))]);
+
+        hash.insert(Bytes::from("c"), Bytes::from("a long value"), false);
+        assert!(matches!(hash.encoding, Encoding::Dict(_)));
+        assert_eq!((hash.len(), hash.get(b"b")), (2, Some(Bytes::from("3"))));
+
+        let mut hash = Hash::new(2, 8);
+        for field in ["a", "b", "c"] {
+            hash.insert(Bytes::from(field), Bytes::new(), false);
+        }
+        assert!(matches!(hash.encoding, Encoding::Dict(_)));
+        assert!(hash.contains(b"c") && !hash.contains(b"d"));
+    }
+
+    #[test]
+    fn fields_expire_soonest_first() {
+        let mut hash = Hash::new(128, 64);
+        for field in ["a", "b", "c"] {
+            hash.insert(Bytes::from(field), Bytes::new(), false);
+        }
+        hash.set_expire(Bytes::from("a"), 30);
+        hash.set_expire(Bytes::from("b"), 10);
+        hash.set_expire(Bytes::from("b"), 20);
+        hash.set_expire(Bytes::from("x"), 10);
+        assert_eq!((hash.min_expire(), hash.get_expire(b"x")), (Some(20), None));
+
+        // Overwriting a field discards its time to live, unless asked not to.
+        hash.insert(Bytes::from("a"), Bytes::from("1"), true);
+        assert_eq!(hash.get_expire(b"a"), Some(30));
+        hash.insert(Bytes::from("b"), Bytes::from("2"), false);
+        assert_eq!(hash.min_expire(), Some(30));
+
+        assert_eq!(hash.expire_fields(29, usize::MAX), 0);
+        assert_eq!(hash.expire_fields(30, usize::MAX), 1);
+        assert_eq!((hash.len(), hash.min_expire()), (2, None));
+    }
+}
@@ -0,0 +1,160 @@
+use std::iter;
+use crate::util::random;
+
+/// Sorted set of integers packed in a single buffer, after Redis'
+/// `intset.c`.
+///
+/// Every integer takes the same number of bytes, the width of the largest
+/// in magnitude: 2, 4 or 8. Adding an integer that does not fit upgrades the
+/// whole set to the next width, which it keeps once the integer is removed.
+/// Lookups are binary searches, insertions and removals shift the tail.
+#[derive(Debug, Clone)]
+pub(crate) struct Intset {
+    /// Bytes per integer.
+    width: usize,
+    /// The integers in ascending order, little endian.
+    data: Vec<u8>,
+}
+
+impl Intset {
+    pub(crate) fn new() -> Self {
+        Self { width: 2, data: Vec::new() }
+    }
+
+    /// Number of integers.
+    pub(crate) fn len(&self) -> usize {
+        self.data.len() / self.width
+    }
+
+    /// Width needed to store `value`, Redis' `_intsetValueEncoding`.
+    fn value_width(value: i64) -> usize {
+        if i16::try_from(value).is_ok() {
+            2
+        } else if i32::try_from(value).is_ok() {
+            4
+        } else {
+            8
+        }
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<i64> {
+        let bytes = self.data.get(index * self.width..(index + 1) * self.width)?;
+        Some(match self.width {
+            2 => i16::from_le_bytes(bytes.try_into().expect("2 bytes")) as i64,
+            4 => i32::from_le_bytes(bytes.try_into().expect("4 bytes")) as i64,
+            _ => i64::from_le_bytes(bytes.try_into().expect("8 bytes")),
+        })
+    }
+
+    fn encode(value: i64, width: usize) -> impl Iterator<Item = u8> {
+        value.to_le_bytes().into_iter().take(width)
+    }
+
+    /// Index of `value`, or the index it would be inserted at.
+    fn search(&self, value: i64) -> Result<usize, usize> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let current = self.get(mid).expect("index within bounds");
+            if current == value {
+                return Ok(mid);
+            } else if current < value {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Err(low)
+    }
+
+    pub(crate) fn contains(&self, value: i64) -> bool {
+        Self::value_width(value) <= self.width && self.search(value).is_ok()
+    }
+
+    /// Add `value`. Returns `false` if it was already there.
+    pub(crate) fn insert(&mut self, value: i64) -> bool {
+        let width = Self::value_width(value);
+        if width > self.width {
+            // Out of the range of every integer of the set: it goes first if
+            // negative, last otherwise.
+            let mut data = Vec::with_capacity((self.len() + 1) * width);
+            if value < 0 {
+                data.extend(Self::encode(value, width));
+            }
+            data.extend(self.iter().flat_map(|n| Self::encode(n, width)));
+            if value >= 0 {
+                data.extend(Self::encode(value, width));
+            }
+            self.data = data;
+            self.width = width;
+            return true;
+        }
+        match self.search(value) {
+            Ok(_) => false,
+            Err(index) => {
+                let offset = index * self.width;
+                self.data.splice(offset..offset, Self::encode(value, self.width));
+                true
+            }
+        }
+    }
+
+    /// Remove `value`. Returns whether it was there.
+    pub(crate) fn remove(&mut self, value: i64) -> bool {
+        if Self::value_width(value) > self.width {
+            return false;
+        }
+        match self.search(value) {
+            Ok(index) => {
+                self.data.drain(index * self.width..(index + 1) * self.width);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub(crate) fn min(&self) -> Option<i64> {
+        self.get(0)
+    }
+
+    pub(crate) fn max(&self) -> Option<i64> {
+        self.get(self.len().checked_sub(1)?)
+    }
+
+    pub(crate) fn random(&self) -> Option<i64> {
+        self.get(random() as usize % self.len().max(1))
+    }
+
+    /// The integers in ascending order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = i64> + '_ {
+        let mut index = 0;
+        iter::from_fn(move || {
+            let value = self.get(index)?;
+            index += 1;
+            Some(value)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorted_and_upgraded() {
+        let mut intset = Intset::new();
+        assert!(intset.insert(5) && intset.insert(-3) && intset.insert(1));
+        assert!(!intset.insert(5));
+        assert_eq!((intset.width, intset.iter().collect::<Vec<_>>()), (2, vec![-3, 1, 5]));
+
+        assert!(intset.insert(70000));
+        assert_eq!((intset.width, intset.data.len()), (4, 16));
+        assert!(intset.insert(i64::MIN));
+        assert_eq!(intset.iter().collect::<Vec<_>>(), [i64::MIN, -3, 1, 5, 70000]);
+        assert_eq!((intset.min(), intset.max()), (Some(i64::MIN), Some(70000)));
+
+        assert!(intset.remove(i64::MIN) && !intset.remove(i64::MIN) && !intset.remove(2));
+        assert_eq!(intset.width, 8);
+        assert!(intset.contains(70000) && !intset.contains(70001));
+    }
+}
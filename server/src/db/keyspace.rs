@@ -0,0 +1,362 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
+use ahash::AHashMap;
+use bytes::Bytes;
+use crate::db::dict::Dict;
+use crate::db::object::RedisObject;
+use crate::util::mstime;
+
+/// A logical database, Redis' `redisDb`.
+///
+/// Keys live in `dict`. Keys with a time to live also have an entry in
+/// `expires`, holding the UNIX time in milliseconds at which they expire.
+/// Hashes with fields having a time to live are ordered in `hexpires`.
+#[derive(Debug)]
+pub(crate) struct Db {
+    pub(crate) id: usize,
+    dict: Dict<Bytes, RedisObject>,
+    expires: Dict<Bytes, i64>,
+    // Where the active expire cycle resumes scanning `expires`.
+    expires_cursor: usize,
+    /// Estimate of the time to live of the keys, in milliseconds, kept by the
+    /// active expire cycle.
+    pub(crate) avg_ttl: i64,
+    /// Keys deleted because their time to live elapsed.
+    pub(crate) expired_keys: u64,
+    // Hashes with fields having a time to live, by the time the next one
+    // expires at, Redis' `hexpires`. An entry may be sooner than the hash's
+    // next field, or stale once the key is gone, and is checked again when
+    // its time comes; but every such hash has an entry no later than it.
+    hexpires: BTreeSet<(i64, Bytes)>,
+    /// Hash fields deleted because their time to live elapsed.
+    pub(crate) expired_fields: u64,
+    // Ids of the clients blocked on each key, in the order they blocked.
+    blocking_keys: AHashMap<Bytes, VecDeque<usize>>,
+    // Keys with blocked clients that were set since the blocked clients were
+    // last served, in the order they were set.
+    ready_keys: Vec<Bytes>,
+}
+
+/// Keys visited by one step of the active expire cycle.
+#[derive(Debug, Default)]
+pub(crate) struct ExpireSample {
+    pub(crate) sampled: usize,
+    pub(crate) expired: usize,
+    /// Sum of the time to live left of the keys not expired, and their count.
+    pub(crate) ttl_sum: i64,
+    pub(crate) ttl_samples: i64,
+}
+
+impl Db {
+    fn new(id: usize) -> Self {
+        Self {
+            id,
+            dict: Dict::new(),
+            expires: Dict::new(),
+            expires_cursor: 0,
+            avg_ttl: 0,
+            expired_keys: 0,
+            hexpires: BTreeSet::new(),
+            expired_fields: 0,
+            blocking_keys: AHashMap::new(),
+            ready_keys: vec![],
+        }
+    }
+
+    /// Number of keys, expired ones not yet reclaimed included.
+    pub(crate) fn len(&self) -> usize {
+        self.dict.len()
+    }
+
+    /// Number of keys with a time to live.
+    pub(crate) fn expires_len(&self) -> usize {
+        self.expires.len()
+    }
+
+    /// Look `key` up, deleting it first if it has expired, as Redis'
+    /// `lookupKey` does.
+    pub(crate) fn lookup_key(&mut self, key: &[u8]) -> Option<&mut RedisObject> {
+        self.expire_if_needed(key);
+        self.dict.get_mut(key)
+    }
+
+    /// Set `key` to `value`, creating it if needed. The time to live of an
+    /// existing key is discarded unless `keep_ttl` is set, Redis' `setKey`.
+    pub(crate) fn set_key(&mut self, key: Bytes, value: RedisObject, keep_ttl: bool) {
+        if !keep_ttl {
+            self.expires.remove(&key[..]);
+        }
+        // A hash renamed, moved or copied keeps the time to live of its
+        // fields.
+        if let Some(when) = match &value {
+            RedisObject::Hash(hash) => hash.min_expire(),
+            _ => None,
+        } {
+            self.add_hash_expire(key.clone(), when);
+        }
+        self.signal_key_as_ready(&key);
+        self.dict.insert(key, value);
+    }
+
+    /// Delete `key` along with its time to live. Returns `true` if it existed.
+    pub(crate) fn delete_key(&mut self, key: &[u8]) -> bool {
+        self.expires.remove(key);
+        self.dict.remove(key).is_some()
+    }
+
+    /// Remove `key` and return its value and expire time, to add it back
+    /// under another name or in another database.
+    pub(crate) fn take_key(&mut self, key: &[u8]) -> Option<(RedisObject, Option<i64>)> {
+        let (_, value) = self.dict.remove(key)?;
+        let expire = self.expires.remove(key).map(|(_, when)| when);
+        Some((value, expire))
+    }
+
+    /// The keys not expired yet, in no particular order.
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &Bytes> + '_ {
+        let now = mstime();
+        self.dict.iter().map(|(key, _)| key).filter(move |key| self.get_expire(key).is_none_or(|when| when > now))
+    }
+
+    /// Visit the keys of a bucket with their value, returning the cursor of
+    /// the next call. See `Dict::scan`.
+    pub(crate) fn scan<F>(&self, cursor: usize, f: F) -> usize
+    where
+        F: FnMut(&Bytes, &RedisObject),
+    {
+        self.dict.scan(cursor, f)
+    }
+
+    /// A random key not expired, Redis' `dbRandomKey`. Expired keys picked
+    /// on the way are deleted.
+    pub(crate) fn random_key(&mut self) -> Option<Bytes> {
+        loop {
+            let key = self.dict.random_entry()?.0.clone();
+            if !self.expire_if_needed(&key) {
+                return Some(key);
+            }
+        }
+    }
+
+    /// UNIX time in milliseconds at which `key` expires, if it has a time to
+    /// live.
+    pub(crate) fn get_expire(&self, key: &[u8]) -> Option<i64> {
+        self.expires.get(key).copied()
+    }
+
+    /// Make an existing `key` expire at `when`, a UNIX time in milliseconds.
+    pub(crate) fn set_expire(&mut self, key: Bytes, when: i64) {
+        if self.dict.contains_key(&key[..]) {
+            self.expires.insert(key, when);
+        }
+    }
+
+    /// Remove the time to live of `key`. Returns `true` if it had one.
+    pub(crate) fn remove_expire(&mut self, key: &[u8]) -> bool {
+        self.expires.remove(key).is_some()
+    }
+
+    /// Delete `key` if its time to live has elapsed, or the fields of a hash
+    /// whose time to live has. Returns `true` if the key was deleted.
+    fn expire_if_needed(&mut self, key: &[u8]) -> bool {
+        let now = mstime();
+        if self.get_expire(key).is_some_and(|when| when <= now) {
+            self.expired_keys += 1;
+            return self.delete_key(key);
+        }
+
+        // Unless some hash has an expired field, there is no need to look.
+        if self.hexpires.first().is_none_or(|(when, _)| *when > now) {
+            return false;
+        }
+        let Some(RedisObject::Hash(hash)) = self.dict.get_mut(key) else {
+            return false;
+        };
+        let expired = hash.expire_fields(now, usize::MAX);
+        self.expired_fields += expired as u64;
+        expired > 0 && hash.is_empty() && self.delete_key(key)
+    }
+
+    /// Note that a field of the hash at `key` expires at `when`.
+    pub(crate) fn add_hash_expire(&mut self, key: Bytes, when: i64) {
+        self.hexpires.insert((when, key));
+    }
+
+    /// Delete up to `limit` hash fields expired at `now`, the soonest first,
+    /// and the hashes left empty. Returns how many fields were deleted.
+    pub(crate) fn expire_hash_fields(&mut self, now: i64, limit: usize) -> usize {
+        let mut expired = 0;
+        while expired < limit {
+            if self.hexpires.first().is_none_or(|(when, _)| *when > now) {
+                break;
+            }
+            let (_, key) = self.hexpires.pop_first().expect("hexpires is not empty");
+            let Some(RedisObject::Hash(hash)) = self.dict.get_mut(&key[..]) else {
+                continue;
+            };
+            expired += hash.expire_fields(now, limit - expired);
+            if hash.is_empty() {
+                self.delete_key(&key);
+            } else if let Some(when) = hash.min_expire() {
+                self.hexpires.insert((when, key));
+            }
+        }
+        self.expired_fields += expired as u64;
+        expired
+    }
+
+    /// Visit about `num` keys with a time to live, resuming where the
+    /// previous call stopped, and delete the ones expired at `now`.
+    ///
+    /// At most `num * 20` buckets are visited, so a sparse table does not
+    /// make the step long.
+    pub(crate) fn sample_expires(&mut self, num: usize, now: i64) -> ExpireSample {
+        let mut sample = ExpireSample::default();
+        let mut expired = vec![];
+        let mut checked_buckets = 0;
+
+        while sample.sampled < num && checked_buckets < num * 20 {
+            self.expires_cursor = self.expires.scan(self.expires_cursor, |key, when| {
+                sample.sampled += 1;
+                if *when <= now {
+                    expired.push(key.clone());
+                } else {
+                    sample.ttl_sum += when - now;
+                    sample.ttl_samples += 1;
+                }
+            });
+            checked_buckets += 1;
+            if self.expires_cursor == 0 {
+                break;
+            }
+        }
+
+        for key in expired {
+            self.delete_key(&key);
+            self.expired_keys += 1;
+            sample.expired += 1;
+        }
+        sample
+    }
+
+    /// Queue `client_id` among the clients blocked on `key`.
+    pub(crate) fn block_client(&mut self, key: Bytes, client_id: usize) {
+        self.blocking_keys.entry(key).or_default().push_back(client_id);
+    }
+
+    /// Remove `client_id` from the clients blocked on `key`.
+    pub(crate) fn unblock_client(&mut self, key: &[u8], client_id: usize) {
+        if let Some(clients) = self.blocking_keys.get_mut(key) {
+            clients.retain(|id| *id != client_id);
+            if clients.is_empty() {
+                self.blocking_keys.remove(key);
+            }
+        }
+    }
+
+    /// Note that `key` may now serve the clients blocked on it, Redis'
+    /// `signalKeyAsReady`.
+    fn signal_key_as_ready(&mut self, key: &Bytes) {
+        if self.blocking_keys.contains_key(key) && !self.ready_keys.contains(key) {
+            self.ready_keys.push(key.clone());
+        }
+    }
+
+    /// Take the keys signaled as ready, each with the clients blocked on it
+    /// at this point, first blocked first.
+    pub(crate) fn take_ready_keys(&mut self) -> Vec<(Bytes, Vec<usize>)> {
+        mem::take(&mut self.ready_keys)
+            .into_iter()
+            .filter_map(|key| {
+                let clients = self.blocking_keys.get(&key)?.iter().copied().collect();
+                Some((key, clients))
+            })
+            .collect()
+    }
+
+    /// Number of keys clients are blocked on.
+    pub(crate) fn blocking_keys_len(&self) -> usize {
+        self.blocking_keys.len()
+    }
+
+    /// Ids of the blocked clients, once per key they are blocked on.
+    pub(crate) fn blocked_clients(&self) -> impl Iterator<Item = usize> + '_ {
+        self.blocking_keys.values().flatten().copied()
+    }
+
+    /// Remove every key, returning how many there were.
+    pub(crate) fn flush(&mut self) -> usize {
+        self.expires.clear();
+        self.expires_cursor = 0;
+        self.hexpires.clear();
+        self.avg_ttl = 0;
+        self.dict.clear()
+    }
+
+    /// Shrink the tables if they are mostly empty.
+    pub(crate) fn try_resize(&mut self) {
+        self.dict.try_resize();
+        self.expires.try_resize();
+    }
+
+    /// Spend about `ms` milliseconds rehashing the main table, or the expires
+    /// one if the main table is not rehashing. Returns `false` if neither
+    /// was, Redis' `incrementallyRehash`.
+    pub(crate) fn rehash_ms(&mut self, ms: u64) -> bool {
+        if self.dict.is_rehashing() {
+            self.dict.rehash_ms(ms);
+            true
+        } else if self.expires.is_rehashing() {
+            self.expires.rehash_ms(ms);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// The logical databases of the server.
+///
+/// Like the other shared state of `RedisServer`, the keyspace is a handle:
+/// clones share the same databases.
+#[derive(Debug, Clone)]
+pub(crate) struct Keyspace {
+    dbs: Arc<Mutex<Vec<Db>>>,
+}
+
+impl Keyspace {
+    pub(crate) fn new(databases: usize) -> Self {
+        Self { dbs: Arc::new(Mutex::new((0..databases).map(Db::new).collect())) }
+    }
+
+    /// Lock the databases, indexed by their id.
+    pub(crate) fn lock(&self) -> MutexGuard<'_, Vec<Db>> {
+        self.dbs.lock().unwrap()
+    }
+}
+
+/// Swap the contents of two databases, Redis' `dbSwapDatabases`. The ids stay
+/// in place, so clients that selected either one see the other's keys.
+pub(crate) fn swap_databases(dbs: &mut [Db], id1: usize, id2: usize) {
+    if id1 == id2 {
+        return;
+    }
+    let (low, high) = (id1.min(id2), id1.max(id2));
+    let (head, tail) = dbs.split_at_mut(high);
+    let (db1, db2) = (&mut head[low], &mut tail[0]);
+    mem::swap(&mut db1.dict, &mut db2.dict);
+    mem::swap(&mut db1.expires, &mut db2.expires);
+    mem::swap(&mut db1.expires_cursor, &mut db2.expires_cursor);
+    mem::swap(&mut db1.hexpires, &mut db2.hexpires);
+    mem::swap(&mut db1.avg_ttl, &mut db2.avg_ttl);
+
+    // Clients stay blocked on the database they selected, which may now hold
+    // the keys they wait for, Redis' `scanDatabaseForReadyKeys`.
+    for db in [db1, db2] {
+        let ready: Vec<Bytes> = db.blocking_keys.keys().filter(|key| db.dict.contains_key(&key[..])).cloned().collect();
+        for key in ready {
+            db.signal_key_as_ready(&key);
+        }
+    }
+}
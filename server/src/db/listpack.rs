@@ -0,0 +1,403 @@
+use std::fmt;
+use bytes::Bytes;
+use crate::util::string2ll;
+
+// Encodings of an entry, Redis' `LP_ENCODING_*`. Small integers and short
+// strings keep their length in the first byte.
+const LP_ENCODING_7BIT_UINT_MASK: u8 = 0x80;
+const LP_ENCODING_6BIT_STR: u8 = 0x80;
+const LP_ENCODING_6BIT_STR_MASK: u8 = 0xC0;
+const LP_ENCODING_13BIT_INT: u8 = 0xC0;
+const LP_ENCODING_13BIT_INT_MASK: u8 = 0xE0;
+const LP_ENCODING_12BIT_STR: u8 = 0xE0;
+const LP_ENCODING_12BIT_STR_MASK: u8 = 0xF0;
+const LP_ENCODING_32BIT_STR: u8 = 0xF0;
+const LP_ENCODING_16BIT_INT: u8 = 0xF1;
+const LP_ENCODING_24BIT_INT: u8 = 0xF2;
+const LP_ENCODING_32BIT_INT: u8 = 0xF3;
+const LP_ENCODING_64BIT_INT: u8 = 0xF4;
+
+/// An entry of a listpack: strings that are the canonical representation of
+/// a 64 bit integer are stored as the integer.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ListpackEntry<'a> {
+    Int(i64),
+    Str(&'a [u8]),
+}
+
+impl ListpackEntry<'_> {
+    pub(crate) fn to_bytes(self) -> Bytes {
+        match self {
+            ListpackEntry::Int(n) => Bytes::from(n.to_string()),
+            ListpackEntry::Str(s) => Bytes::copy_from_slice(s),
+        }
+    }
+
+    /// Whether the entry is `value`, compared as strings.
+    pub(crate) fn eq_bytes(self, value: &[u8]) -> bool {
+        match self {
+            ListpackEntry::Int(n) => string2ll(value) == Some(n),
+            ListpackEntry::Str(s) => s == value,
+        }
+    }
+}
+
+/// Sequence of strings packed in a single buffer, after Redis' `listpack.c`.
+///
+/// Every entry is its encoding, its data and the length of both, written
+/// so it can be read backwards. The entries can thus be walked from either
+/// end, at the cost of walking to reach one by index. Small integers take
+/// a byte and short strings one byte on top of their data, against a
+/// pointer and an allocation each in a `Vec<Bytes>`.
+#[derive(Clone, Default)]
+pub(crate) struct Listpack {
+    data: Vec<u8>,
+    len: usize,
+}
+
+impl fmt::Debug for Listpack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Listpack").field("len", &self.len).field("bytes", &self.data.len()).finish()
+    }
+}
+
+impl Listpack {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of entries.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Size of the entries, in bytes.
+    pub(crate) fn bytes(&self) -> usize {
+        self.data.len()
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<ListpackEntry<'_>> {
+        (index < self.len).then(|| decode(&self.data, self.offset_of(index)).0)
+    }
+
+    /// Insert `value` before the entry at `index`, at the end if `index` is
+    /// the length.
+    pub(crate) fn insert(&mut self, index: usize, value: &[u8]) {
+        let offset = self.offset_of(index);
+        self.data.splice(offset..offset, encode(value));
+        self.len += 1;
+    }
+
+    pub(crate) fn push_front(&mut self, value: &[u8]) {
+        self.insert(0, value);
+    }
+
+    pub(crate) fn push_back(&mut self, value: &[u8]) {
+        self.insert(self.len, value);
+    }
+
+    /// Replace the entry at `index` with `value`.
+    pub(crate) fn replace(&mut self, index: usize, value: &[u8]) {
+        let offset = self.offset_of(index);
+        let end = next_offset(&self.data, offset);
+        self.data.splice(offset..end, encode(value));
+    }
+
+    /// Remove `count` entries starting at `index`.
+    pub(crate) fn remove_range(&mut self, index: usize, count: usize) {
+        let offset = self.offset_of(index);
+        let mut end = offset;
+        for _ in 0..count {
+            end = next_offset(&self.data, end);
+        }
+        self.data.drain(offset..end);
+        self.len -= count;
+    }
+
+    pub(crate) fn remove(&mut self, index: usize) {
+        self.remove_range(index, 1);
+    }
+
+    pub(crate) fn pop_front(&mut self) -> Option<Bytes> {
+        let value = self.get(0)?.to_bytes();
+        self.remove(0);
+        Some(value)
+    }
+
+    pub(crate) fn pop_back(&mut self) -> Option<Bytes> {
+        let value = self.get(self.len.checked_sub(1)?)?.to_bytes();
+        self.remove(self.len - 1);
+        Some(value)
+    }
+
+    /// Remove up to `limit` entries equal to `value`, the last ones first if
+    /// `from_back` is set. Returns the number of entries removed.
+    pub(crate) fn remove_matching(&mut self, value: &[u8], limit: usize, from_back: bool) -> usize {
+        let mut ranges = vec![];
+        if from_back {
+            let mut end = self.data.len();
+            while end > 0 && ranges.len() < limit {
+                let offset = prev_offset(&self.data, end);
+                if decode(&self.data, offset).0.eq_bytes(value) {
+                    ranges.push(offset..end);
+                }
+                end = offset;
+            }
+            ranges.reverse();
+        } else {
+            let mut offset = 0;
+            while offset < self.data.len() && ranges.len() < limit {
+                let end = next_offset(&self.data, offset);
+                if decode(&self.data, offset).0.eq_bytes(value) {
+                    ranges.push(offset..end);
+                }
+                offset = end;
+            }
+        }
+
+        if !ranges.is_empty() {
+            let mut data = Vec::with_capacity(self.data.len());
+            let mut kept_from = 0;
+            for range in &ranges {
+                data.extend_from_slice(&self.data[kept_from..range.start]);
+                kept_from = range.end;
+            }
+            data.extend_from_slice(&self.data[kept_from..]);
+            self.data = data;
+            self.len -= ranges.len();
+        }
+        ranges.len()
+    }
+
+    /// Split the entries in two at `index`, returning the ones from `index`
+    /// on.
+    pub(crate) fn split_off(&mut self, index: usize) -> Listpack {
+        let offset = self.offset_of(index);
+        let tail = Listpack { data: self.data.split_off(offset), len: self.len - index };
+        self.len = index;
+        tail
+    }
+
+    /// Entries from first to last, or backwards with `rev`.
+    pub(crate) fn iter(&self) -> Iter<'_> {
+        Iter { data: &self.data, front: 0, back: self.data.len(), remaining: self.len }
+    }
+
+    /// Offset of the entry at `index`, the end of the data if `index` is the
+    /// length. The walk starts from the closer end.
+    fn offset_of(&self, index: usize) -> usize {
+        if index <= self.len / 2 {
+            (0..index).fold(0, |offset, _| next_offset(&self.data, offset))
+        } else {
+            (index..self.len).fold(self.data.len(), |end, _| prev_offset(&self.data, end))
+        }
+    }
+}
+
+/// Iterator over the entries of a `Listpack`.
+pub(crate) struct Iter<'a> {
+    data: &'a [u8],
+    // Offset of the next entry from the front, and end of the next one from
+    // the back.
+    front: usize,
+    back: usize,
+    remaining: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = ListpackEntry<'a>;
+
+    fn next(&mut self) -> Option<ListpackEntry<'a>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let (entry, _) = decode(self.data, self.front);
+        self.front = next_offset(self.data, self.front);
+        Some(entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        self.back = prev_offset(self.data, self.back);
+        Some(decode(self.data, self.back).0)
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+/// Encode `value` as an entry, as an integer if it is the canonical
+/// representation of one.
+fn encode(value: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(value.len() + 10);
+    match string2ll(value) {
+        Some(n) => encode_int(n, &mut buf),
+        None => encode_str(value, &mut buf),
+    }
+    encode_backlen(buf.len(), &mut buf);
+    buf
+}
+
+fn encode_int(n: i64, buf: &mut Vec<u8>) {
+    if (0..=127).contains(&n) {
+        buf.push(n as u8);
+    } else if (-4096..=4095).contains(&n) {
+        let n = if n < 0 { (1 << 13) + n } else { n } as u16;
+        buf.extend_from_slice(&[(n >> 8) as u8 | LP_ENCODING_13BIT_INT, n as u8]);
+    } else if let Ok(n) = i16::try_from(n) {
+        buf.push(LP_ENCODING_16BIT_INT);
+        buf.extend_from_slice(&n.to_le_bytes());
+    } else if (-(1 << 23)..1 << 23).contains(&n) {
+        buf.push(LP_ENCODING_24BIT_INT);
+        buf.extend_from_slice(&(n as i32).to_le_bytes()[..3]);
+    } else if let Ok(n) = i32::try_from(n) {
+        buf.push(LP_ENCODING_32BIT_INT);
+        buf.extend_from_slice(&n.to_le_bytes());
+    } else {
+        buf.push(LP_ENCODING_64BIT_INT);
+        buf.extend_from_slice(&n.to_le_bytes());
+    }
+}
+
+fn encode_str(s: &[u8], buf: &mut Vec<u8>) {
+    let len = s.len();
+    if len < 64 {
+        buf.push(LP_ENCODING_6BIT_STR | len as u8);
+    } else if len < 4096 {
+        buf.extend_from_slice(&[LP_ENCODING_12BIT_STR | (len >> 8) as u8, len as u8]);
+    } else {
+        buf.push(LP_ENCODING_32BIT_STR);
+        buf.extend_from_slice(&(len as u32).to_le_bytes());
+    }
+    buf.extend_from_slice(s);
+}
+
+/// Append the length `len` of an entry, 7 bits per byte, most significant
+/// first. Every byte but the first has its high bit set, so the length can
+/// be read from its last byte backwards.
+fn encode_backlen(len: usize, buf: &mut Vec<u8>) {
+    let bytes = backlen_size(len);
+    for i in (0..bytes).rev() {
+        let byte = ((len >> (7 * i)) & 127) as u8;
+        buf.push(if i == bytes - 1 { byte } else { byte | 128 });
+    }
+}
+
+fn backlen_size(len: usize) -> usize {
+    match len {
+        0..=127 => 1,
+        128..16383 => 2,
+        16383..2097151 => 3,
+        2097151..268435455 => 4,
+        _ => 5,
+    }
+}
+
+/// Decode the entry at `offset`, with the size of its encoding and data.
+fn decode(data: &[u8], offset: usize) -> (ListpackEntry<'_>, usize) {
+    let byte = data[offset];
+    let str_at = |start: usize, len: usize| ListpackEntry::Str(&data[offset + start..offset + start + len]);
+    let int_at = |len: usize| {
+        let mut bytes = [0; 8];
+        bytes[..len].copy_from_slice(&data[offset + 1..offset + 1 + len]);
+        // Sign-extend from the top byte read.
+        let shift = 64 - 8 * len as u32;
+        ListpackEntry::Int(i64::from_le_bytes(bytes) << shift >> shift)
+    };
+
+    if byte & LP_ENCODING_7BIT_UINT_MASK == 0 {
+        (ListpackEntry::Int(byte as i64), 1)
+    } else if byte & LP_ENCODING_6BIT_STR_MASK == LP_ENCODING_6BIT_STR {
+        let len = (byte & 0x3F) as usize;
+        (str_at(1, len), 1 + len)
+    } else if byte & LP_ENCODING_13BIT_INT_MASK == LP_ENCODING_13BIT_INT {
+        let n = ((byte & 0x1F) as i64) << 8 | data[offset + 1] as i64;
+        (ListpackEntry::Int(if n >= 1 << 12 { n - (1 << 13) } else { n }), 2)
+    } else if byte & LP_ENCODING_12BIT_STR_MASK == LP_ENCODING_12BIT_STR {
+        let len = ((byte & 0x0F) as usize) << 8 | data[offset + 1] as usize;
+        (str_at(2, len), 2 + len)
+    } else {
+        match byte {
+            LP_ENCODING_32BIT_STR => {
+                let len = u32::from_le_bytes(data[offset + 1..offset + 5].try_into().unwrap()) as usize;
+                (str_at(5, len), 5 + len)
+            }
+            LP_ENCODING_16BIT_INT => (int_at(2), 3),
+            LP_ENCODING_24BIT_INT => (int_at(3), 4),
+            LP_ENCODING_32BIT_INT => (int_at(4), 5),
+            LP_ENCODING_64BIT_INT => (int_at(8), 9),
+            _ => unreachable!("invalid listpack encoding {:#x}", byte),
+        }
+    }
+}
+
+/// Offset of the entry following the one at `offset`.
+fn next_offset(data: &[u8], offset: usize) -> usize {
+    let (_, len) = decode(data, offset);
+    offset + len + backlen_size(len)
+}
+
+/// Offset of the entry ending right before `end`, from its back length.
+fn prev_offset(data: &[u8], end: usize) -> usize {
+    let mut p = end - 1;
+    let mut len = 0;
+    let mut shift = 0;
+    loop {
+        len |= ((data[p] & 127) as usize) << shift;
+        if data[p] & 128 == 0 {
+            break;
+        }
+        shift += 7;
+        p -= 1;
+    }
+    p - len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_round_trip_both_ways() {
+        let long = "x".repeat(5000);
+        let values = ["0", "127", "-1", "4095", "-4096", "30000", "-8000000", "2000000000", "-9223372036854775808", "012", "", "hello", &long];
+        let mut lp = Listpack::new();
+        for value in values {
+            lp.push_back(value.as_bytes());
+        }
+        let forward: Vec<Bytes> = lp.iter().map(ListpackEntry::to_bytes).collect();
+        let backward: Vec<Bytes> = lp.iter().rev().map(ListpackEntry::to_bytes).collect();
+        assert_eq!(forward, values);
+        assert!(backward.iter().rev().eq(forward.iter()));
+        assert!(matches!(lp.get(9), Some(ListpackEntry::Str(b"012"))));
+    }
+
+    #[test]
+    fn positional_updates() {
+        let mut lp = Listpack::new();
+        for value in ["a", "b", "a", "c", "a"] {
+            lp.push_back(value.as_bytes());
+        }
+        lp.insert(1, b"1");
+        lp.replace(0, b"z");
+        assert_eq!(lp.remove_matching(b"a", 1, true), 1);
+        assert_eq!(lp.iter().map(ListpackEntry::to_bytes).collect::<Vec<_>>(), ["z", "1", "b", "a", "c"]);
+        let tail = lp.split_off(3);
+        assert_eq!((lp.len(), tail.len()), (3, 2));
+        assert_eq!(lp.pop_back().unwrap(), "b");
+        assert_eq!(lp.pop_front().unwrap(), "z");
+    }
+}
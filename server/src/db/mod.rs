@@ -0,0 +1,10 @@
+pub(crate) mod dict;
+pub(crate) mod expire;
+pub(crate) mod hash;
+pub(crate) mod intset;
+pub(crate) mod keyspace;
+pub(crate) mod listpack;
+pub(crate) mod object;
+pub(crate) mod quicklist;
+pub(crate) mod set;
+pub(crate) mod zset;
@@ -0,0 +1,102 @@
+use bytes::Bytes;
+use crate::db::hash::Hash;
+use crate::db::quicklist::Quicklist;
+use crate::db::set::Set;
+use crate::db::zset::ZSet;
+use crate::util::string2ll;
+
+/// Value stored under a key, Redis' `robj`.
+///
+/// Every data type is a variant, holding the encoding currently used for it.
+#[derive(Debug, Clone)]
+pub(crate) enum RedisObject {
+    /// String kept as its bytes.
+    String(Bytes),
+    /// String modified in place by `APPEND` or `SETRANGE`, which Redis
+    /// makes modifiable by giving it its own allocation.
+    RawString(Bytes),
+    /// String representing a 64 bit signed integer, kept as the integer,
+    /// Redis' `int` encoding.
+    Int(i64),
+    /// List kept as a quicklist of listpacks.
+    List(Quicklist),
+    /// Hash, kept as a listpack while small.
+    Hash(Hash),
+    /// Set, kept as an intset or a listpack while small.
+    Set(Set),
+    /// Sorted set.
+    ZSet(ZSet),
+}
+
+impl RedisObject {
+    /// Create a string object, using the `int` encoding if the value is the
+    /// canonical representation of an integer, as Redis'
+    /// `tryObjectEncoding` does.
+    pub(crate) fn string(value: Bytes) -> RedisObject {
+        match string2ll(&value) {
+            Some(n) if value.len() <= 20 => RedisObject::Int(n),
+            _ => RedisObject::String(value),
+        }
+    }
+
+    /// Name of the type of the object, as replied by `TYPE`.
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            RedisObject::String(_) | RedisObject::RawString(_) | RedisObject::Int(_) => "string",
+            RedisObject::List(_) => "list",
+            RedisObject::Hash(_) => "hash",
+            RedisObject::Set(_) => "set",
+            RedisObject::ZSet(_) => "zset",
+        }
+    }
+
+    /// Name of the encoding of the object, as replied by `OBJECT ENCODING`.
+    /// Strings are told apart by length as Redis allocates them: up to 44
+    /// bytes along with their object, `embstr`, separately otherwise.
+    pub(crate) fn encoding_name(&self) -> &'static str {
+        match self {
+            RedisObject::String(value) if value.len() <= 44 => "embstr",
+            RedisObject::String(_) | RedisObject::RawString(_) => "raw",
+            RedisObject::Int(_) => "int",
+            RedisObject::List(list) if list.node_count() <= 1 => "listpack",
+            RedisObject::List(_) => "quicklist",
+            RedisObject::Hash(hash) => hash.encoding_name(),
+            RedisObject::Set(set) => set.encoding_name(),
+            RedisObject::ZSet(_) => "skiplist",
+        }
+    }
+
+    /// Value of a string object, `None` if the object is of another type.
+    pub(crate) fn string_value(&self) -> Option<Bytes> {
+        match self {
+            RedisObject::String(value) | RedisObject::RawString(value) => Some(value.clone()),
+            RedisObject::Int(n) => Some(Bytes::from(n.to_string())),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::TestClient;
+
+    #[test]
+    fn strings_changed_in_place_are_raw() {
+        let mut c = TestClient::new();
+        let encoding = |c: &mut TestClient, key: &str| c.call(&["OBJECT", "ENCODING", key]);
+        c.call(&["SET", "k", "a"]);
+        assert_eq!(encoding(&mut c, "k"), "$6\r\nembstr\r\n");
+        c.call(&["APPEND", "k", "b"]);
+        assert_eq!(encoding(&mut c, "k"), "$3\r\nraw\r\n");
+
+        c.call(&["APPEND", "n", "12"]);
+        assert_eq!(encoding(&mut c, "n"), "$3\r\nint\r\n");
+        c.call(&["SETRANGE", "n", "0", "3"]);
+        assert_eq!(encoding(&mut c, "n"), "$3\r\nraw\r\n");
+
+        c.call(&["SETRANGE", "r", "1", "x"]);
+        assert_eq!(encoding(&mut c, "r"), "$3\r\nraw\r\n");
+        c.call(&["SET", "r", "x"]);
+        assert_eq!(encoding(&mut c, "r"), "$6\r\nembstr\r\n");
+    }
+}
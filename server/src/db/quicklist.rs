@@ -0,0 +1,301 @@
+use std::collections::VecDeque;
+use bytes::Bytes;
+use crate::db::listpack::{Listpack, ListpackEntry};
+
+/// Largest node, in bytes, for a negative `list-max-listpack-size` of -1 to
+/// -5.
+const OPTIMIZATION_LEVEL: [usize; 5] = [4096, 8192, 16384, 32768, 65536];
+
+/// Largest node, in bytes, when `list-max-listpack-size` limits the entries
+/// per node instead.
+const SIZE_SAFETY_LIMIT: usize = 8192;
+
+/// Bytes an entry may take on top of its data: encoding and back length.
+const ENTRY_OVERHEAD: usize = 11;
+
+/// The value of a list, a sequence of listpack nodes, after Redis'
+/// `quicklist.c`.
+///
+/// Nodes hold entries up to a size set by `fill`, the
+/// `list-max-listpack-size` directive: positive, the number of entries;
+/// negative, a size in bytes from 4kb (-1) to 64kb (-5). Pushing or popping
+/// at either end touches one small node, while the entries of a long list
+/// stay packed.
+#[derive(Debug, Clone)]
+pub(crate) struct Quicklist {
+    nodes: VecDeque<Listpack>,
+    len: usize,
+    fill: i32,
+}
+
+impl Quicklist {
+    pub(crate) fn new(fill: i32) -> Self {
+        Self { nodes: VecDeque::new(), len: 0, fill }
+    }
+
+    /// Number of entries.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of listpack nodes.
+    pub(crate) fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn single(value: &[u8]) -> Listpack {
+        let mut node = Listpack::new();
+        node.push_back(value);
+        node
+    }
+
+    pub(crate) fn push_front(&mut self, value: &[u8]) {
+        match self.nodes.front_mut() {
+            Some(node) if allow_insert(self.fill, node, value.len()) => node.push_front(value),
+            _ => self.nodes.push_front(Self::single(value)),
+        }
+        self.len += 1;
+    }
+
+    pub(crate) fn push_back(&mut self, value: &[u8]) {
+        match self.nodes.back_mut() {
+            Some(node) if allow_insert(self.fill, node, value.len()) => node.push_back(value),
+            _ => self.nodes.push_back(Self::single(value)),
+        }
+        self.len += 1;
+    }
+
+    pub(crate) fn pop_front(&mut self) -> Option<Bytes> {
+        let node = self.nodes.front_mut()?;
+        let value = node.pop_front();
+        if node.is_empty() {
+            self.nodes.pop_front();
+        }
+        self.len -= 1;
+        value
+    }
+
+    pub(crate) fn pop_back(&mut self) -> Option<Bytes> {
+        let node = self.nodes.back_mut()?;
+        let value = node.pop_back();
+        if node.is_empty() {
+            self.nodes.pop_back();
+        }
+        self.len -= 1;
+        value
+    }
+
+    /// Node holding the entry at `index`, and the index of the entry in the
+    /// node. The walk starts from the closer end.
+    fn locate(&self, index: usize) -> Option<(usize, usize)> {
+        if index >= self.len {
+            return None;
+        }
+        if index < self.len / 2 {
+            let mut index = index;
+            for (i, node) in self.nodes.iter().enumerate() {
+                if index < node.len() {
+                    return Some((i, index));
+                }
+                index -= node.len();
+            }
+        } else {
+            let mut from_back = self.len - 1 - index;
+            for (i, node) in self.nodes.iter().enumerate().rev() {
+                if from_back < node.len() {
+                    return Some((i, node.len() - 1 - from_back));
+                }
+                from_back -= node.len();
+            }
+        }
+        None
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<Bytes> {
+        let (i, j) = self.locate(index)?;
+        self.nodes[i].get(j).map(ListpackEntry::to_bytes)
+    }
+
+    /// Replace the entry at `index`. Returns `false` if it is out of range.
+    pub(crate) fn set(&mut self, index: usize, value: &[u8]) -> bool {
+        let Some((i, j)) = self.locate(index) else {
+            return false;
+        };
+        self.nodes[i].replace(j, value);
+        // A larger entry may leave the node too big, halve it then. The
+        // number of entries is unchanged, only the size matters.
+        let node = &mut self.nodes[i];
+        if node.len() > 1 && node.bytes() > size_limit(self.fill) {
+            let tail = node.split_off(node.len() / 2);
+            self.nodes.insert(i + 1, tail);
+        }
+        true
+    }
+
+    /// Insert `value` before the entry at `index`, at the end if `index` is
+    /// the length.
+    pub(crate) fn insert(&mut self, index: usize, value: &[u8]) {
+        if index == 0 {
+            return self.push_front(value);
+        }
+        if index >= self.len {
+            return self.push_back(value);
+        }
+        let Some((i, j)) = self.locate(index) else {
+            return;
+        };
+        if allow_insert(self.fill, &self.nodes[i], value.len()) {
+            self.nodes[i].insert(j, value);
+        } else if j == 0 && allow_insert(self.fill, &self.nodes[i - 1], value.len()) {
+            // At the boundary of two nodes, the previous one may have room.
+            self.nodes[i - 1].push_back(value);
+        } else {
+            // Split the full node around the new entry.
+            let tail = self.nodes[i].split_off(j);
+            self.nodes.insert(i + 1, Self::single(value));
+            if !tail.is_empty() {
+                self.nodes.insert(i + 2, tail);
+            }
+            if self.nodes[i].is_empty() {
+                self.nodes.remove(i);
+            }
+        }
+        self.len += 1;
+    }
+
+    /// Remove `count` entries starting at `index`.
+    pub(crate) fn remove_range(&mut self, index: usize, count: usize) {
+        let mut count = count.min(self.len.saturating_sub(index));
+        while count > 0 {
+            let Some((i, j)) = self.locate(index) else {
+                break;
+            };
+            let removed = count.min(self.nodes[i].len() - j);
+            if removed == self.nodes[i].len() {
+                self.nodes.remove(i);
+            } else {
+                self.nodes[i].remove_range(j, removed);
+            }
+            self.len -= removed;
+            count -= removed;
+        }
+    }
+
+    /// Remove entries equal to `value`: the first `count` ones if positive,
+    /// the last `-count` ones if negative, all of them if zero. Returns the
+    /// number of entries removed.
+    pub(crate) fn remove_matching(&mut self, value: &[u8], count: i64) -> usize {
+        let from_back = count < 0;
+        let mut left = if count == 0 { usize::MAX } else { count.unsigned_abs() as usize };
+        let mut removed = 0;
+
+        let mut order: Vec<usize> = (0..self.nodes.len()).collect();
+        if from_back {
+            order.reverse();
+        }
+        for i in order {
+            if left == 0 {
+                break;
+            }
+            let n = self.nodes[i].remove_matching(value, left, from_back);
+            left -= n;
+            removed += n;
+        }
+
+        self.nodes.retain(|node| !node.is_empty());
+        self.len -= removed;
+        removed
+    }
+
+    /// Entries from first to last, or backwards with `rev`.
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = ListpackEntry<'_>> + '_ {
+        self.nodes.iter().flat_map(|node| node.iter())
+    }
+
+    /// `count` entries from `index` on.
+    pub(crate) fn range(&self, index: usize, count: usize) -> impl Iterator<Item = Bytes> + '_ {
+        let (first, skip) = self.locate(index).unwrap_or((self.nodes.len(), 0));
+        self.nodes.range(first..).flat_map(|node| node.iter()).skip(skip).take(count).map(ListpackEntry::to_bytes)
+    }
+}
+
+/// Whether `node` would stay within `fill` with an entry of `extra` more
+/// bytes, Redis' `_quicklistNodeAllowInsert`.
+fn allow_insert(fill: i32, node: &Listpack, extra: usize) -> bool {
+    let new_size = node.bytes() + extra + ENTRY_OVERHEAD;
+    (fill < 0 || node.len() < fill.max(1) as usize) && new_size <= size_limit(fill)
+}
+
+/// Largest size in bytes of a node for `fill`.
+fn size_limit(fill: i32) -> usize {
+    if fill >= 0 {
+        SIZE_SAFETY_LIMIT
+    } else {
+        OPTIMIZATION_LEVEL[(fill.unsigned_abs() as usize - 1).min(OPTIMIZATION_LEVEL.len() - 1)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(list: &Quicklist) -> Vec<Bytes> {
+        list.iter().map(ListpackEntry::to_bytes).collect()
+    }
+
+    #[test]
+    fn nodes_respect_the_fill() {
+        let mut list = Quicklist::new(3);
+        for i in 0..10 {
+            list.push_back(i.to_string().as_bytes());
+        }
+        assert_eq!((list.len(), list.nodes.len()), (10, 4));
+        list.insert(4, b"x");
+        list.push_front(b"y");
+        assert_eq!(entries(&list), ["y", "0", "1", "2", "3", "x", "4", "5", "6", "7", "8", "9"]);
+        assert_eq!(list.get(5).unwrap(), "x");
+        assert_eq!(list.range(4, 3).collect::<Vec<_>>(), ["3", "x", "4"]);
+
+        let mut list = Quicklist::new(-1);
+        for _ in 0..100 {
+            list.push_front(&[b'a'; 100]);
+        }
+        assert!(list.nodes.len() > 1 && list.nodes.iter().all(|node| node.bytes() <= 4096));
+    }
+
+    #[test]
+    fn set_splits_only_oversized_nodes() {
+        let mut list = Quicklist::new(3);
+        for i in 0..6 {
+            list.push_back(i.to_string().as_bytes());
+        }
+        assert_eq!(list.nodes.len(), 2);
+        assert!(list.set(1, b"x") && list.set(4, b"y"));
+        assert_eq!(list.nodes.len(), 2);
+        assert_eq!(entries(&list), ["0", "x", "2", "3", "y", "5"]);
+
+        assert!(list.set(0, &[b'a'; SIZE_SAFETY_LIMIT]));
+        assert_eq!(list.nodes.len(), 3);
+        assert_eq!(list.len(), 6);
+    }
+
+    #[test]
+    fn removals_drop_empty_nodes() {
+        let mut list = Quicklist::new(2);
+        for value in ["a", "b", "a", "c", "a", "a"] {
+            list.push_back(value.as_bytes());
+        }
+        assert_eq!(list.remove_matching(b"a", -2), 2);
+        assert_eq!(entries(&list), ["a", "b", "a", "c"]);
+        list.remove_range(1, 2);
+        assert_eq!(entries(&list), ["a", "c"]);
+        assert_eq!(list.nodes.len(), 2);
+        assert_eq!(list.pop_back().unwrap(), "c");
+        assert_eq!(list.pop_front().unwrap(), "a");
+        assert!(list.is_empty() && list.nodes.is_empty());
+    }
+}
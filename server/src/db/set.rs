@@ -0,0 +1,254 @@
+use bytes::Bytes;
+use crate::db::dict::Dict;
+use crate::db::intset::Intset;
+use crate::db::listpack::Listpack;
+use crate::util::{random, string2ll};
+
+/// Largest number of members of an intset, whatever `set-max-intset-entries`
+/// says, Redis' `intsetMaxEntries`.
+const INTSET_MAX_ENTRIES: usize = 1 << 30;
+
+#[derive(Debug, Clone)]
+enum Encoding {
+    Intset(Intset),
+    /// Members in the order they were added.
+    Listpack(Listpack),
+    Dict(Dict<Bytes, ()>),
+}
+
+/// The value of a set, after Redis' `t_set.c`.
+///
+/// A set of integers only is kept sorted in an intset, up to
+/// `max_intset_entries` members. Other small sets are packed in a listpack,
+/// searched linearly, up to `max_listpack_entries` members no longer than
+/// `max_listpack_value` bytes. Past those limits the set is converted to a
+/// `Dict` for good, as a hash is.
+#[derive(Debug, Clone)]
+pub(crate) struct Set {
+    encoding: Encoding,
+    max_intset_entries: usize,
+    max_listpack_entries: usize,
+    max_listpack_value: usize,
+}
+
+impl Set {
+    /// Create an empty set, encoded as an intset.
+    pub(crate) fn new(max_intset_entries: usize, max_listpack_entries: usize, max_listpack_value: usize) -> Self {
+        Self {
+            encoding: Encoding::Intset(Intset::new()),
+            max_intset_entries: max_intset_entries.min(INTSET_MAX_ENTRIES),
+            max_listpack_entries,
+            max_listpack_value,
+        }
+    }
+
+    /// Pick the encoding of an empty set about to receive `size_hint`
+    /// members, the first being `member`, Redis' `setTypeCreate`.
+    pub(crate) fn presize(&mut self, member: &[u8], size_hint: usize) {
+        debug_assert!(self.is_empty());
+        self.encoding = if string2ll(member).is_some() && size_hint <= self.max_intset_entries {
+            Encoding::Intset(Intset::new())
+        } else if size_hint <= self.max_listpack_entries {
+            Encoding::Listpack(Listpack::new())
+        } else {
+            Encoding::Dict(Dict::new())
+        };
+    }
+
+    /// Number of members.
+    pub(crate) fn len(&self) -> usize {
+        match &self.encoding {
+            Encoding::Intset(intset) => intset.len(),
+            Encoding::Listpack(listpack) => listpack.len(),
+            Encoding::Dict(dict) => dict.len(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn contains(&self, member: &[u8]) -> bool {
+        match &self.encoding {
+            Encoding::Intset(intset) => string2ll(member).is_some_and(|n| intset.contains(n)),
+            Encoding::Listpack(listpack) => listpack.iter().any(|entry| entry.eq_bytes(member)),
+            Encoding::Dict(dict) => dict.contains_key(member),
+        }
+    }
+
+    /// Add `member`. Returns `false` if it was already there.
+    pub(crate) fn insert(&mut self, member: Bytes) -> bool {
+        match &mut self.encoding {
+            Encoding::Intset(intset) => match string2ll(&member) {
+                Some(n) => {
+                    if !intset.insert(n) {
+                        return false;
+                    }
+                    if intset.len() > self.max_intset_entries {
+                        self.convert_to_dict();
+                    }
+                    true
+                }
+                None => {
+                    // The members of the intset would be no longer than the
+                    // longest of its bounds in the listpack.
+                    let longest = [intset.min(), intset.max()]
+                        .into_iter()
+                        .flatten()
+                        .map(|n| n.to_string().len())
+                        .max()
+                        .unwrap_or(0);
+                    if intset.len() < self.max_listpack_entries
+                        && member.len() <= self.max_listpack_value
+                        && longest <= self.max_listpack_value
+                    {
+                        let mut listpack = Listpack::new();
+                        for n in intset.iter() {
+                            listpack.push_back(n.to_string().as_bytes());
+                        }
+                        listpack.push_back(&member);
+                        self.encoding = Encoding::Listpack(listpack);
+                    } else {
+                        self.convert_to_dict();
+                        self.insert(member);
+                    }
+                    true
+                }
+            },
+            Encoding::Listpack(listpack) => {
+                if listpack.iter().any(|entry| entry.eq_bytes(&member)) {
+                    return false;
+                }
+                if listpack.len() < self.max_listpack_entries && member.len() <= self.max_listpack_value {
+                    listpack.push_back(&member);
+                } else {
+                    self.convert_to_dict();
+                    self.insert(member);
+                }
+                true
+            }
+            Encoding::Dict(dict) => dict.insert(member, ()).is_none(),
+        }
+    }
+
+    /// Remove `member`. Returns whether it was there.
+    pub(crate) fn remove(&mut self, member: &[u8]) -> bool {
+        match &mut self.encoding {
+            Encoding::Intset(intset) => string2ll(member).is_some_and(|n| intset.remove(n)),
+            Encoding::Listpack(listpack) => match listpack.iter().position(|entry| entry.eq_bytes(member)) {
+                Some(index) => {
+                    listpack.remove(index);
+                    true
+                }
+                None => false,
+            },
+            Encoding::Dict(dict) => dict.remove(member).is_some(),
+        }
+    }
+
+    fn convert_to_dict(&mut self) {
+        let mut dict = Dict::new();
+        for member in self.iter() {
+            dict.insert(member, ());
+        }
+        self.encoding = Encoding::Dict(dict);
+    }
+
+    /// Every member: in ascending order for an intset, in no particular
+    /// order otherwise.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Bytes> + '_ {
+        let (intset, listpack, dict) = match &self.encoding {
+            Encoding::Intset(intset) => (Some(intset.iter().map(|n| Bytes::from(n.to_string()))), None, None),
+            Encoding::Listpack(listpack) => (None, Some(listpack.iter().map(|entry| entry.to_bytes())), None),
+            Encoding::Dict(dict) => (None, None, Some(dict.iter().map(|(member, _)| member.clone()))),
+        };
+        intset.into_iter().flatten().chain(listpack.into_iter().flatten()).chain(dict.into_iter().flatten())
+    }
+
+    pub(crate) fn random_member(&self) -> Option<Bytes> {
+        match &self.encoding {
+            Encoding::Intset(intset) => intset.random().map(|n| Bytes::from(n.to_string())),
+            Encoding::Listpack(listpack) => {
+                listpack.get(random() as usize % listpack.len().max(1)).map(|entry| entry.to_bytes())
+            }
+            Encoding::Dict(dict) => dict.random_entry().map(|(member, _)| member.clone()),
+        }
+    }
+
+    /// Remove and return a random member.
+    pub(crate) fn pop_random(&mut self) -> Option<Bytes> {
+        let member = self.random_member()?;
+        self.remove(&member);
+        Some(member)
+    }
+
+    /// Visit the members of a bucket and return the next cursor, as
+    /// `Dict::scan`. An intset or a listpack is visited at once, with 0 as
+    /// next cursor.
+    pub(crate) fn scan<F>(&self, cursor: usize, mut f: F) -> usize
+    where
+        F: FnMut(&Bytes),
+    {
+        match &self.encoding {
+            Encoding::Dict(dict) => dict.scan(cursor, |member, _| f(member)),
+            _ => {
+                for member in self.iter() {
+                    f(&member);
+                }
+                0
+            }
+        }
+    }
+
+    /// Name of the encoding, as replied by `OBJECT ENCODING`.
+    pub(crate) fn encoding_name(&self) -> &'static str {
+        match &self.encoding {
+            Encoding::Intset(_) => "intset",
+            Encoding::Listpack(_) => "listpack",
+            Encoding::Dict(_) => "hashtable",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn members(set: &Set) -> Vec<Bytes> {
+        set.iter().collect()
+    }
+
+    #[test]
+    fn encodings_follow_the_limits() {
+        let mut set = Set::new(3, 3, 4);
+        for member in ["3", "-1", "2", "2"] {
+            set.insert(Bytes::from(member));
+        }
+        assert_eq!((set.encoding_name(), members(&set)), ("intset", vec![Bytes::from("-1"), Bytes::from("2"), Bytes::from("3")]));
+        assert!(set.contains(b"2") && !set.contains(b"02") && !set.contains(b"a"));
+
+        // A string turns an intset into a listpack, keeping its members.
+        assert!(set.remove(b"3"));
+        assert!(set.insert(Bytes::from("a")));
+        assert_eq!((set.encoding_name(), set.len()), ("listpack", 3));
+        assert!(set.contains(b"2") && set.contains(b"a"));
+        assert!(!set.insert(Bytes::from("-1")));
+
+        assert!(set.insert(Bytes::from("b")));
+        assert_eq!((set.encoding_name(), set.len()), ("hashtable", 4));
+        assert!(set.remove(b"-1") && set.contains(b"b"));
+
+        // Too many integers go straight to a hash table.
+        let mut set = Set::new(2, 8, 64);
+        for member in ["1", "2", "3"] {
+            set.insert(Bytes::from(member));
+        }
+        assert_eq!(set.encoding_name(), "hashtable");
+
+        let mut set = Set::new(512, 2, 64);
+        set.presize(b"a", 3);
+        assert_eq!(set.encoding_name(), "hashtable");
+        set.presize(b"1", 3);
+        assert_eq!(set.encoding_name(), "intset");
+    }
+}
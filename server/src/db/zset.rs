@@ -0,0 +1,98 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use ahash::AHashMap;
+use bytes::Bytes;
+
+/// Score of a member, ordered as Redis orders them. Scores are never NaN.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Score(f64);
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// The value of a sorted set, after the `skiplist` encoding of Redis'
+/// `t_zset.c`.
+///
+/// A map gives the score of a member, while the members ordered by score,
+/// then lexicographically, are kept in a `BTreeSet` standing for the skip
+/// list.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ZSet {
+    scores: AHashMap<Bytes, f64>,
+    ranking: BTreeSet<(Score, Bytes)>,
+}
+
+impl ZSet {
+    /// Number of members.
+    pub(crate) fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub(crate) fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    /// Add `member` with `score`, or update its score. Returns the previous
+    /// score.
+    pub(crate) fn insert(&mut self, member: Bytes, score: f64) -> Option<f64> {
+        // -0 and 0 are the same score.
+        let score = score + 0.0;
+        let old = self.scores.insert(member.clone(), score);
+        if let Some(old) = old {
+            self.ranking.remove(&(Score(old), member.clone()));
+        }
+        self.ranking.insert((Score(score), member));
+        old
+    }
+
+    /// Remove and return the member with the lowest score.
+    pub(crate) fn pop_min(&mut self) -> Option<(Bytes, f64)> {
+        let (score, member) = self.ranking.pop_first()?;
+        self.scores.remove(&member);
+        Some((member, score.0))
+    }
+
+    /// Remove and return the member with the highest score.
+    pub(crate) fn pop_max(&mut self) -> Option<(Bytes, f64)> {
+        let (score, member) = self.ranking.pop_last()?;
+        self.scores.remove(&member);
+        Some((member, score.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn members_are_ranked_by_score_then_name() {
+        let mut zset = ZSet::default();
+        zset.insert(Bytes::from("b"), 1.0);
+        zset.insert(Bytes::from("a"), 1.0);
+        zset.insert(Bytes::from("c"), -0.0);
+        assert_eq!(zset.insert(Bytes::from("c"), 2.0), Some(0.0));
+        zset.insert(Bytes::from("d"), f64::NEG_INFINITY);
+
+        assert_eq!(zset.len(), 4);
+        assert_eq!(zset.pop_min(), Some((Bytes::from("d"), f64::NEG_INFINITY)));
+        assert_eq!(zset.pop_min(), Some((Bytes::from("a"), 1.0)));
+        assert_eq!(zset.pop_max(), Some((Bytes::from("c"), 2.0)));
+        assert_eq!(zset.score(b"b"), Some(1.0));
+        assert_eq!(zset.score(b"c"), None);
+    }
+}
@@ -0,0 +1,140 @@
+//! Errors of commands.
+//!
+//! A command that fails replies with a RESP error whose first word is an
+//! error code, such as `ERR` or `WRONGTYPE`, that clients match on. Every
+//! such error is a `RedisError`; only a failed connection is not replied.
+
+use std::{fmt, io};
+use resp::{parse::ParseError, protocol::Protocol};
+
+// Some codes belong to features still to come, authentication, cluster,
+// scripting and transactions.
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) enum RedisError {
+    /// Generic error, `ERR`, with its message.
+    Err(String),
+    /// The command was given the wrong number of arguments.
+    WrongArity(String),
+    /// The key holds a value of another type.
+    WrongType,
+    /// The connection must authenticate first.
+    NoAuth,
+    /// The user may not run the command, or access its keys or channels.
+    NoPerm(String),
+    /// The command would use memory beyond `maxmemory`.
+    Oom,
+    /// Writes are refused by a read only replica.
+    ReadOnly,
+    /// The hash slot is served by another node of the cluster.
+    Moved { slot: u16, address: String },
+    /// The hash slot is being migrated; ask the other node once.
+    Ask { slot: u16, address: String },
+    /// A script is running.
+    Busy,
+    /// A transaction was discarded because of previous errors.
+    ExecAbort,
+    /// `EVALSHA` was given an unknown script.
+    NoScript,
+    /// An error with a code of its own, such as `NOPROTO` or `WRONGPASS`,
+    /// kept as the whole line.
+    Custom(String),
+    /// The connection failed. Nothing can be replied and it must be closed.
+    Io(io::Error),
+}
+
+/// Result of a command.
+pub(crate) type Result<T> = std::result::Result<T, RedisError>;
+
+impl RedisError {
+    /// `ERR syntax error`, replied on unknown or conflicting options.
+    pub(crate) fn syntax() -> RedisError {
+        RedisError::Err("syntax error".to_string())
+    }
+
+    /// Replied when an argument or a value is not a 64 bit integer.
+    pub(crate) fn not_integer() -> RedisError {
+        RedisError::Err("value is not an integer or out of range".to_string())
+    }
+
+    /// Replied when an argument or a value is not a valid float.
+    pub(crate) fn not_float() -> RedisError {
+        RedisError::Err("value is not a valid float".to_string())
+    }
+}
+
+impl fmt::Display for RedisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedisError::Err(msg) => write!(f, "ERR {}", msg),
+            RedisError::WrongArity(command) => write!(f, "ERR wrong number of arguments for '{}' command", command),
+            RedisError::WrongType => "WRONGTYPE Operation against a key holding the wrong kind of value".fmt(f),
+            RedisError::NoAuth => "NOAUTH Authentication required.".fmt(f),
+            RedisError::NoPerm(msg) => write!(f, "NOPERM {}", msg),
+            RedisError::Oom => "OOM command not allowed when used memory > 'maxmemory'.".fmt(f),
+            RedisError::ReadOnly => "READONLY You can't write against a read only replica.".fmt(f),
+            RedisError::Moved { slot, address } => write!(f, "MOVED {} {}", slot, address),
+            RedisError::Ask { slot, address } => write!(f, "ASK {} {}", slot, address),
+            RedisError::Busy => {
+                "BUSY Redis is busy running a script. You can only call SCRIPT KILL or SHUTDOWN NOSAVE.".fmt(f)
+            }
+            RedisError::ExecAbort => "EXECABORT Transaction discarded because of previous errors.".fmt(f),
+            RedisError::NoScript => "NOSCRIPT No matching script. Please use EVAL.".fmt(f),
+            RedisError::Custom(line) => line.fmt(f),
+            RedisError::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for RedisError {}
+
+/// The error reply.
+impl From<RedisError> for Protocol {
+    fn from(err: RedisError) -> Protocol {
+        Protocol::Error(err.to_string())
+    }
+}
+
+impl From<io::Error> for RedisError {
+    fn from(err: io::Error) -> RedisError {
+        RedisError::Io(err)
+    }
+}
+
+/// Errors of the argument parser. Running out of arguments means the
+/// command was given too few; the caller fills in the command name.
+impl From<ParseError> for RedisError {
+    fn from(err: ParseError) -> RedisError {
+        match err {
+            ParseError::EndOfStream => RedisError::WrongArity(String::new()),
+            ParseError::Other(err) => {
+                let msg = err.to_string();
+                match msg.strip_prefix("ERR ") {
+                    Some(msg) => RedisError::Err(msg.to_string()),
+                    None => RedisError::Err(msg),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replies_carry_the_redis_code() {
+        assert_eq!(RedisError::syntax().to_string(), "ERR syntax error");
+        assert_eq!(RedisError::WrongArity("get".to_string()).to_string(), "ERR wrong number of arguments for 'get' command");
+        assert!(RedisError::WrongType.to_string().starts_with("WRONGTYPE "));
+        let moved = RedisError::Moved { slot: 3999, address: "127.0.0.1:6381".to_string() };
+        assert!(matches!(Protocol::from(moved), Protocol::Error(line) if line == "MOVED 3999 127.0.0.1:6381"));
+    }
+
+    #[test]
+    fn parse_errors_are_not_prefixed_twice() {
+        let err = RedisError::from(ParseError::from("ERR value is not a valid float"));
+        assert_eq!(err.to_string(), "ERR value is not a valid float");
+        assert!(matches!(RedisError::from(ParseError::EndOfStream), RedisError::WrongArity(_)));
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::io::ErrorKind;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use ahash::AHashMap;
+use core::lifecycle::construct::Constructive;
+use core::lifecycle::lifecycle::{ConstructiveLiteLifecycle, LiteLifecycle};
+use crate::eventloop::event::EventID;
+use crate::eventloop::time_event::{EventFinalizerProc, TimeEventManager, TimeProc, AE_NOMORE};
+
+struct TimeEvent {
+    time_proc: Box<TimeProc>,
+    finalizer: Option<Box<EventFinalizerProc>>,
+}
+
+#[derive(Default)]
+struct TimeEventTable {
+    next_id: EventID,
+
+    // Pending events ordered by deadline. The id breaks ties so that events
+    // due at the same instant run in creation order.
+    events: BTreeMap<(Instant, EventID), TimeEvent>,
+
+    // Deadline of every pending event, to find it back by id.
+    deadlines: AHashMap<EventID, Instant>,
+
+    // Event whose `TimeProc` is running, and whether it was deleted meanwhile,
+    // in which case it is not rescheduled once the callback returns.
+    running: Option<EventID>,
+    running_deleted: bool,
+}
+
+/// `TimeEventManager` keeping the timers in a `BTreeMap` ordered by deadline,
+/// so both the nearest timer and the due ones are found without scanning all
+/// of them, unlike the unsorted list Redis walks in `processTimeEvents`.
+///
+/// The manager is a handle: clones share the same timers. The event loop
+/// processes them while the server and the commands it runs register new
+/// ones. No lock is held while a `TimeProc` runs, so a callback is free to
+/// create or delete time events itself.
+#[derive(Clone, Default)]
+pub(crate) struct BTreeTimeEventManager {
+    table: Arc<Mutex<TimeEventTable>>,
+}
+
+impl fmt::Debug for BTreeTimeEventManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BTreeTimeEventManager")
+            .field("events", &self.table.lock().unwrap().events.len())
+            .finish()
+    }
+}
+
+impl BTreeTimeEventManager {
+    /// Take the earliest event due at `now`, ignoring the ones created after
+    /// `max_id`: an event registered by a callback never runs in the same
+    /// round, as in Redis.
+    fn pop_due_event(&self, now: Instant, max_id: EventID) -> Option<(EventID, TimeEvent)> {
+        let mut table = self.table.lock().unwrap();
+        let key = table
+            .events
+            .range(..=(now, EventID::MAX))
+            .map(|(key, _)| *key)
+            .find(|(_, id)| *id <= max_id)?;
+
+        let event = table.events.remove(&key)?;
+        table.deadlines.remove(&key.1);
+        table.running = Some(key.1);
+        table.running_deleted = false;
+        Some((key.1, event))
+    }
+}
+
+impl Constructive for BTreeTimeEventManager {
+    type Instance = BTreeTimeEventManager;
+
+    fn new() -> Self::Instance {
+        Self::default()
+    }
+}
+
+impl LiteLifecycle for BTreeTimeEventManager {}
+
+impl ConstructiveLiteLifecycle for BTreeTimeEventManager {}
+
+impl TimeEventManager for BTreeTimeEventManager {
+    fn create_time_event(&mut self, duration: Duration, f: Box<TimeProc>, finalizer: Option<Box<EventFinalizerProc>>)
+        -> io::Result<EventID> {
+        let mut table = self.table.lock().unwrap();
+        let id = table.next_id;
+        table.next_id += 1;
+
+        let when = Instant::now() + duration;
+        table.events.insert((when, id), TimeEvent { time_proc: f, finalizer });
+        table.deadlines.insert(id, when);
+        Ok(id)
+    }
+
+    fn delete_time_event(&mut self, event_id: EventID) -> io::Result<()> {
+        let mut table = self.table.lock().unwrap();
+        let event = match table.deadlines.remove(&event_id) {
+            Some(when) => table.events.remove(&(when, event_id)),
+            // Deleting the running event only keeps it from being rescheduled,
+            // its finalizer runs once the callback returns.
+            None if table.running == Some(event_id) && !table.running_deleted => {
+                table.running_deleted = true;
+                None
+            }
+            None => return Err(io::Error::new(ErrorKind::NotFound, "no such time event")),
+        };
+        drop(table);
+
+        if let Some(finalizer) = event.and_then(|event| event.finalizer) {
+            finalizer(event_id);
+        }
+        Ok(())
+    }
+
+    fn process_time_events(&mut self) -> io::Result<i64> {
+        let max_id = self.table.lock().unwrap().next_id - 1;
+        let mut processed = 0;
+
+        // `now` is sampled once, so a callback that takes long cannot keep
+        // this loop busy with events falling due meanwhile.
+        let now = Instant::now();
+        while let Some((id, mut event)) = self.pop_due_event(now, max_id) {
+            let retval = (event.time_proc)(id);
+            processed += 1;
+
+            let mut table = self.table.lock().unwrap();
+            let deleted = table.running_deleted;
+            table.running = None;
+            if retval == AE_NOMORE || deleted {
+                drop(table);
+                if let Some(finalizer) = event.finalizer {
+                    finalizer(id);
+                }
+            } else {
+                let when = Instant::now() + Duration::from_millis(retval.max(0) as u64);
+                table.events.insert((when, id), event);
+                table.deadlines.insert(id, when);
+            }
+        }
+
+        Ok(processed)
+    }
+
+    fn until_earliest_timer(&self) -> Option<Duration> {
+        let table = self.table.lock().unwrap();
+        table
+            .events
+            .keys()
+            .next()
+            .map(|(when, _)| when.saturating_duration_since(Instant::now()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    #[test]
+    fn runs_due_events_in_deadline_order() {
+        let mut manager = BTreeTimeEventManager::new();
+        let order = Arc::new(Mutex::new(vec![]));
+
+        for (delay, tag) in [(20, 2), (0, 0), (10, 1)] {
+            let order = order.clone();
+            manager
+                .create_time_event(Duration::from_millis(delay), Box::new(move |_| {
+                    order.lock().unwrap().push(tag);
+                    AE_NOMORE
+                }), None)
+                .unwrap();
+        }
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert_eq!(manager.process_time_events().unwrap(), 3);
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+        assert_eq!(manager.until_earliest_timer(), None);
+    }
+
+    #[test]
+    fn repeating_event_is_rescheduled_until_deleted() {
+        let mut manager = BTreeTimeEventManager::new();
+        let runs = Arc::new(AtomicI64::new(0));
+        let finalized = Arc::new(AtomicI64::new(0));
+
+        let (r, f) = (runs.clone(), finalized.clone());
+        let id = manager
+            .create_time_event(Duration::ZERO, Box::new(move |_| {
+                r.fetch_add(1, Ordering::SeqCst);
+                0
+            }), Some(Box::new(move |_| {
+                f.fetch_add(1, Ordering::SeqCst);
+            })))
+            .unwrap();
+
+        manager.process_time_events().unwrap();
+        manager.process_time_events().unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert!(manager.until_earliest_timer().is_some());
+
+        manager.delete_time_event(id).unwrap();
+        assert_eq!(finalized.load(Ordering::SeqCst), 1);
+        assert!(manager.delete_time_event(id).is_err());
+        assert_eq!(manager.process_time_events().unwrap(), 0);
+    }
+
+    #[test]
+    fn event_deleting_itself_is_finalized_once() {
+        let mut manager = BTreeTimeEventManager::new();
+        let finalized = Arc::new(AtomicI64::new(0));
+
+        let mut handle = manager.clone();
+        let f = finalized.clone();
+        manager
+            .create_time_event(Duration::ZERO, Box::new(move |id| {
+                handle.delete_time_event(id).unwrap();
+                100
+            }), Some(Box::new(move |_| {
+                f.fetch_add(1, Ordering::SeqCst);
+            })))
+            .unwrap();
+
+        assert_eq!(manager.process_time_events().unwrap(), 1);
+        assert_eq!(finalized.load(Ordering::SeqCst), 1);
+        assert_eq!(manager.until_earliest_timer(), None);
+    }
+}
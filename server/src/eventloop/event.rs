@@ -1,4 +1,4 @@
 
 
 
-pub(crate) type EventID = i64;
\ No newline at end of file
+pub(crate) type EventID = i64;
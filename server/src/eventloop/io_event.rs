@@ -6,8 +6,14 @@ pub(crate) trait IoEventManager {
 
 
     // There's no actual process io event in Redis, there's only process_events mix of process_time_events
-    // and logically process_io_events
-    fn process_io_events(&mut self, timeout: Option<Duration>) -> io::Result<i64>;
+    // and logically process_io_events. Waiting and dispatching are kept apart so that the event loop can
+    // run its after-sleep hook in between, as `aeProcessEvents` does.
+
+    // Wait at most `timeout` for file events, `None` waiting forever. Returns the number of fired events.
+    fn poll_io_events(&mut self, timeout: Option<Duration>) -> io::Result<usize>;
+
+    // Handle the events fired by the last `poll_io_events`.
+    fn process_fired_io_events(&mut self) -> io::Result<i64>;
 
 
 }
@@ -6,7 +6,8 @@ use std::time::Duration;
 use mio::{Events, Interest, Poll, Token};
 use mio::event::Event;
 use mio::net::TcpListener;
-use crate::client::{Client, ClientManager};
+use crate::blocked::{handle_clients_blocked_on_keys, unblock_client};
+use crate::client::ClientManager;
 use crate::eventloop::io_event::IoEventManager;
 use crate::server::RedisServer;
 
@@ -21,12 +22,13 @@ pub(crate) struct MioEventManager {
 
     client_manager: Arc<Mutex<ClientManager>>,
 
+    redis_server: RedisServer,
 }
 
 impl MioEventManager {
     const ACCEPTOR: Token = Token(0);
 
-    const EVENTS_SIZE: usize = 1024;
+    pub(crate) const EVENTS_SIZE: usize = 1024;
 
     pub(crate) fn new(redis_server: RedisServer) -> Self {
         let poll = Poll::new().unwrap();
@@ -42,7 +44,8 @@ impl MioEventManager {
             events: Arc::new(Mutex::new(Events::with_capacity(Self::EVENTS_SIZE))),
             binder: Arc::new(Mutex::new(server)),
             id_generator: AtomicUsize::new(1),
-            client_manager: Arc::new(Mutex::new(redis_server.client_manager()))
+            client_manager: Arc::new(Mutex::new(redis_server.client_manager())),
+            redis_server,
         }
     }
 
@@ -50,62 +53,164 @@ impl MioEventManager {
         event.token() == Self::ACCEPTOR
     }
 
-    fn accept_new_client(&self) -> () {
-        if let Ok((mut connection, address)) = self.binder.lock().unwrap().accept() {
-            println!("Accepted connection from: {}", address);
-            let fd = self.id_generator.fetch_add(1, Ordering::Relaxed);
-            self.mio_poll.registry().register(
-                &mut connection,
-                Token(fd),
-                Interest::READABLE, ).expect("TODO: panic message");
-            self.client_manager.lock().unwrap().create_client(fd, connection, address);
-        };
-
+    fn accept_new_client(&self) {
+        // The listener is edge-triggered as well, so keep accepting until the
+        // backlog is empty. Connections queued behind the first one would
+        // otherwise wait for the next incoming connection to be noticed.
+        loop {
+            match self.binder.lock().unwrap().accept() {
+                Ok((mut connection, address)) => {
+                    log::debug!("Accepted {}", address);
+                    let fd = self.id_generator.fetch_add(1, Ordering::Relaxed);
+                    self.mio_poll.registry().register(
+                        &mut connection,
+                        Token(fd),
+                        Interest::READABLE, ).expect("TODO: panic message");
+                    self.client_manager.lock().unwrap().create_client(fd, connection, address);
+                    self.redis_server.stats.lock().unwrap().total_connections_received += 1;
+                }
+                Err(ref err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    log::warn!("Accepting client connection: {}", err);
+                    break;
+                }
+            }
+        }
     }
 
-    fn read_for_client(&self, event: &Event) -> () {
-        // event
+    fn read_for_client(&self, event: &Event) {
         let mut binding = self.client_manager.lock().unwrap();
-        let mut client = binding.get_client(event.token().0);
+        let client = binding.get_client(event.token().0);
         match client {
-            None => {unreachable!()}
-            Some(mut c) => {
-                match c.read_from_query() {
-                    Ok(_) => {}
-                    Err(_) => {binding.remove_client(event.token().0)}
+            // The client may have been dropped while handling an earlier
+            // event of the same poll round.
+            None => {}
+            Some(c) => {
+                let mut c = c.lock().unwrap();
+                // Pipelined commands are all served here; any error, including
+                // the peer going away, ends the client.
+                if c.read_from_query(&self.redis_server).is_err() {
+                    drop(c);
+                    self.free_client(&mut binding, event.token().0)
+                } else if c.has_pending_replies() {
+                    binding.add_pending_write(event.token().0)
                 }
             }
         }
+    }
+
+    fn write_for_client(&self, event: &Event) {
+        let mut binding = self.client_manager.lock().unwrap();
+        let Some(client) = binding.get_client(event.token().0) else {
+            return;
+        };
 
+        let result = client.lock().unwrap().write_to_client(&self.redis_server, self.mio_poll.registry());
+        if result.is_err() {
+            self.free_client(&mut binding, event.token().0)
+        }
     }
 
-    fn remove_client(&self, event: &Event) -> () {
+    /// Flush the output buffer of every client that produced replies since
+    /// the last call, mirroring Redis' `handleClientsWithPendingWrites`.
+    ///
+    /// Most replies fit into the socket buffer and are written right here,
+    /// without a round trip through the poller. Clients whose replies could
+    /// not be written in full get writable interest registered and are
+    /// finished from `write_for_client` once their socket drains.
+    ///
+    /// Returns the number of clients processed.
+    pub(crate) fn handle_clients_with_pending_writes(&self) -> io::Result<usize> {
         let mut binding = self.client_manager.lock().unwrap();
-        binding.remove_client(event.token().0)
+        let pending = binding.take_clients_pending_write();
+
+        for &client_id in &pending {
+            let Some(client) = binding.get_client(client_id) else {
+                continue;
+            };
+
+            let result = client.lock().unwrap().write_to_client(&self.redis_server, self.mio_poll.registry());
+            if result.is_err() {
+                self.free_client(&mut binding, client_id);
+            }
+        }
+
+        Ok(pending.len())
+    }
+
+    /// Serve the clients blocked on keys that became ready, then process the
+    /// commands the clients unblocked meanwhile sent while they were blocked,
+    /// Redis' `processUnblockedClients`.
+    ///
+    /// Returns the number of unblocked clients processed.
+    pub(crate) fn handle_blocked_clients(&self) -> io::Result<usize> {
+        handle_clients_blocked_on_keys(&self.redis_server);
+
+        let mut binding = self.client_manager.lock().unwrap();
+        let mut processed = 0;
+        while let Some(client_id) = binding.pop_unblocked_client() {
+            let Some(client) = binding.get_client(client_id) else {
+                continue;
+            };
+
+            let mut client = client.lock().unwrap();
+            if client.process_input_buffer(&self.redis_server).is_err() {
+                drop(client);
+                self.free_client(&mut binding, client_id);
+            } else if client.has_pending_replies() {
+                binding.add_pending_write(client_id);
+            }
+            processed += 1;
+        }
+
+        Ok(processed)
+    }
+
+    fn remove_client(&self, event: &Event) {
+        let mut binding = self.client_manager.lock().unwrap();
+        self.free_client(&mut binding, event.token().0)
+    }
+
+    /// Close a client, first taking it out of the blocked state, Redis'
+    /// `freeClient`.
+    fn free_client(&self, binding: &mut ClientManager, client_id: usize) {
+        if let Some(client) = binding.remove_client(client_id) {
+            unblock_client(&self.redis_server, &mut client.lock().unwrap());
+        }
     }
 }
 
 impl IoEventManager for MioEventManager {
 
-    fn process_io_events(&mut self, timeout: Option<Duration>) -> io::Result<i64> {
+    fn poll_io_events(&mut self, timeout: Option<Duration>) -> io::Result<usize> {
         let mut events = self.events.lock().unwrap();
-        match self.mio_poll.poll(&mut *events, timeout) {
-            Ok(()) => {
-                let mut counter: i64 = 0;
-                for mio_event in events.iter() {
-                    if Self::is_accept_event(mio_event) {
-                        self.accept_new_client();
-                    } else if mio_event.is_readable() {
-                        self.read_for_client(mio_event)
-                    } else if mio_event.is_read_closed() || mio_event.is_write_closed() {
-                        self.remove_client(mio_event);
-                    }
-                    counter += 1;
+        match self.mio_poll.poll(&mut events, timeout) {
+            Ok(()) => Ok(events.iter().count()),
+            // `poll` clears the events before waiting, so nothing fired.
+            Err(ref err) if err.kind() == ErrorKind::Interrupted => Ok(0),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn process_fired_io_events(&mut self) -> io::Result<i64> {
+        let events = self.events.lock().unwrap();
+        let mut counter: i64 = 0;
+        for mio_event in events.iter() {
+            if Self::is_accept_event(mio_event) {
+                self.accept_new_client();
+            } else if mio_event.is_readable() || mio_event.is_writable() {
+                if mio_event.is_readable() {
+                    self.read_for_client(mio_event);
                 }
-                Ok(counter)
+                if mio_event.is_writable() {
+                    self.write_for_client(mio_event);
+                }
+            } else if mio_event.is_read_closed() || mio_event.is_write_closed() {
+                self.remove_client(mio_event);
             }
-            Err(ref err) if err.kind() == ErrorKind::Interrupted => Ok(0),
-            Err(ref err) => panic!("{}: No error here", err),
+            counter += 1;
         }
+        Ok(counter)
     }
 }
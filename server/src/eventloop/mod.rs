@@ -1,7 +1,9 @@
 pub(crate) mod time_event;
+pub(crate) mod btree_time_event_manager;
 pub(crate) mod event;
 pub(crate) mod io_event;
 pub(crate) mod mio_event_manager;
+#[allow(clippy::module_inception)]
 pub(crate) mod eventloop;
 
 
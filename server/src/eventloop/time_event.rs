@@ -3,8 +3,17 @@ use std::time::Duration;
 use crate::eventloop::event::EventID;
 use core::lifecycle::lifecycle::ConstructiveLiteLifecycle;
 
+/// Returned by a `TimeProc` that does not want to run again.
+pub(crate) const AE_NOMORE: i64 = -1;
+
+/// Callback of a time event. It returns the number of milliseconds after which
+/// it wants to be called again, or `AE_NOMORE` to have the event deleted.
+pub(crate) type TimeProc = dyn FnMut(EventID) -> i64 + Send;
+
+/// Called once a time event is gone, either because it returned `AE_NOMORE`
+/// or because it was deleted.
+pub(crate) type EventFinalizerProc = dyn FnOnce(EventID) + Send;
 
-type TimeProc<DATA: Sized + Send> = dyn FnOnce(DATA);
 pub(crate) trait TimeEventManager: ConstructiveLiteLifecycle {
     //Redis code is as
     // ```c
@@ -12,7 +21,7 @@ pub(crate) trait TimeEventManager: ConstructiveLiteLifecycle {
     //         aeTimeProc *proc, void *clientData,
     //         aeEventFinalizerProc *finalizerProc)
     // ```
-    fn create_time_event<DATA: Sized + Send>(&mut self, duration: Duration, f: TimeProc<DATA>)
+    fn create_time_event(&mut self, duration: Duration, f: Box<TimeProc>, finalizer: Option<Box<EventFinalizerProc>>)
         -> io::Result<EventID>;
 
     // int aeDeleteTimeEvent(aeEventLoop *eventLoop, long long id)
@@ -20,4 +29,10 @@ pub(crate) trait TimeEventManager: ConstructiveLiteLifecycle {
 
     // static int processTimeEvents(aeEventLoop *eventLoop)
     fn process_time_events(&mut self) -> io::Result<i64>;
-}
\ No newline at end of file
+
+    // static int64_t usUntilEarliestTimer(aeEventLoop *eventLoop)
+    //
+    // `None` when there is no timer at all, so the caller may sleep until some
+    // file event fires.
+    fn until_earliest_timer(&self) -> Option<Duration>;
+}
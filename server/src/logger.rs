@@ -0,0 +1,97 @@
+//! The server log, after Redis' `serverLog`.
+//!
+//! Messages go through the `log` macros and are written to the standard
+//! output, one line each, with the pid, the role, the time and a symbol for
+//! the level: `.` debug, `-` verbose, `*` notice and `#` warning. Redis'
+//! levels map onto `log`'s as trace, debug, info and warn.
+
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+struct ServerLogger;
+
+static LOGGER: ServerLogger = ServerLogger;
+
+/// Install the server log, keeping the messages at `verbosity` or above.
+pub(crate) fn init(verbosity: LevelFilter) {
+    // Only fails if a logger is already installed, which is then kept.
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(verbosity);
+}
+
+impl Log for ServerLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let symbol = match record.level() {
+            Level::Trace => '.',
+            Level::Debug => '-',
+            Level::Info => '*',
+            Level::Warn | Level::Error => '#',
+        };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        // A closed standard output is no reason to stop serving.
+        let _ = writeln!(
+            std::io::stdout().lock(),
+            "{}:M {} {} {}",
+            std::process::id(),
+            timestamp(now.as_millis() as u64),
+            symbol,
+            record.args()
+        );
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// Format UNIX time in milliseconds as Redis does, `18 Oct 2026
+/// 08:29:09.123`, in UTC.
+fn timestamp(ms: u64) -> String {
+    let secs = ms / 1000;
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+
+    // Civil date from the days since the epoch, counting in eras of 400
+    // years that start on March 1st.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:02} {} {} {:02}:{:02}:{:02}.{:03}",
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(timestamp(0), "01 Jan 1970 00:00:00.000");
+        assert_eq!(timestamp(951_782_400_000), "29 Feb 2000 00:00:00.000");
+        assert_eq!(timestamp(1_792_312_149_123), "18 Oct 2026 08:29:09.123");
+    }
+}
@@ -1,15 +1,36 @@
+use std::process;
 use crate::ae::SingleThreadEventLoop;
+use crate::config::RedisServerConfig;
+use crate::cron::ServerCron;
 use crate::server::RedisServer;
 
 mod ae;
 mod eventloop;
 mod server;
+mod blocked;
 mod client;
 mod command;
+mod config;
 mod connection;
+mod cron;
+mod db;
+mod error;
+mod logger;
+mod util;
+#[cfg(test)]
+mod testing;
 
 fn main() {
-    // SERVER.client_manager;
-    let redis_server = RedisServer::default();
+    let config = RedisServerConfig::load(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    logger::init(config.verbosity);
+    if !config.save_params.is_empty() {
+        let points: Vec<String> = config.save_params.iter().map(|sp| format!("{} {}", sp.seconds, sp.changes)).collect();
+        log::warn!("RDB persistence is not supported, ignoring the save points \"{}\"", points.join(" "));
+    }
+    let redis_server = RedisServer::new(config);
+    ServerCron::start(&redis_server).expect("failed to create the server cron");
     SingleThreadEventLoop::new(redis_server).run();
 }
@@ -1,29 +1,124 @@
-use std::sync::Arc;
+use std::sync::atomic::AtomicU32;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
 use crate::client::ClientManager;
+use crate::config::RedisServerConfig;
+use crate::db::keyspace::Keyspace;
+use crate::eventloop::btree_time_event_manager::BTreeTimeEventManager;
+
+/// Redis version reported to clients.
+pub(crate) const REDIS_VERSION: &str = "7.4.0";
 
 #[derive(Debug, Clone)]
 pub(crate) struct RedisServer {
     pub(crate) client_manager: ClientManager,
-    pub(crate) config: Arc<RedisServerConfig>
+    pub(crate) config: Arc<RedisServerConfig>,
+    pub(crate) keyspace: Keyspace,
+    pub(crate) stats: Arc<Mutex<Stats>>,
+    pub(crate) persistence: Arc<Mutex<Persistence>>,
+    pub(crate) start_time: Instant,
+    pub(crate) time_event_manager: BTreeTimeEventManager,
+    /// Frequency the server cron currently runs at. It differs from the
+    /// configured one when `dynamic-hz` raised it to cope with many clients.
+    pub(crate) hz: Arc<AtomicU32>,
 }
 
 impl Default for RedisServer {
     fn default() -> Self {
-        Self {
-            client_manager: ClientManager::default(),
-            config: Arc::new(RedisServerConfig{port: 6379})
-        }
+        Self::new(RedisServerConfig::default())
     }
 }
 
 impl RedisServer {
+    pub(crate) fn new(config: RedisServerConfig) -> Self {
+        Self {
+            client_manager: ClientManager::new(config.client_output_buffer_limits, config.request_limits()),
+            hz: Arc::new(AtomicU32::new(config.hz)),
+            keyspace: Keyspace::new(config.databases),
+            config: Arc::new(config),
+            stats: Arc::new(Mutex::new(Stats::default())),
+            persistence: Arc::new(Mutex::new(Persistence::default())),
+            start_time: Instant::now(),
+            time_event_manager: BTreeTimeEventManager::default(),
+        }
+    }
+
     pub fn client_manager(&self) -> ClientManager {
         self.client_manager.clone()
     }
+
+    /// Account for `changes` writes to the dataset, Redis' `server.dirty`.
+    pub(crate) fn incr_dirty(&self, changes: u64) {
+        self.persistence.lock().unwrap().dirty += changes;
+    }
 }
 
-#[derive(Debug, Clone)]
-pub(crate) struct RedisServerConfig {
-    pub(crate) port: i32,
+/// Counters reported in the `stats` section of `INFO`.
+#[derive(Debug, Default)]
+pub(crate) struct Stats {
+    pub(crate) total_connections_received: u64,
+    pub(crate) total_commands_processed: u64,
+    pub(crate) net_input_bytes: u64,
+    pub(crate) net_output_bytes: u64,
+    pub(crate) client_output_buffer_limit_disconnections: u64,
+    pub(crate) client_query_buffer_limit_disconnections: u64,
+    /// Estimate of the percentage of keys with a time to live that are
+    /// already expired, from the samples of the active expire cycle.
+    pub(crate) expired_stale_perc: f64,
+    /// Active expire cycles stopped by their time limit.
+    pub(crate) expired_time_cap_reached_count: u64,
+    pub(crate) instantaneous_ops: InstantaneousMetric,
+    pub(crate) instantaneous_net_input: InstantaneousMetric,
+    pub(crate) instantaneous_net_output: InstantaneousMetric,
+}
+
+/// Per-second rate of a counter, averaged over the last samples taken by the
+/// server cron.
+#[derive(Debug, Default)]
+pub(crate) struct InstantaneousMetric {
+    last_sample_time: Option<Instant>,
+    last_sample_count: u64,
+    samples: [u64; InstantaneousMetric::SAMPLES],
+    idx: usize,
+}
+
+impl InstantaneousMetric {
+    const SAMPLES: usize = 16;
 
-}
\ No newline at end of file
+    /// Record the rate at which `current_count` grew since the previous call,
+    /// as Redis' `trackInstantaneousMetric` does.
+    pub(crate) fn track(&mut self, current_count: u64) {
+        let now = Instant::now();
+        if let Some(last_sample_time) = self.last_sample_time {
+            let ms = now.duration_since(last_sample_time).as_millis() as u64;
+            let delta = current_count.saturating_sub(self.last_sample_count);
+            self.samples[self.idx] = (delta * 1000).checked_div(ms).unwrap_or(0);
+            self.idx = (self.idx + 1) % Self::SAMPLES;
+        }
+        self.last_sample_time = Some(now);
+        self.last_sample_count = current_count;
+    }
+
+    /// Average of the recorded samples.
+    pub(crate) fn get(&self) -> u64 {
+        self.samples.iter().sum::<u64>() / Self::SAMPLES as u64
+    }
+}
+
+/// State of the background persistence, reported in the `persistence` section
+/// of `INFO`.
+#[derive(Debug)]
+pub(crate) struct Persistence {
+    /// Changes to the dataset since the last successful save.
+    pub(crate) dirty: u64,
+    pub(crate) lastsave: SystemTime,
+}
+
+impl Default for Persistence {
+    fn default() -> Self {
+        Self {
+            dirty: 0,
+            lastsave: SystemTime::now(),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Run commands end to end in tests: requests are written to a loopback
+//! socket, served by a `Client` as the event loop would, and the raw replies
+//! read back.
+
+use std::io::{Cursor, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+use bytes::{Buf, BytesMut};
+use resp::protocol::Protocol;
+use crate::client::Client;
+use crate::config::RedisServerConfig;
+use crate::server::RedisServer;
+
+pub(crate) struct TestClient {
+    pub(crate) server: RedisServer,
+    client: Client,
+    peer: TcpStream,
+    replies: BytesMut,
+}
+
+impl TestClient {
+    pub(crate) fn new() -> TestClient {
+        Self::with_config(RedisServerConfig::default())
+    }
+
+    pub(crate) fn with_config(config: RedisServerConfig) -> TestClient {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let peer = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        peer.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let (socket, address) = listener.accept().unwrap();
+        socket.set_nonblocking(true).unwrap();
+
+        let client = Client::new(
+            0,
+            mio::net::TcpStream::from_std(socket),
+            address,
+            config.request_limits(),
+            &config.client_output_buffer_limits,
+        );
+        TestClient { server: RedisServer::new(config), client, peer, replies: BytesMut::new() }
+    }
+
+    /// Send a multibulk request made of `args` and return the raw reply.
+    pub(crate) fn call(&mut self, args: &[&str]) -> String {
+        let mut request = format!("*{}\r\n", args.len());
+        for arg in args {
+            request.push_str(&format!("${}\r\n{}\r\n", arg.len(), arg));
+        }
+        self.send(request.as_bytes());
+        self.reply()
+    }
+
+    /// Send `bytes` and serve them. Returns `false` once the client is to be
+    /// closed.
+    pub(crate) fn send(&mut self, bytes: &[u8]) -> bool {
+        self.peer.write_all(bytes).unwrap();
+        let served = self.client.read_from_query(&self.server).is_ok();
+        self.client.connection.flush().unwrap();
+        served
+    }
+
+    /// Next raw reply.
+    pub(crate) fn reply(&mut self) -> String {
+        loop {
+            let mut cursor = Cursor::new(&self.replies[..]);
+            if Protocol::check(&mut cursor).is_ok() {
+                let len = cursor.position() as usize;
+                let reply = String::from_utf8_lossy(&self.replies[..len]).into_owned();
+                self.replies.advance(len);
+                return reply;
+            }
+            let mut chunk = [0; 4096];
+            let n = self.peer.read(&mut chunk).unwrap();
+            assert!(n > 0, "connection closed");
+            self.replies.extend_from_slice(&chunk[..n]);
+        }
+    }
+}
@@ -0,0 +1,517 @@
+//! Helpers shared by the commands, after Redis' `util.c`.
+
+use std::cell::Cell;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current UNIX time in milliseconds, Redis' `mstime`.
+pub(crate) fn mstime() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64
+}
+
+// The argument parser of the `resp` crate needs the same conversions.
+pub(crate) use resp::util::{string2ld, string2ll};
+
+/// Add two floats given as strings, for `INCRBYFLOAT` and `HINCRBYFLOAT`, and
+/// format the sum as Redis' `ld2string` does in `LD_STR_HUMAN` mode, or
+/// `None` if it is not finite.
+///
+/// Redis parses and adds in `long double` precision, then prints the sum
+/// with `%.17Lf`: fixed point, never an exponent, with the trailing zeros
+/// removed. An `f64` is too short to give the same digits, so the
+/// arithmetic of the x86-64 `long double` is reproduced with `LongDouble`.
+/// Both strings must have been accepted by `string2ld`: those not written in
+/// decimal are infinite, and so is their sum.
+pub(crate) fn add_floats(a: &[u8], b: &[u8]) -> Option<String> {
+    let sum = LongDouble::parse(a)?.add(LongDouble::parse(b)?).to_human_string();
+    // Stored values must stay within what `string2ld` accepts.
+    sum.parse::<f64>().ok().filter(|sum| sum.is_finite())?;
+    Some(sum)
+}
+
+/// An x87 extended precision float, the `long double` of x86-64:
+/// `mantissa × 2^exponent` with 64 bits of mantissa, the top one set unless
+/// the value is zero. Results are rounded to nearest, ties to even.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LongDouble {
+    negative: bool,
+    mantissa: u64,
+    exponent: i64,
+}
+
+impl LongDouble {
+    /// Number of digits after the point of the text formatting, `%.17Lf`.
+    const PRECISION: u32 = 17;
+
+    /// The nearest value to `n × 2^exponent`. `inexact` tells that non-zero
+    /// bits below `n` were dropped, to round correctly.
+    fn round(negative: bool, n: BigUint, exponent: i64, inexact: bool) -> LongDouble {
+        let bits = n.bits();
+        if bits == 0 {
+            return LongDouble { negative: false, mantissa: 0, exponent: 0 };
+        }
+        if bits <= 64 {
+            let shift = 64 - bits;
+            return LongDouble { negative, mantissa: n.to_u64() << shift, exponent: exponent - shift as i64 };
+        }
+        let shift = bits - 64;
+        let mut mantissa = n.shr(shift).to_u64();
+        let mut exponent = exponent + shift as i64;
+        let half = n.bit(shift - 1);
+        let below_half = n.low_bits_nonzero(shift - 1) || inexact;
+        if half && (below_half || mantissa & 1 == 1) {
+            mantissa = mantissa.wrapping_add(1);
+            if mantissa == 0 {
+                mantissa = 1 << 63;
+                exponent += 1;
+            }
+        }
+        LongDouble { negative, mantissa, exponent }
+    }
+
+    /// Parse an optional sign, digits with an optional fraction and an
+    /// optional exponent, as `strtold` does.
+    fn parse(s: &[u8]) -> Option<LongDouble> {
+        let (negative, s) = match s.split_first() {
+            Some((b'-', rest)) => (true, rest),
+            Some((b'+', rest)) => (false, rest),
+            _ => (false, s),
+        };
+        let (mantissa, exponent) = match s.iter().position(|&c| c == b'e' || c == b'E') {
+            Some(i) => (&s[..i], std::str::from_utf8(&s[i + 1..]).ok()?.parse::<i64>().ok()?),
+            None => (s, 0),
+        };
+        let (integer, fraction) = match mantissa.iter().position(|&c| c == b'.') {
+            Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+            None => (mantissa, &[][..]),
+        };
+        if (integer.is_empty() && fraction.is_empty())
+            || !integer.iter().chain(fraction).all(u8::is_ascii_digit)
+        {
+            return None;
+        }
+
+        let mut n = BigUint::default();
+        for digit in integer.iter().chain(fraction) {
+            n.mul_add_small(10, u32::from(digit - b'0'));
+        }
+        if n.bits() == 0 {
+            return Some(LongDouble::round(negative, n, 0, false));
+        }
+        let exponent = exponent.checked_sub(i64::try_from(fraction.len()).ok()?)?;
+        // `string2ld` bounds the length of the string, this bounds the
+        // exponent to the same order of magnitude.
+        if exponent.unsigned_abs() > 100_000 {
+            return None;
+        }
+
+        if exponent >= 0 {
+            for _ in 0..exponent {
+                n.mul_add_small(10, 0);
+            }
+            return Some(LongDouble::round(negative, n, 0, false));
+        }
+        // Divide by 10^-exponent, shifted left first so that the quotient
+        // keeps more bits than the mantissa. Truncating at every division
+        // gives the truncated quotient of the whole division, and it is
+        // inexact if any of them is.
+        let shift = 66 + 4 * exponent.unsigned_abs();
+        let mut n = n.shl(shift);
+        let mut inexact = false;
+        let mut remaining = exponent.unsigned_abs();
+        while remaining > 0 {
+            let digits = remaining.min(9);
+            inexact |= n.div_small(10u32.pow(digits as u32)) != 0;
+            remaining -= digits;
+        }
+        Some(LongDouble::round(negative, n, -(shift as i64), inexact))
+    }
+
+    fn add(self, other: LongDouble) -> LongDouble {
+        if other.mantissa == 0 {
+            return self;
+        }
+        if self.mantissa == 0 {
+            return other;
+        }
+        // Exact sum on the smaller exponent, then rounded once.
+        let exponent = self.exponent.min(other.exponent);
+        let a = BigUint::from_u64(self.mantissa).shl((self.exponent - exponent) as u64);
+        let b = BigUint::from_u64(other.mantissa).shl((other.exponent - exponent) as u64);
+        if self.negative == other.negative {
+            LongDouble::round(self.negative, a.add(&b), exponent, false)
+        } else if a >= b {
+            LongDouble::round(self.negative, a.sub(&b), exponent, false)
+        } else {
+            LongDouble::round(other.negative, b.sub(&a), exponent, false)
+        }
+    }
+
+    /// Format as `%.17Lf`, then drop the trailing zeros after the point and
+    /// the point itself if nothing is left after it, and turn `-0` into `0`.
+    fn to_human_string(self) -> String {
+        let n = BigUint::from_u64(self.mantissa);
+        let mut digits = if self.exponent >= 0 {
+            let mut integer = n.shl(self.exponent as u64);
+            for _ in 0..Self::PRECISION {
+                integer.mul_add_small(10, 0);
+            }
+            integer.to_decimal()
+        } else {
+            let mut scaled = n;
+            for _ in 0..Self::PRECISION {
+                scaled.mul_add_small(10, 0);
+            }
+            let shift = self.exponent.unsigned_abs();
+            let mut rounded = scaled.shr(shift);
+            if shift > 0 && scaled.bit(shift - 1) && (scaled.low_bits_nonzero(shift - 1) || rounded.bit(0)) {
+                rounded.mul_add_small(1, 1);
+            }
+            rounded.to_decimal()
+        };
+
+        let precision = Self::PRECISION as usize;
+        if digits.len() <= precision {
+            digits = format!("{}{}", "0".repeat(precision + 1 - digits.len()), digits);
+        }
+        let point = digits.len() - precision;
+        let fraction = digits[point..].trim_end_matches('0');
+        let text = if fraction.is_empty() {
+            digits[..point].to_string()
+        } else {
+            format!("{}.{}", &digits[..point], fraction)
+        };
+        if self.negative && text != "0" {
+            format!("-{}", text)
+        } else {
+            text
+        }
+    }
+}
+
+/// Unsigned integer of any size, least significant limb first and without
+/// leading zero limbs, for the exact arithmetic of `LongDouble`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct BigUint(Vec<u32>);
+
+impl BigUint {
+    fn from_u64(n: u64) -> BigUint {
+        let mut big = BigUint(vec![n as u32, (n >> 32) as u32]);
+        big.trim();
+        big
+    }
+
+    fn trim(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
+    /// Number of significant bits.
+    fn bits(&self) -> u64 {
+        self.0.last().map_or(0, |top| self.0.len() as u64 * 32 - u64::from(top.leading_zeros()))
+    }
+
+    fn bit(&self, i: u64) -> bool {
+        self.0.get((i / 32) as usize).is_some_and(|limb| limb >> (i % 32) & 1 == 1)
+    }
+
+    /// Whether any of the `count` lowest bits is set.
+    fn low_bits_nonzero(&self, count: u64) -> bool {
+        let (limbs, bits) = ((count / 32) as usize, count % 32);
+        self.0.iter().take(limbs).any(|&limb| limb != 0)
+            || (bits > 0 && self.0.get(limbs).is_some_and(|limb| limb & ((1 << bits) - 1) != 0))
+    }
+
+    /// The lowest 64 bits.
+    fn to_u64(&self) -> u64 {
+        let limb = |i: usize| u64::from(self.0.get(i).copied().unwrap_or(0));
+        limb(0) | limb(1) << 32
+    }
+
+    /// `self = self × mul + add`.
+    fn mul_add_small(&mut self, mul: u32, add: u32) {
+        let mut carry = u64::from(add);
+        for limb in &mut self.0 {
+            let product = u64::from(*limb) * u64::from(mul) + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+        if carry > 0 {
+            self.0.push(carry as u32);
+        }
+    }
+
+    /// Divide in place, returning the remainder.
+    fn div_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.0.iter_mut().rev() {
+            let dividend = remainder << 32 | u64::from(*limb);
+            *limb = (dividend / u64::from(divisor)) as u32;
+            remainder = dividend % u64::from(divisor);
+        }
+        self.trim();
+        remainder as u32
+    }
+
+    fn shl(&self, count: u64) -> BigUint {
+        let (limbs, bits) = ((count / 32) as usize, count % 32);
+        let mut result = vec![0; limbs];
+        let mut carry = 0;
+        for &limb in &self.0 {
+            result.push(((u64::from(limb) << bits) as u32) | carry);
+            carry = if bits == 0 { 0 } else { limb >> (32 - bits) };
+        }
+        result.push(carry);
+        let mut result = BigUint(result);
+        result.trim();
+        result
+    }
+
+    fn shr(&self, count: u64) -> BigUint {
+        let (limbs, bits) = ((count / 32) as usize, count % 32);
+        let source = self.0.get(limbs..).unwrap_or_default();
+        let mut result: Vec<u32> = source
+            .iter()
+            .enumerate()
+            .map(|(i, &limb)| {
+                let high = if bits == 0 { 0 } else { source.get(i + 1).map_or(0, |&next| next << (32 - bits)) };
+                limb >> bits | high
+            })
+            .collect();
+        result.truncate(source.len());
+        let mut result = BigUint(result);
+        result.trim();
+        result
+    }
+
+    fn add(&self, other: &BigUint) -> BigUint {
+        let mut result = Vec::with_capacity(self.0.len().max(other.0.len()) + 1);
+        let mut carry = 0u64;
+        for i in 0..self.0.len().max(other.0.len()) {
+            let sum = u64::from(self.0.get(i).copied().unwrap_or(0)) + u64::from(other.0.get(i).copied().unwrap_or(0)) + carry;
+            result.push(sum as u32);
+            carry = sum >> 32;
+        }
+        result.push(carry as u32);
+        let mut result = BigUint(result);
+        result.trim();
+        result
+    }
+
+    /// `self - other`, `other` being no larger.
+    fn sub(&self, other: &BigUint) -> BigUint {
+        let mut result = Vec::with_capacity(self.0.len());
+        let mut borrow = 0i64;
+        for (i, &limb) in self.0.iter().enumerate() {
+            let mut difference = i64::from(limb) - i64::from(other.0.get(i).copied().unwrap_or(0)) - borrow;
+            borrow = i64::from(difference < 0);
+            if difference < 0 {
+                difference += 1 << 32;
+            }
+            result.push(difference as u32);
+        }
+        let mut result = BigUint(result);
+        result.trim();
+        result
+    }
+
+    fn to_decimal(&self) -> String {
+        let mut n = self.clone();
+        let mut chunks = Vec::new();
+        while n.bits() > 0 {
+            chunks.push(n.div_small(1_000_000_000));
+        }
+        match chunks.split_last() {
+            None => "0".to_string(),
+            Some((first, rest)) => {
+                let mut text = first.to_string();
+                for chunk in rest.iter().rev() {
+                    text.push_str(&format!("{:09}", chunk));
+                }
+                text
+            }
+        }
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.len().cmp(&other.0.len()).then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+}
+
+thread_local! {
+    // State of `random`, seeded from the time and the process id as Redis
+    // seeds its generator.
+    static RANDOM_STATE: Cell<u64> = Cell::new(
+        (SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64 ^ std::process::id() as u64)
+            | 1,
+    );
+}
+
+/// Pseudo random number, from a xorshift64* generator. Not suitable for
+/// anything security related.
+pub(crate) fn random() -> u64 {
+    RANDOM_STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    })
+}
+
+/// Glob-style matching of `string` against `pattern`, Redis'
+/// `stringmatchlen`.
+///
+/// `*` matches any sequence, `?` any byte, `[abc]`, `[^abc]` and `[a-z]`
+/// sets of bytes, and `\` escapes the next byte.
+pub(crate) fn string_match(pattern: &[u8], string: &[u8], nocase: bool) -> bool {
+    let mut skip_longer_matches = false;
+    string_match_impl(pattern, string, nocase, &mut skip_longer_matches, 0)
+}
+
+fn string_match_impl(pattern: &[u8], string: &[u8], nocase: bool, skip_longer_matches: &mut bool, nesting: usize) -> bool {
+    // Bound the recursion of patterns such as `a*a*a*...`.
+    if nesting > 1000 {
+        return false;
+    }
+    let eq = |a: u8, b: u8| if nocase { a.eq_ignore_ascii_case(&b) } else { a == b };
+    let (mut p, mut s) = (0, 0);
+
+    while p < pattern.len() && s < string.len() {
+        match pattern[p] {
+            b'*' => {
+                while pattern.get(p + 1) == Some(&b'*') {
+                    p += 1;
+                }
+                if p + 1 == pattern.len() {
+                    return true;
+                }
+                while s < string.len() {
+                    if string_match_impl(&pattern[p + 1..], &string[s..], nocase, skip_longer_matches, nesting + 1) {
+                        return true;
+                    }
+                    // A later `*` already failed on every suffix, matching
+                    // more here cannot help.
+                    if *skip_longer_matches {
+                        return false;
+                    }
+                    s += 1;
+                }
+                *skip_longer_matches = true;
+                return false;
+            }
+            b'?' => s += 1,
+            b'[' => {
+                p += 1;
+                let not = pattern.get(p) == Some(&b'^');
+                if not {
+                    p += 1;
+                }
+                let mut matched = false;
+                loop {
+                    if p >= pattern.len() {
+                        // Unterminated set, the last byte ends the pattern.
+                        p -= 1;
+                        break;
+                    }
+                    if pattern[p] == b'\\' && p + 1 < pattern.len() {
+                        p += 1;
+                        matched |= pattern[p] == string[s];
+                    } else if pattern[p] == b']' {
+                        break;
+                    } else if p + 2 < pattern.len() && pattern[p + 1] == b'-' {
+                        let (mut start, mut end, mut c) = (pattern[p], pattern[p + 2], string[s]);
+                        if start > end {
+                            (start, end) = (end, start);
+                        }
+                        if nocase {
+                            (start, end, c) = (start.to_ascii_lowercase(), end.to_ascii_lowercase(), c.to_ascii_lowercase());
+                        }
+                        p += 2;
+                        matched |= (start..=end).contains(&c);
+                    } else {
+                        matched |= eq(pattern[p], string[s]);
+                    }
+                    p += 1;
+                }
+                if matched == not {
+                    return false;
+                }
+                s += 1;
+            }
+            c => {
+                let c = if c == b'\\' && p + 1 < pattern.len() {
+                    p += 1;
+                    pattern[p]
+                } else {
+                    c
+                };
+                if !eq(c, string[s]) {
+                    return false;
+                }
+                s += 1;
+            }
+        }
+        p += 1;
+        if s == string.len() {
+            while pattern.get(p) == Some(&b'*') {
+                p += 1;
+            }
+            break;
+        }
+    }
+    p == pattern.len() && s == string.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_patterns() {
+        let matches = |pattern: &str, string: &str| string_match(pattern.as_bytes(), string.as_bytes(), false);
+        assert!(matches("*", "anything"));
+        assert!(matches("h?llo", "hello") && !matches("h?llo", "hllo"));
+        assert!(matches("h*llo", "heeeello") && matches("h*llo", "hllo"));
+        assert!(matches("h[ae]llo", "hallo") && !matches("h[ae]llo", "hillo"));
+        assert!(matches("h[^e]llo", "hallo") && !matches("h[^e]llo", "hello"));
+        assert!(matches("h[a-b]llo", "hbllo") && matches("h[b-a]llo", "hallo") && !matches("h[a-b]llo", "hcllo"));
+        assert!(matches("user:\\*", "user:*") && !matches("user:\\*", "user:1"));
+        assert!(matches("a*b*", "axxbyy") && !matches("a*b*c", "axxbyy"));
+        assert!(string_match(b"HELLO", b"hello", true));
+        // Fails fast rather than trying every split of the string.
+        assert!(!matches(&format!("{}b", "a*".repeat(30)), &"a".repeat(60)));
+    }
+
+    #[test]
+    fn float_sums() {
+        let add = |a: &str, b: &str| add_floats(a.as_bytes(), b.as_bytes());
+        assert_eq!(add("0.1", "0.2").as_deref(), Some("0.3"));
+        assert_eq!(add("0.1", "0.7").as_deref(), Some("0.8"));
+        assert_eq!(add("10.5", "-0.5").as_deref(), Some("10"));
+        assert_eq!(add("3.0e3", "+200").as_deref(), Some("3200"));
+        assert_eq!(add("-.3", "0.3").as_deref(), Some("0"));
+        assert_eq!(add("-5", "2.5").as_deref(), Some("-2.5"));
+        // Fixed point, never an exponent, as Redis prints them.
+        assert_eq!(add("1e20", "1").as_deref(), Some("100000000000000000000"));
+        assert_eq!(add("1e17", "0").as_deref(), Some("100000000000000000"));
+        assert_eq!(add("123456789012345678", "0").as_deref(), Some("123456789012345678"));
+        assert_eq!(add("99999999999999999.5", "0").as_deref(), Some("99999999999999999.5"));
+        assert_eq!(add("1e-20", "0").as_deref(), Some("0"));
+        assert_eq!(add("-1e-20", "0").as_deref(), Some("0"));
+        // The digits of a `long double` past its precision show.
+        assert_eq!(add("1234567.1", "0").as_deref(), Some("1234567.10000000000002274"));
+        assert_eq!(add("1.7976931348623157e308", "0").map(|sum| sum.len()), Some(309));
+        assert_eq!(add("1.7e308", "1.7e308"), None);
+        assert_eq!(add("inf", "1"), None);
+    }
+}